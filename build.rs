@@ -3,12 +3,16 @@ use std::path::{Path, PathBuf};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
 
-    println!("cargo:rerun-if-changed=proto/book_ticker.proto");
-
     let proto_files = &[
+        "./proto/attestation.proto",
         "./proto/book_ticker.proto",
+        "./proto/consolidated.proto",
         "./proto/order_book.proto",
+        "./proto/publisher.proto",
         "./proto/trade.proto"];
+    for proto_file in proto_files {
+        println!("cargo:rerun-if-changed={}", proto_file);
+    }
     let proto_include = &["proto"];
 
     tonic_build::configure()
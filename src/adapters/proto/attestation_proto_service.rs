@@ -0,0 +1,41 @@
+use tonic::{Request, Response, Status};
+use crate::adapters::proto::attestation_proto::attestation_proto_service_server::{AttestationProtoService, AttestationProtoServiceServer};
+use crate::adapters::proto::attestation_proto::{Attestation as ProtoAttestation, AttestationPayload as ProtoAttestationPayload, Empty, PublicKey};
+use crate::attestation::{Attestation, ALGORITHM, SIGNER};
+
+pub struct MyAttestationService;
+
+// Map a signed attestation to its proto message
+pub fn to_proto_attestation(attestation: Attestation) -> ProtoAttestation {
+    let payload = attestation.payload;
+    ProtoAttestation {
+        payload: Some(ProtoAttestationPayload {
+            kind: payload.kind,
+            symbol: payload.symbol,
+            values: payload.values.into_iter().collect(),
+            source_update_ids: payload.source_update_ids,
+            timestamp: payload.timestamp,
+        }),
+        signature: attestation.signature,
+        public_key: attestation.public_key,
+    }
+}
+
+#[tonic::async_trait]
+impl AttestationProtoService for MyAttestationService {
+    async fn get_public_key(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<PublicKey>, Status> {
+        let signer = SIGNER.as_ref()
+            .ok_or_else(|| Status::not_found("Signing is not configured"))?;
+        Ok(Response::new(PublicKey {
+            algorithm: ALGORITHM.to_string(),
+            public_key: signer.public_key_hex(),
+        }))
+    }
+}
+
+pub fn create_attestation_service() -> AttestationProtoServiceServer<MyAttestationService> {
+    AttestationProtoServiceServer::new(MyAttestationService)
+}
@@ -1,6 +1,17 @@
 // This file is @generated by prost-build.
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
-pub struct Empty {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SymbolRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+}
+/// Window in seconds, one of reference_price_windows_secs, 0 means the first configured window
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WindowRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub window_secs: u64,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TickerData {
     #[prost(string, tag = "1")]
@@ -15,22 +26,68 @@ pub struct TickerData {
     pub best_ask_price: ::prost::alloc::string::String,
     #[prost(string, tag = "6")]
     pub best_ask_qty: ::prost::alloc::string::String,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "7")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "8")]
+    pub received_at: u64,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+/// Decimal string, e.g. "50500.005". The attestation is only set when signing is configured.
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MidPrice {
-    #[prost(double, tag = "1")]
-    pub mid_price: f64,
+    #[prost(string, tag = "1")]
+    pub mid_price: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub attestation: ::core::option::Option<super::attestation_proto::Attestation>,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "3")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "4")]
+    pub received_at: u64,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+/// Decimal string
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MidWeightedPrice {
-    #[prost(double, tag = "1")]
-    pub mid_weighted_price: f64,
+    #[prost(string, tag = "1")]
+    pub mid_weighted_price: ::prost::alloc::string::String,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "2")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "3")]
+    pub received_at: u64,
+}
+/// Prices are decimal strings, coverage is the share of the window covered by samples (0 to 1)
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Twap {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub window_secs: u64,
+    #[prost(uint64, tag = "3")]
+    pub sample_count: u64,
+    #[prost(double, tag = "4")]
+    pub coverage: f64,
+    #[prost(string, tag = "5")]
+    pub twap_mid: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub twap_microprice: ::prost::alloc::string::String,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "7")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "8")]
+    pub received_at: u64,
 }
 /// Generated client implementations.
 pub mod book_ticker_proto_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    /// Market data getters fail with UNAVAILABLE until the first update of a trading pair was received and with
+    /// FAILED_PRECONDITION once it is older than the configured maximum age, NOT_FOUND is left for unknown pairs
     #[derive(Debug, Clone)]
     pub struct BookTickerProtoServiceClient<T> {
         inner: tonic::client::Grpc<T>,
@@ -115,7 +172,7 @@ pub mod book_ticker_proto_service_client {
         }
         pub async fn get_ticker_data(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::TickerData>, tonic::Status> {
             self.inner
                 .ready()
@@ -142,7 +199,7 @@ pub mod book_ticker_proto_service_client {
         }
         pub async fn get_mid_price(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::MidPrice>, tonic::Status> {
             self.inner
                 .ready()
@@ -169,7 +226,7 @@ pub mod book_ticker_proto_service_client {
         }
         pub async fn get_mid_weighted_price(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
         ) -> std::result::Result<
             tonic::Response<super::MidWeightedPrice>,
             tonic::Status,
@@ -197,6 +254,66 @@ pub mod book_ticker_proto_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Streams the current ticker followed by every update. Updates a slow client
+        /// cannot keep up with are dropped, the stream continues with the latest ones.
+        pub async fn subscribe_ticker(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::TickerData>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/book_ticker_proto.BookTickerProtoService/SubscribeTicker",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "book_ticker_proto.BookTickerProtoService",
+                        "SubscribeTicker",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Time-weighted average of the mid and the microprice, sampled on every ticker update
+        pub async fn get_twap(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WindowRequest>,
+        ) -> std::result::Result<tonic::Response<super::Twap>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/book_ticker_proto.BookTickerProtoService/GetTwap",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "book_ticker_proto.BookTickerProtoService",
+                        "GetTwap",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -208,20 +325,42 @@ pub mod book_ticker_proto_service_server {
     pub trait BookTickerProtoService: std::marker::Send + std::marker::Sync + 'static {
         async fn get_ticker_data(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::TickerData>, tonic::Status>;
         async fn get_mid_price(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::MidPrice>, tonic::Status>;
         async fn get_mid_weighted_price(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::SymbolRequest>,
         ) -> std::result::Result<
             tonic::Response<super::MidWeightedPrice>,
             tonic::Status,
         >;
+        /// Server streaming response type for the SubscribeTicker method.
+        type SubscribeTickerStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::TickerData, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams the current ticker followed by every update. Updates a slow client
+        /// cannot keep up with are dropped, the stream continues with the latest ones.
+        async fn subscribe_ticker(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeTickerStream>,
+            tonic::Status,
+        >;
+        /// Time-weighted average of the mid and the microprice, sampled on every ticker update
+        async fn get_twap(
+            &self,
+            request: tonic::Request<super::WindowRequest>,
+        ) -> std::result::Result<tonic::Response<super::Twap>, tonic::Status>;
     }
+    /// Market data getters fail with UNAVAILABLE until the first update of a trading pair was received and with
+    /// FAILED_PRECONDITION once it is older than the configured maximum age, NOT_FOUND is left for unknown pairs
     #[derive(Debug)]
     pub struct BookTickerProtoServiceServer<T> {
         inner: Arc<T>,
@@ -304,7 +443,8 @@ pub mod book_ticker_proto_service_server {
                     struct GetTickerDataSvc<T: BookTickerProtoService>(pub Arc<T>);
                     impl<
                         T: BookTickerProtoService,
-                    > tonic::server::UnaryService<super::Empty> for GetTickerDataSvc<T> {
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetTickerDataSvc<T> {
                         type Response = super::TickerData;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
@@ -312,7 +452,7 @@ pub mod book_ticker_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::SymbolRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -352,7 +492,8 @@ pub mod book_ticker_proto_service_server {
                     struct GetMidPriceSvc<T: BookTickerProtoService>(pub Arc<T>);
                     impl<
                         T: BookTickerProtoService,
-                    > tonic::server::UnaryService<super::Empty> for GetMidPriceSvc<T> {
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetMidPriceSvc<T> {
                         type Response = super::MidPrice;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
@@ -360,7 +501,7 @@ pub mod book_ticker_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::SymbolRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -400,7 +541,7 @@ pub mod book_ticker_proto_service_server {
                     struct GetMidWeightedPriceSvc<T: BookTickerProtoService>(pub Arc<T>);
                     impl<
                         T: BookTickerProtoService,
-                    > tonic::server::UnaryService<super::Empty>
+                    > tonic::server::UnaryService<super::SymbolRequest>
                     for GetMidWeightedPriceSvc<T> {
                         type Response = super::MidWeightedPrice;
                         type Future = BoxFuture<
@@ -409,7 +550,7 @@ pub mod book_ticker_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::SymbolRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -444,6 +585,102 @@ pub mod book_ticker_proto_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/book_ticker_proto.BookTickerProtoService/SubscribeTicker" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeTickerSvc<T: BookTickerProtoService>(pub Arc<T>);
+                    impl<
+                        T: BookTickerProtoService,
+                    > tonic::server::ServerStreamingService<super::SymbolRequest>
+                    for SubscribeTickerSvc<T> {
+                        type Response = super::TickerData;
+                        type ResponseStream = T::SubscribeTickerStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as BookTickerProtoService>::subscribe_ticker(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SubscribeTickerSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/book_ticker_proto.BookTickerProtoService/GetTwap" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetTwapSvc<T: BookTickerProtoService>(pub Arc<T>);
+                    impl<
+                        T: BookTickerProtoService,
+                    > tonic::server::UnaryService<super::WindowRequest>
+                    for GetTwapSvc<T> {
+                        type Response = super::Twap;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WindowRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as BookTickerProtoService>::get_twap(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetTwapSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
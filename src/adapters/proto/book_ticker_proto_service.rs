@@ -1,9 +1,19 @@
 use tonic::{Request, Response, Status};
 use adapters::proto::book_ticker_proto::book_ticker_proto_service_server::{BookTickerProtoService, BookTickerProtoServiceServer};
-use adapters::proto::book_ticker_proto::{Empty, TickerData, MidPrice, MidWeightedPrice};
+use adapters::proto::book_ticker_proto::{SymbolRequest, TickerData, MidPrice, MidWeightedPrice, WindowRequest, Twap};
 use std::sync::Arc;
 use crate::adapters;
+use crate::adapters::proto::attestation_proto;
+use crate::adapters::proto::attestation_proto_service::to_proto_attestation;
+use crate::attestation::{self, AttestationPayload};
+use crate::adapters::proto::staleness::staleness_status;
+use crate::adapters::proto::update_stream::{forward_updates, UpdateStream};
+use crate::config::CONFIG;
+use crate::domain::entities::book_ticker::BookTickerData;
+use crate::domain::entities::freshness::DataTimes;
+use crate::domain::entities::symbol::normalize_symbol;
 use crate::domain::services::book_ticker_service::BookTickerServiceTrait; // Zaimportuj swój serwis
+use tokio_util::sync::CancellationToken;
 
 pub mod book_ticker_proto {
     tonic::include_proto!("book_ticker_proto"); // Wygenerowany kod gRPC
@@ -11,11 +21,32 @@ pub mod book_ticker_proto {
 
 pub struct MyBookTickerService {
     book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>, // Użycie prawdziwej usługi
+    shutdown: CancellationToken, // Ends the update streams
 }
 
 impl MyBookTickerService {
     pub fn new(book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>) -> Self {
-        Self { book_ticker_service }
+        Self { book_ticker_service, shutdown: CancellationToken::new() }
+    }
+
+    // Update streams end when `shutdown` is cancelled
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+// Mapowanie BookTickerData (Rust) na TickerData (proto)
+fn to_proto_ticker(ticker_data: BookTickerData, times: DataTimes) -> TickerData {
+    TickerData {
+        symbol: ticker_data.symbol,
+        update_id: ticker_data.update_id as i32,
+        best_bid_price: ticker_data.best_bid_price.to_string(),
+        best_bid_qty: ticker_data.best_bid_qty.to_string(),
+        best_ask_price: ticker_data.best_ask_price.to_string(),
+        best_ask_qty: ticker_data.best_ask_qty.to_string(),
+        event_time: times.event_time,
+        received_at: times.received_at,
     }
 }
 
@@ -23,42 +54,102 @@ impl MyBookTickerService {
 impl BookTickerProtoService for MyBookTickerService {
     async fn get_ticker_data(
         &self,
-        _request: Request<Empty>,
+        request: Request<SymbolRequest>,
     ) -> Result<Response<TickerData>, Status> {
-        let ticker_data = self.book_ticker_service.get_ticker_data().await;
-        let response = TickerData {
-            symbol: ticker_data.symbol,
-            update_id: ticker_data.update_id as i32,
-            best_bid_price: ticker_data.best_bid_price,
-            best_bid_qty: ticker_data.best_bid_qty,
-            best_ask_price: ticker_data.best_ask_price,
-            best_ask_qty: ticker_data.best_ask_qty,
-        };
-        Ok(Response::new(response))
+        let symbol = request.into_inner().symbol;
+        let times = self.book_ticker_service.data_times(&symbol).map_err(|e| staleness_status(&symbol, e))?;
+        let ticker_data = self.book_ticker_service.get_ticker_data(&symbol).await
+            .ok_or_else(|| Status::not_found(format!("No data for symbol {}", symbol)))?;
+        Ok(Response::new(to_proto_ticker(ticker_data, times)))
     }
 
     async fn get_mid_price(
         &self,
-        _request: Request<Empty>,
+        request: Request<SymbolRequest>,
     ) -> Result<Response<MidPrice>, Status> {
-        let mid_price = self.book_ticker_service.mid_price().await;
-        let response = MidPrice { mid_price };
+        let symbol = request.into_inner().symbol;
+        // The mid is read from the ticker so the attestation names the update it came from
+        let times = self.book_ticker_service.data_times(&symbol).map_err(|e| staleness_status(&symbol, e))?;
+        let ticker_data = self.book_ticker_service.get_ticker_data(&symbol).await
+            .ok_or_else(|| Status::not_found(format!("No data for symbol {}", symbol)))?;
+        let response = MidPrice {
+            mid_price: ticker_data.mid_price().to_string(),
+            attestation: attestation::attest(|| AttestationPayload::mid_price(&ticker_data)).map(to_proto_attestation),
+            event_time: times.event_time,
+            received_at: times.received_at,
+        };
         Ok(Response::new(response))
     }
 
     async fn get_mid_weighted_price(
         &self,
-        _request: Request<Empty>,
+        request: Request<SymbolRequest>,
     ) -> Result<Response<MidWeightedPrice>, Status> {
-        let mid_weighted_price = self.book_ticker_service.mid_weighted_price().await;
-        let response = MidWeightedPrice { mid_weighted_price };
+        let symbol = request.into_inner().symbol;
+        let times = self.book_ticker_service.data_times(&symbol).map_err(|e| staleness_status(&symbol, e))?;
+        let mid_weighted_price = self.book_ticker_service.mid_weighted_price(&symbol).await
+            .ok_or_else(|| Status::not_found(format!("No data for symbol {}", symbol)))?;
+        let response = MidWeightedPrice {
+            mid_weighted_price: mid_weighted_price.to_string(),
+            event_time: times.event_time,
+            received_at: times.received_at,
+        };
         Ok(Response::new(response))
     }
+
+    async fn get_twap(
+        &self,
+        request: Request<WindowRequest>,
+    ) -> Result<Response<Twap>, Status> {
+        let request = request.into_inner();
+        let window_secs = CONFIG.default.reference_price_window(Some(request.window_secs))
+            .map_err(Status::invalid_argument)?;
+        let times = self.book_ticker_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let twap = self.book_ticker_service.twap(&request.symbol, window_secs).await
+            .ok_or_else(|| Status::not_found(format!("No data for symbol {}", request.symbol)))?;
+        Ok(Response::new(Twap {
+            symbol: normalize_symbol(&request.symbol),
+            window_secs: twap.window_secs,
+            sample_count: twap.sample_count,
+            coverage: twap.coverage,
+            twap_mid: twap.mid.to_string(),
+            twap_microprice: twap.microprice.to_string(),
+            event_time: times.event_time,
+            received_at: times.received_at,
+        }))
+    }
+
+    type SubscribeTickerStream = UpdateStream<TickerData>;
+
+    async fn subscribe_ticker(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<Self::SubscribeTickerStream>, Status> {
+        let symbol = normalize_symbol(&request.into_inner().symbol);
+        if symbol.is_empty() {
+            return Err(Status::invalid_argument("Symbol is required"));
+        }
+
+        // Subscribe before reading the current state so no update is missed
+        let updates = self.book_ticker_service.subscribe();
+        let initial = self.book_ticker_service.get_ticker_data(&symbol).await
+            .map(|ticker_data| to_proto_ticker(ticker_data, self.book_ticker_service.last_times(&symbol).unwrap_or_default()));
+
+        // Streamed updates are sent as they arrive, they carry their receive time but are never refused as stale
+        let book_ticker_service = self.book_ticker_service.clone();
+        let stream = forward_updates(updates, initial, CONFIG.default.grpc_stream_buffer, self.shutdown.clone(), move |ticker_data: BookTickerData| {
+            let relevant = normalize_symbol(&ticker_data.symbol) == symbol;
+            let times = book_ticker_service.last_times(&symbol).unwrap_or_default();
+            async move { relevant.then(|| to_proto_ticker(ticker_data, times)) }
+        });
+        Ok(Response::new(stream))
+    }
 }
 
 // Funkcja do tworzenia serwisu gRPC z prawdziwą implementacją
 pub fn create_book_ticker_service(
-    book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>
+    book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+    shutdown: CancellationToken,
 ) -> BookTickerProtoServiceServer<MyBookTickerService> {
-    BookTickerProtoServiceServer::new(MyBookTickerService::new(book_ticker_service))
-}
\ No newline at end of file
+    BookTickerProtoServiceServer::new(MyBookTickerService::new(book_ticker_service).with_shutdown(shutdown))
+}
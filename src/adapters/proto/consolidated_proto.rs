@@ -0,0 +1,432 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SymbolRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+}
+/// Prices and quantities are decimal strings
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VenueQuote {
+    #[prost(string, tag = "1")]
+    pub venue: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub best_bid_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub best_bid_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub best_ask_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub best_ask_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub mid: ::prost::alloc::string::String,
+    /// ms since the UNIX epoch, unset when the venue does not send one
+    #[prost(uint64, optional, tag = "7")]
+    pub event_time: ::core::option::Option<u64>,
+    /// ms since the UNIX epoch
+    #[prost(uint64, tag = "8")]
+    pub received_at: u64,
+    /// Only fresh quotes are consolidated
+    #[prost(bool, tag = "9")]
+    pub fresh: bool,
+}
+/// Method is median or volume_weighted
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConsolidatedPrice {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub method: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub price: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "4")]
+    pub venues_used: u32,
+    #[prost(message, repeated, tag = "5")]
+    pub venues: ::prost::alloc::vec::Vec<VenueQuote>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct VenueQuotes {
+    #[prost(message, repeated, tag = "1")]
+    pub venues: ::prost::alloc::vec::Vec<VenueQuote>,
+}
+/// Generated client implementations.
+pub mod consolidated_proto_service_client {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    use tonic::codegen::http::Uri;
+    #[derive(Debug, Clone)]
+    pub struct ConsolidatedProtoServiceClient<T> {
+        inner: tonic::client::Grpc<T>,
+    }
+    impl ConsolidatedProtoServiceClient<tonic::transport::Channel> {
+        /// Attempt to create a new client by connecting to a given endpoint.
+        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
+        where
+            D: TryInto<tonic::transport::Endpoint>,
+            D::Error: Into<StdError>,
+        {
+            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
+            Ok(Self::new(conn))
+        }
+    }
+    impl<T> ConsolidatedProtoServiceClient<T>
+    where
+        T: tonic::client::GrpcService<tonic::body::BoxBody>,
+        T::Error: Into<StdError>,
+        T::ResponseBody: Body<Data = Bytes> + std::marker::Send + 'static,
+        <T::ResponseBody as Body>::Error: Into<StdError> + std::marker::Send,
+    {
+        pub fn new(inner: T) -> Self {
+            let inner = tonic::client::Grpc::new(inner);
+            Self { inner }
+        }
+        pub fn with_origin(inner: T, origin: Uri) -> Self {
+            let inner = tonic::client::Grpc::with_origin(inner, origin);
+            Self { inner }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> ConsolidatedProtoServiceClient<InterceptedService<T, F>>
+        where
+            F: tonic::service::Interceptor,
+            T::ResponseBody: Default,
+            T: tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+                Response = http::Response<
+                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
+                >,
+            >,
+            <T as tonic::codegen::Service<
+                http::Request<tonic::body::BoxBody>,
+            >>::Error: Into<StdError> + std::marker::Send + std::marker::Sync,
+        {
+            ConsolidatedProtoServiceClient::new(
+                InterceptedService::new(inner, interceptor),
+            )
+        }
+        /// Compress requests with the given encoding.
+        ///
+        /// This requires the server to support it otherwise it might respond with an
+        /// error.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.send_compressed(encoding);
+            self
+        }
+        /// Enable decompressing responses.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.inner = self.inner.accept_compressed(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_decoding_message_size(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.inner = self.inner.max_encoding_message_size(limit);
+            self
+        }
+        /// Returns the price across the venues with a fresh quote, UNAVAILABLE when too few venues have one
+        pub async fn get_consolidated_price(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConsolidatedPrice>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/consolidated_proto.ConsolidatedProtoService/GetConsolidatedPrice",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "consolidated_proto.ConsolidatedProtoService",
+                        "GetConsolidatedPrice",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the latest quote of every venue, also when it is too old to be consolidated
+        pub async fn get_venue_quotes(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<tonic::Response<super::VenueQuotes>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/consolidated_proto.ConsolidatedProtoService/GetVenueQuotes",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "consolidated_proto.ConsolidatedProtoService",
+                        "GetVenueQuotes",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod consolidated_proto_service_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with ConsolidatedProtoServiceServer.
+    #[async_trait]
+    pub trait ConsolidatedProtoService: std::marker::Send + std::marker::Sync + 'static {
+        /// Returns the price across the venues with a fresh quote, UNAVAILABLE when too few venues have one
+        async fn get_consolidated_price(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ConsolidatedPrice>,
+            tonic::Status,
+        >;
+        /// Returns the latest quote of every venue, also when it is too old to be consolidated
+        async fn get_venue_quotes(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<tonic::Response<super::VenueQuotes>, tonic::Status>;
+    }
+    #[derive(Debug)]
+    pub struct ConsolidatedProtoServiceServer<T> {
+        inner: Arc<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    impl<T> ConsolidatedProtoServiceServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>>
+    for ConsolidatedProtoServiceServer<T>
+    where
+        T: ConsolidatedProtoService,
+        B: Body + std::marker::Send + 'static,
+        B::Error: Into<StdError> + std::marker::Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            match req.uri().path() {
+                "/consolidated_proto.ConsolidatedProtoService/GetConsolidatedPrice" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetConsolidatedPriceSvc<T: ConsolidatedProtoService>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: ConsolidatedProtoService,
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetConsolidatedPriceSvc<T> {
+                        type Response = super::ConsolidatedPrice;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ConsolidatedProtoService>::get_consolidated_price(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetConsolidatedPriceSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/consolidated_proto.ConsolidatedProtoService/GetVenueQuotes" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVenueQuotesSvc<T: ConsolidatedProtoService>(pub Arc<T>);
+                    impl<
+                        T: ConsolidatedProtoService,
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetVenueQuotesSvc<T> {
+                        type Response = super::VenueQuotes;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ConsolidatedProtoService>::get_venue_quotes(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetVenueQuotesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                _ => {
+                    Box::pin(async move {
+                        Ok(
+                            http::Response::builder()
+                                .status(200)
+                                .header("grpc-status", tonic::Code::Unimplemented as i32)
+                                .header(
+                                    http::header::CONTENT_TYPE,
+                                    tonic::metadata::GRPC_CONTENT_TYPE,
+                                )
+                                .body(empty_body())
+                                .unwrap(),
+                        )
+                    })
+                }
+            }
+        }
+    }
+    impl<T> Clone for ConsolidatedProtoServiceServer<T> {
+        fn clone(&self) -> Self {
+            let inner = self.inner.clone();
+            Self {
+                inner,
+                accept_compression_encodings: self.accept_compression_encodings,
+                send_compression_encodings: self.send_compression_encodings,
+                max_decoding_message_size: self.max_decoding_message_size,
+                max_encoding_message_size: self.max_encoding_message_size,
+            }
+        }
+    }
+    /// Generated gRPC service name
+    pub const SERVICE_NAME: &str = "consolidated_proto.ConsolidatedProtoService";
+    impl<T> tonic::server::NamedService for ConsolidatedProtoServiceServer<T> {
+        const NAME: &'static str = SERVICE_NAME;
+    }
+}
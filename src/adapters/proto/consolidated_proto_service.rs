@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use crate::adapters::proto::consolidated_proto::consolidated_proto_service_server::{ConsolidatedProtoService, ConsolidatedProtoServiceServer};
+use crate::adapters::proto::consolidated_proto::{ConsolidatedPrice, SymbolRequest, VenueQuote as ProtoVenueQuote, VenueQuotes};
+use crate::config::CONFIG;
+use crate::domain::entities::consolidated_price::{ConsolidationError, VenueQuote};
+use crate::domain::services::consolidation_service::ConsolidationService;
+
+pub struct MyConsolidatedService {
+    consolidation_service: Arc<ConsolidationService>,
+}
+
+impl MyConsolidatedService {
+    pub fn new(consolidation_service: Arc<ConsolidationService>) -> Self {
+        Self { consolidation_service }
+    }
+}
+
+// Map a VenueQuote (Rust) to its proto message
+fn to_proto_quote(quote: &VenueQuote) -> ProtoVenueQuote {
+    ProtoVenueQuote {
+        venue: quote.venue.clone(),
+        best_bid_price: quote.best_bid.price.to_string(),
+        best_bid_qty: quote.best_bid.qty.to_string(),
+        best_ask_price: quote.best_ask.price.to_string(),
+        best_ask_qty: quote.best_ask.qty.to_string(),
+        mid: quote.mid().to_string(),
+        event_time: quote.times.event_time,
+        received_at: quote.times.received_at,
+        fresh: quote.fresh,
+    }
+}
+
+// UNAVAILABLE when no venue (or too few venues) can be consolidated, NOT_FOUND for symbols that are not one of the trading pairs
+fn consolidation_status(symbol: &str, error: ConsolidationError) -> Status {
+    match error {
+        ConsolidationError::NoData if !CONFIG.default.is_trading_pair(symbol) => Status::not_found(format!("No data for symbol {}", symbol)),
+        ConsolidationError::NoData => Status::unavailable(format!("No data has been received for symbol {}", symbol)),
+        not_enough => Status::unavailable(format!("{}: {}", symbol, not_enough)),
+    }
+}
+
+#[tonic::async_trait]
+impl ConsolidatedProtoService for MyConsolidatedService {
+    async fn get_consolidated_price(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<ConsolidatedPrice>, Status> {
+        let symbol = request.into_inner().symbol;
+        let price = self.consolidation_service.consolidated(&symbol).map_err(|e| consolidation_status(&symbol, e))?;
+        Ok(Response::new(ConsolidatedPrice {
+            symbol: price.symbol,
+            method: price.method.to_string(),
+            price: price.price.to_string(),
+            venues_used: price.venues_used as u32,
+            venues: price.quotes.iter().map(to_proto_quote).collect(),
+        }))
+    }
+
+    async fn get_venue_quotes(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<VenueQuotes>, Status> {
+        let symbol = request.into_inner().symbol;
+        let quotes = self.consolidation_service.quotes(&symbol);
+        if quotes.is_empty() {
+            return Err(consolidation_status(&symbol, ConsolidationError::NoData));
+        }
+        Ok(Response::new(VenueQuotes {
+            venues: quotes.iter().map(to_proto_quote).collect(),
+        }))
+    }
+}
+
+pub fn create_consolidated_service(consolidation_service: Arc<ConsolidationService>) -> ConsolidatedProtoServiceServer<MyConsolidatedService> {
+    ConsolidatedProtoServiceServer::new(MyConsolidatedService::new(consolidation_service))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use tonic::Code;
+    use crate::domain::entities::market_event::TopOfBook;
+    use crate::domain::entities::order_book::OrderBookEntry;
+
+    fn top(bid: &str, ask: &str) -> TopOfBook {
+        TopOfBook {
+            symbol: "BTCFDUSD".to_string(),
+            update_id: 1,
+            best_bid: OrderBookEntry { price: bid.parse().unwrap(), qty: dec!(1) },
+            best_ask: OrderBookEntry { price: ask.parse().unwrap(), qty: dec!(1) },
+            event_time: None,
+        }
+    }
+
+    fn request(symbol: &str) -> Request<SymbolRequest> {
+        Request::new(SymbolRequest { symbol: symbol.to_string() })
+    }
+
+    #[tokio::test]
+    async fn test_missing_venues_are_unavailable_and_unknown_symbols_not_found() {
+        let service = Arc::new(ConsolidationService::new().with_min_venues(2));
+        let grpc = MyConsolidatedService::new(service.clone());
+
+        assert_eq!(grpc.get_consolidated_price(request("BTCFDUSD")).await.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(grpc.get_venue_quotes(request("BTCFDUSD")).await.unwrap_err().code(), Code::Unavailable);
+        assert_eq!(grpc.get_consolidated_price(request("NOTTRADED")).await.unwrap_err().code(), Code::NotFound);
+        assert_eq!(grpc.get_venue_quotes(request("NOTTRADED")).await.unwrap_err().code(), Code::NotFound);
+
+        // One venue is not enough for the price, its quote is still served
+        service.update("binance", &top("99", "101"));
+        let status = grpc.get_consolidated_price(request("BTCFDUSD")).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert!(status.message().contains("1 venues have fresh quotes, 2 are required"));
+        let quotes = grpc.get_venue_quotes(request("BTCFDUSD")).await.unwrap().into_inner().venues;
+        assert_eq!((quotes[0].venue.as_str(), quotes[0].mid.as_str(), quotes[0].fresh), ("binance", "100", true));
+
+        service.update("coinbase", &top("101", "103"));
+        let price = grpc.get_consolidated_price(request("BTCFDUSD")).await.unwrap().into_inner();
+        assert_eq!((price.price.as_str(), price.method.as_str(), price.venues_used), ("101", "median", 2));
+        assert_eq!(price.venues.len(), 2);
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use crate::adapters::proto::book_ticker_proto::book_ticker_proto_service_server::BookTickerProtoServiceServer;
+use crate::adapters::proto::book_ticker_proto_service::MyBookTickerService;
+use crate::adapters::proto::order_book_proto::order_book_proto_service_server::OrderBookProtoServiceServer;
+use crate::adapters::proto::order_book_proto_service::MyOrderBookService;
+use crate::adapters::proto::trade_history_proto::trade_history_proto_service_server::TradeHistoryProtoServiceServer;
+use crate::adapters::proto::trade_history_proto_service::MyTradeHistoryService;
+use crate::health::{self, FeedStatus, HealthRegistry};
+use crate::metrics::{STREAM_BOOK_TICKER, STREAM_ORDER_BOOK, STREAM_TRADE};
+
+// How often the grpc.health.v1 statuses are refreshed
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+fn serving_status(ready: bool) -> ServingStatus {
+    if ready { ServingStatus::Serving } else { ServingStatus::NotServing }
+}
+
+fn feed_ready(statuses: &[FeedStatus], feed: &str) -> bool {
+    statuses.iter().any(|status| status.feed == feed && status.ready)
+}
+
+// Keep the grpc.health.v1 service in line with the feed readiness, every gRPC service
+// follows the feed it serves and the overall ("") status follows /readyz
+pub async fn report_feed_health(mut reporter: HealthReporter, health: Arc<HealthRegistry>) {
+    let mut interval = tokio::time::interval(REPORT_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        let statuses = health.feed_statuses();
+
+        reporter.set_service_status("", serving_status(health::is_ready(&statuses))).await;
+        reporter.set_service_status(
+            <BookTickerProtoServiceServer<MyBookTickerService> as NamedService>::NAME,
+            serving_status(feed_ready(&statuses, STREAM_BOOK_TICKER)),
+        ).await;
+        reporter.set_service_status(
+            <OrderBookProtoServiceServer<MyOrderBookService> as NamedService>::NAME,
+            serving_status(feed_ready(&statuses, STREAM_ORDER_BOOK)),
+        ).await;
+        reporter.set_service_status(
+            <TradeHistoryProtoServiceServer<MyTradeHistoryService> as NamedService>::NAME,
+            serving_status(feed_ready(&statuses, STREAM_TRADE)),
+        ).await;
+    }
+}
@@ -0,0 +1,54 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::codegen::http;
+use tower::{Layer, Service};
+use crate::metrics::{GRPC_REQUESTS, GRPC_REQUEST_DURATION};
+
+// Tower layer counting gRPC requests and measuring their latency per method
+#[derive(Debug, Clone, Default)]
+pub struct GrpcMetricsLayer;
+
+impl<S> Layer<S> for GrpcMetricsLayer {
+    type Service = GrpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcMetricsService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GrpcMetricsService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for GrpcMetricsService<S>
+where
+    S: Service<http::Request<B>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // The path is /<package>.<Service>/<Method>
+        let method = request.uri().path().to_string();
+        let started = Instant::now();
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await;
+            GRPC_REQUESTS.with_label_values(&[&method]).inc();
+            GRPC_REQUEST_DURATION
+                .with_label_values(&[&method])
+                .observe(started.elapsed().as_secs_f64());
+            response
+        })
+    }
+}
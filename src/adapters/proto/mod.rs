@@ -1,6 +1,16 @@
+pub mod attestation_proto;
+pub mod attestation_proto_service;
 pub mod book_ticker_proto;
 pub mod book_ticker_proto_service;
+pub mod consolidated_proto;
+pub mod consolidated_proto_service;
+pub mod grpc_health;
+pub mod grpc_metrics;
 pub mod order_book_proto;
 pub mod order_book_proto_service;
+pub mod publisher_proto;
+pub mod publisher_proto_service;
+pub mod staleness;
 pub mod trade_history_proto;
-pub mod trade_history_proto_service;
\ No newline at end of file
+pub mod trade_history_proto_service;
+pub mod update_stream;
@@ -2,6 +2,11 @@
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct Empty {}
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SymbolRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OrderBookSd {
     #[prost(string, tag = "1")]
     pub stream: ::prost::alloc::string::String,
@@ -24,19 +29,29 @@ pub struct OrderBookLevel {
     #[prost(string, tag = "2")]
     pub qty: ::prost::alloc::string::String,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+/// The attestation is only set by GetTopOfBook when signing is configured
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OrderBookTop {
     #[prost(message, optional, tag = "1")]
     pub best_bid: ::core::option::Option<OrderBookEntry>,
     #[prost(message, optional, tag = "2")]
     pub best_ask: ::core::option::Option<OrderBookEntry>,
+    #[prost(message, optional, tag = "3")]
+    pub attestation: ::core::option::Option<super::attestation_proto::Attestation>,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "4")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "5")]
+    pub received_at: u64,
 }
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+/// Prices and quantities are decimal strings with the exchange precision
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OrderBookEntry {
-    #[prost(double, tag = "1")]
-    pub price: f64,
-    #[prost(double, tag = "2")]
-    pub qty: f64,
+    #[prost(string, tag = "1")]
+    pub price: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub qty: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FullOrderBook {
@@ -44,12 +59,153 @@ pub struct FullOrderBook {
     pub bids: ::prost::alloc::vec::Vec<OrderBookEntry>,
     #[prost(message, repeated, tag = "2")]
     pub asks: ::prost::alloc::vec::Vec<OrderBookEntry>,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "3")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "4")]
+    pub received_at: u64,
+}
+/// Levels per side (0 means 10) and the band around the mid either in bps or in percent
+/// (decimal strings, both empty means 10 bps)
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AnalyticsRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub levels: u32,
+    #[prost(string, tag = "3")]
+    pub band_bps: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub band_pct: ::prost::alloc::string::String,
+}
+/// Numbers are decimal strings
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Spread {
+    #[prost(string, tag = "1")]
+    pub best_bid: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub best_ask: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub mid: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub absolute: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub bps: ::prost::alloc::string::String,
+}
+/// (bid - ask) / (bid + ask) quantity of the top levels, from -1 to 1
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DepthImbalance {
+    #[prost(uint32, tag = "1")]
+    pub levels: u32,
+    #[prost(string, tag = "2")]
+    pub bid_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub ask_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub imbalance: ::prost::alloc::string::String,
+}
+/// Liquidity resting between lower_price and upper_price
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BandLiquidity {
+    #[prost(string, tag = "1")]
+    pub band_bps: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub lower_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub upper_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub bid_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub ask_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub bid_notional: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub ask_notional: ::prost::alloc::string::String,
+}
+/// Quantity-weighted average prices of the top levels
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WeightedPrices {
+    #[prost(uint32, tag = "1")]
+    pub levels: u32,
+    #[prost(string, tag = "2")]
+    pub bid_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub ask_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub weighted_mid: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub microprice: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BookAnalytics {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub spread: ::core::option::Option<Spread>,
+    #[prost(message, optional, tag = "3")]
+    pub imbalance: ::core::option::Option<DepthImbalance>,
+    #[prost(message, optional, tag = "4")]
+    pub liquidity: ::core::option::Option<BandLiquidity>,
+    #[prost(message, optional, tag = "5")]
+    pub weighted: ::core::option::Option<WeightedPrices>,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "6")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "7")]
+    pub received_at: u64,
+}
+/// Side is buy or sell, the amount is set either in the base asset (qty) or in the quote asset (quote_qty),
+/// both decimal strings
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImpactRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub side: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub quote_qty: ::prost::alloc::string::String,
+}
+/// Fill of the visible depth, numbers are decimal strings. Slippage is the cost against the mid in bps.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImpactEstimate {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub side: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub filled_qty: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub filled_quote: ::prost::alloc::string::String,
+    #[prost(string, tag = "5")]
+    pub average_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub worst_price: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub mid: ::prost::alloc::string::String,
+    #[prost(string, tag = "8")]
+    pub slippage_bps: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "9")]
+    pub levels_consumed: u32,
+    #[prost(bool, tag = "10")]
+    pub insufficient_depth: bool,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "11")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "12")]
+    pub received_at: u64,
 }
 /// Generated client implementations.
 pub mod order_book_proto_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    /// Market data getters fail with UNAVAILABLE until the first update of a trading pair was received and with
+    /// FAILED_PRECONDITION once it is older than the configured maximum age, NOT_FOUND is left for unknown pairs
     #[derive(Debug, Clone)]
     pub struct OrderBookProtoServiceClient<T> {
         inner: tonic::client::Grpc<T>,
@@ -159,7 +315,7 @@ pub mod order_book_proto_service_client {
         }
         pub async fn print_top_of_book(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status> {
             self.inner
                 .ready()
@@ -186,7 +342,7 @@ pub mod order_book_proto_service_client {
         }
         pub async fn get_top_of_book(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::OrderBookTop>, tonic::Status> {
             self.inner
                 .ready()
@@ -213,7 +369,7 @@ pub mod order_book_proto_service_client {
         }
         pub async fn get_full_book(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::FullOrderBook>, tonic::Status> {
             self.inner
                 .ready()
@@ -238,6 +394,126 @@ pub mod order_book_proto_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Streams the top of book after every order book change. Updates a slow client
+        /// cannot keep up with are dropped, the stream continues with the latest ones.
+        pub async fn subscribe_top_of_book(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::OrderBookTop>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/order_book_proto.OrderBookProtoService/SubscribeTopOfBook",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "order_book_proto.OrderBookProtoService",
+                        "SubscribeTopOfBook",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Streams the full order book after every change, same drop policy as above.
+        pub async fn subscribe_book_depth(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::FullOrderBook>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/order_book_proto.OrderBookProtoService/SubscribeBookDepth",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "order_book_proto.OrderBookProtoService",
+                        "SubscribeBookDepth",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Returns the spread, the top-N imbalance, the liquidity within a band around the mid
+        /// and the quantity-weighted mid and microprice of the top levels.
+        pub async fn get_analytics(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AnalyticsRequest>,
+        ) -> std::result::Result<tonic::Response<super::BookAnalytics>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/order_book_proto.OrderBookProtoService/GetAnalytics",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "order_book_proto.OrderBookProtoService",
+                        "GetAnalytics",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Walks the levels a market order would take and returns its fill price and slippage
+        pub async fn estimate_impact(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ImpactRequest>,
+        ) -> std::result::Result<tonic::Response<super::ImpactEstimate>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/order_book_proto.OrderBookProtoService/EstimateImpact",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "order_book_proto.OrderBookProtoService",
+                        "EstimateImpact",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -253,17 +529,59 @@ pub mod order_book_proto_service_server {
         ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status>;
         async fn print_top_of_book(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status>;
         async fn get_top_of_book(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::OrderBookTop>, tonic::Status>;
         async fn get_full_book(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::SymbolRequest>,
         ) -> std::result::Result<tonic::Response<super::FullOrderBook>, tonic::Status>;
+        /// Server streaming response type for the SubscribeTopOfBook method.
+        type SubscribeTopOfBookStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::OrderBookTop, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams the top of book after every order book change. Updates a slow client
+        /// cannot keep up with are dropped, the stream continues with the latest ones.
+        async fn subscribe_top_of_book(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeTopOfBookStream>,
+            tonic::Status,
+        >;
+        /// Server streaming response type for the SubscribeBookDepth method.
+        type SubscribeBookDepthStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::FullOrderBook, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams the full order book after every change, same drop policy as above.
+        async fn subscribe_book_depth(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeBookDepthStream>,
+            tonic::Status,
+        >;
+        /// Returns the spread, the top-N imbalance, the liquidity within a band around the mid
+        /// and the quantity-weighted mid and microprice of the top levels.
+        async fn get_analytics(
+            &self,
+            request: tonic::Request<super::AnalyticsRequest>,
+        ) -> std::result::Result<tonic::Response<super::BookAnalytics>, tonic::Status>;
+        /// Walks the levels a market order would take and returns its fill price and slippage
+        async fn estimate_impact(
+            &self,
+            request: tonic::Request<super::ImpactRequest>,
+        ) -> std::result::Result<tonic::Response<super::ImpactEstimate>, tonic::Status>;
     }
+    /// Market data getters fail with UNAVAILABLE until the first update of a trading pair was received and with
+    /// FAILED_PRECONDITION once it is older than the configured maximum age, NOT_FOUND is left for unknown pairs
     #[derive(Debug)]
     pub struct OrderBookProtoServiceServer<T> {
         inner: Arc<T>,
@@ -395,7 +713,7 @@ pub mod order_book_proto_service_server {
                     struct PrintTopOfBookSvc<T: OrderBookProtoService>(pub Arc<T>);
                     impl<
                         T: OrderBookProtoService,
-                    > tonic::server::UnaryService<super::Empty>
+                    > tonic::server::UnaryService<super::SymbolRequest>
                     for PrintTopOfBookSvc<T> {
                         type Response = super::Empty;
                         type Future = BoxFuture<
@@ -404,7 +722,7 @@ pub mod order_book_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::SymbolRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -444,7 +762,8 @@ pub mod order_book_proto_service_server {
                     struct GetTopOfBookSvc<T: OrderBookProtoService>(pub Arc<T>);
                     impl<
                         T: OrderBookProtoService,
-                    > tonic::server::UnaryService<super::Empty> for GetTopOfBookSvc<T> {
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetTopOfBookSvc<T> {
                         type Response = super::OrderBookTop;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
@@ -452,7 +771,7 @@ pub mod order_book_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::SymbolRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -492,7 +811,8 @@ pub mod order_book_proto_service_server {
                     struct GetFullBookSvc<T: OrderBookProtoService>(pub Arc<T>);
                     impl<
                         T: OrderBookProtoService,
-                    > tonic::server::UnaryService<super::Empty> for GetFullBookSvc<T> {
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetFullBookSvc<T> {
                         type Response = super::FullOrderBook;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
@@ -500,7 +820,7 @@ pub mod order_book_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::SymbolRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -532,6 +852,201 @@ pub mod order_book_proto_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/order_book_proto.OrderBookProtoService/SubscribeTopOfBook" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeTopOfBookSvc<T: OrderBookProtoService>(pub Arc<T>);
+                    impl<
+                        T: OrderBookProtoService,
+                    > tonic::server::ServerStreamingService<super::SymbolRequest>
+                    for SubscribeTopOfBookSvc<T> {
+                        type Response = super::OrderBookTop;
+                        type ResponseStream = T::SubscribeTopOfBookStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OrderBookProtoService>::subscribe_top_of_book(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SubscribeTopOfBookSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/order_book_proto.OrderBookProtoService/SubscribeBookDepth" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeBookDepthSvc<T: OrderBookProtoService>(pub Arc<T>);
+                    impl<
+                        T: OrderBookProtoService,
+                    > tonic::server::ServerStreamingService<super::SymbolRequest>
+                    for SubscribeBookDepthSvc<T> {
+                        type Response = super::FullOrderBook;
+                        type ResponseStream = T::SubscribeBookDepthStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OrderBookProtoService>::subscribe_book_depth(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SubscribeBookDepthSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/order_book_proto.OrderBookProtoService/GetAnalytics" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAnalyticsSvc<T: OrderBookProtoService>(pub Arc<T>);
+                    impl<
+                        T: OrderBookProtoService,
+                    > tonic::server::UnaryService<super::AnalyticsRequest>
+                    for GetAnalyticsSvc<T> {
+                        type Response = super::BookAnalytics;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AnalyticsRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OrderBookProtoService>::get_analytics(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetAnalyticsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/order_book_proto.OrderBookProtoService/EstimateImpact" => {
+                    #[allow(non_camel_case_types)]
+                    struct EstimateImpactSvc<T: OrderBookProtoService>(pub Arc<T>);
+                    impl<
+                        T: OrderBookProtoService,
+                    > tonic::server::UnaryService<super::ImpactRequest>
+                    for EstimateImpactSvc<T> {
+                        type Response = super::ImpactEstimate;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ImpactRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OrderBookProtoService>::estimate_impact(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = EstimateImpactSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -1,9 +1,21 @@
 use tonic::{Request, Response, Status};
 use crate::adapters::proto::order_book_proto::order_book_proto_service_server::{OrderBookProtoService, OrderBookProtoServiceServer};
-use crate::adapters::proto::order_book_proto::{Empty, FullOrderBook as ProtoFullOrderBook, OrderBookSd, OrderBookTop as ProtoOrderBookTop};
-use crate::domain::entities::order_book::OrderBookSD;
+use crate::adapters::proto::order_book_proto::{Empty, FullOrderBook as ProtoFullOrderBook, OrderBookSd, OrderBookTop as ProtoOrderBookTop, SymbolRequest,
+    AnalyticsRequest, BookAnalytics as ProtoBookAnalytics, Spread as ProtoSpread, DepthImbalance as ProtoDepthImbalance,
+    BandLiquidity as ProtoBandLiquidity, WeightedPrices as ProtoWeightedPrices, ImpactRequest, ImpactEstimate as ProtoImpactEstimate};
+use crate::adapters::proto::attestation_proto;
+use crate::adapters::proto::attestation_proto_service::to_proto_attestation;
+use crate::adapters::proto::staleness::staleness_status;
+use crate::adapters::proto::update_stream::{forward_updates, UpdateStream};
+use crate::attestation::{self, AttestationPayload};
+use crate::config::CONFIG;
+use crate::domain::entities::freshness::DataTimes;
+use crate::domain::entities::order_book::{FullOrderBook, OrderBookSD, OrderBookTop, OrderBookUpdate};
+use crate::domain::entities::order_book_analytics::{BookAnalytics, BookAnalyticsParams, ImpactOrder};
+use crate::domain::entities::symbol::normalize_symbol;
 use crate::domain::services::order_book_service::OrderBookServiceTrait;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 pub mod order_book_proto {
     tonic::include_proto!("order_book_proto"); // Wygenerowany kod gRPC
@@ -11,12 +23,99 @@ pub mod order_book_proto {
 
 pub struct MyOrderBookService {
     order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>, // Korzystanie z rzeczywistej implementacji OrderBookService
+    shutdown: CancellationToken, // Ends the update streams
 }
 
 impl MyOrderBookService {
     pub fn new(order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>) -> Self {
-        Self { order_book_service }
+        Self { order_book_service, shutdown: CancellationToken::new() }
     }
+
+    // Update streams end when `shutdown` is cancelled
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+// Mapowanie OrderBookTop (Rust) na OrderBookTop (proto)
+fn to_proto_top(top_of_book: OrderBookTop, times: DataTimes) -> ProtoOrderBookTop {
+    ProtoOrderBookTop {
+        best_bid: Some(crate::adapters::proto::order_book_proto::OrderBookEntry {
+            price: top_of_book.best_bid.price.to_string(),
+            qty: top_of_book.best_bid.qty.to_string(),
+        }),
+        best_ask: Some(crate::adapters::proto::order_book_proto::OrderBookEntry {
+            price: top_of_book.best_ask.price.to_string(),
+            qty: top_of_book.best_ask.qty.to_string(),
+        }),
+        attestation: None,
+        event_time: times.event_time,
+        received_at: times.received_at,
+    }
+}
+
+// Mapowanie FullOrderBook (Rust) na FullOrderBook (proto)
+fn to_proto_full_book(full_book: FullOrderBook, times: DataTimes) -> ProtoFullOrderBook {
+    ProtoFullOrderBook {
+        bids: full_book.bids.unwrap_or_default().iter().map(|b| {
+            crate::adapters::proto::order_book_proto::OrderBookEntry {
+                price: b.price.to_string(),
+                qty: b.qty.to_string(),
+            }
+        }).collect(),
+        asks: full_book.asks.unwrap_or_default().iter().map(|a| {
+            crate::adapters::proto::order_book_proto::OrderBookEntry {
+                price: a.price.to_string(),
+                qty: a.qty.to_string(),
+            }
+        }).collect(),
+        event_time: times.event_time,
+        received_at: times.received_at,
+    }
+}
+
+fn to_proto_analytics(symbol: String, analytics: BookAnalytics, times: DataTimes) -> ProtoBookAnalytics {
+    let BookAnalytics { spread, imbalance, liquidity, weighted } = analytics;
+    ProtoBookAnalytics {
+        symbol,
+        spread: Some(ProtoSpread {
+            best_bid: spread.best_bid.to_string(),
+            best_ask: spread.best_ask.to_string(),
+            mid: spread.mid.to_string(),
+            absolute: spread.absolute.to_string(),
+            bps: spread.bps.to_string(),
+        }),
+        imbalance: Some(ProtoDepthImbalance {
+            levels: imbalance.levels as u32,
+            bid_qty: imbalance.bid_qty.to_string(),
+            ask_qty: imbalance.ask_qty.to_string(),
+            imbalance: imbalance.imbalance.to_string(),
+        }),
+        liquidity: Some(ProtoBandLiquidity {
+            band_bps: liquidity.band_bps.to_string(),
+            lower_price: liquidity.lower_price.to_string(),
+            upper_price: liquidity.upper_price.to_string(),
+            bid_qty: liquidity.bid_qty.to_string(),
+            ask_qty: liquidity.ask_qty.to_string(),
+            bid_notional: liquidity.bid_notional.to_string(),
+            ask_notional: liquidity.ask_notional.to_string(),
+        }),
+        weighted: Some(ProtoWeightedPrices {
+            levels: weighted.levels as u32,
+            bid_price: weighted.bid_price.to_string(),
+            ask_price: weighted.ask_price.to_string(),
+            weighted_mid: weighted.weighted_mid.to_string(),
+            microprice: weighted.microprice.to_string(),
+        }),
+        event_time: times.event_time,
+        received_at: times.received_at,
+    }
+}
+
+// Symbol of a subscription, `None` when it is missing
+fn subscription_symbol(request: Request<SymbolRequest>) -> Option<String> {
+    Some(normalize_symbol(&request.into_inner().symbol)).filter(|symbol| !symbol.is_empty())
 }
 
 #[tonic::async_trait]
@@ -40,7 +139,7 @@ impl OrderBookProtoService for MyOrderBookService {
             };
 
             // Aktualizacja książki zleceń
-            self.order_book_service.update_order_book(update).await;
+            self.order_book_service.apply_snapshot(&update.to_snapshot()).await;
 
             Ok(Response::new(Empty {}))
         } else {
@@ -50,31 +149,26 @@ impl OrderBookProtoService for MyOrderBookService {
 
     async fn print_top_of_book(
         &self,
-        _request: Request<Empty>,
+        request: Request<SymbolRequest>,
     ) -> Result<Response<Empty>, Status> {
         // Wywołanie metody print_top_of_book z rzeczywistego serwisu
-        self.order_book_service.print_top_of_book().await;
+        self.order_book_service.print_top_of_book(&request.into_inner().symbol).await;
 
         Ok(Response::new(Empty {}))
     }
 
     async fn get_top_of_book(
         &self,
-        _request: Request<Empty>,
+        request: Request<SymbolRequest>,
     ) -> Result<Response<ProtoOrderBookTop>, Status> {
-        if let Some(top_of_book) = self.order_book_service.get_top_of_book().await {
-            let response = ProtoOrderBookTop {
-                best_bid: Some(crate::adapters::proto::order_book_proto::OrderBookEntry {
-                    price: top_of_book.best_bid.price,
-                    qty: top_of_book.best_bid.qty,
-                }),
-                best_ask: Some(crate::adapters::proto::order_book_proto::OrderBookEntry {
-                    price: top_of_book.best_ask.price,
-                    qty: top_of_book.best_ask.qty,
-                }),
-            };
-
-            Ok(Response::new(response))
+        let symbol = request.into_inner().symbol;
+        let times = self.order_book_service.data_times(&symbol).map_err(|e| staleness_status(&symbol, e))?;
+        if let Some(top_of_book) = self.order_book_service.get_top_of_book(&symbol).await {
+            let attestation = attestation::attest(|| AttestationPayload::top_of_book(&symbol, &top_of_book));
+            Ok(Response::new(ProtoOrderBookTop {
+                attestation: attestation.map(to_proto_attestation),
+                ..to_proto_top(top_of_book, times)
+            }))
         } else {
             Err(Status::not_found("Order book is empty"))
         }
@@ -82,34 +176,168 @@ impl OrderBookProtoService for MyOrderBookService {
 
     async fn get_full_book(
         &self,
-        _request: Request<Empty>,
+        request: Request<SymbolRequest>,
     ) -> Result<Response<ProtoFullOrderBook>, Status> {
-        if let Some(full_book) = self.order_book_service.get_full_book().await {
-            let response = ProtoFullOrderBook {
-                bids: full_book.bids.unwrap_or_default().iter().map(|b| {
-                    crate::adapters::proto::order_book_proto::OrderBookEntry {
-                        price: b.price,
-                        qty: b.qty,
-                    }
-                }).collect(),
-                asks: full_book.asks.unwrap_or_default().iter().map(|a| {
-                    crate::adapters::proto::order_book_proto::OrderBookEntry {
-                        price: a.price,
-                        qty: a.qty,
-                    }
-                }).collect(),
-            };
-
-            Ok(Response::new(response))
+        let symbol = request.into_inner().symbol;
+        let times = self.order_book_service.data_times(&symbol).map_err(|e| staleness_status(&symbol, e))?;
+        if let Some(full_book) = self.order_book_service.get_full_book(&symbol).await {
+            Ok(Response::new(to_proto_full_book(full_book, times)))
         } else {
             Err(Status::not_found("Full order book is empty"))
         }
     }
+
+    type SubscribeTopOfBookStream = UpdateStream<ProtoOrderBookTop>;
+
+    async fn subscribe_top_of_book(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<Self::SubscribeTopOfBookStream>, Status> {
+        let symbol = subscription_symbol(request)
+            .ok_or_else(|| Status::invalid_argument("Symbol is required"))?;
+
+        // Subscribe before reading the current state so no update is missed
+        let updates = self.order_book_service.subscribe();
+        let initial = self.order_book_service.get_top_of_book(&symbol).await
+            .map(|top| to_proto_top(top, self.order_book_service.last_times(&symbol).unwrap_or_default()));
+
+        // Streamed updates are sent as they arrive, they carry their receive time but are never refused as stale
+        let order_book_service = self.order_book_service.clone();
+        let stream = forward_updates(updates, initial, CONFIG.default.grpc_stream_buffer, self.shutdown.clone(), move |update: OrderBookUpdate| {
+            let top = if update.symbol == symbol {
+                update.top.map(|top| to_proto_top(top, order_book_service.last_times(&symbol).unwrap_or_default()))
+            } else {
+                None
+            };
+            async move { top }
+        });
+        Ok(Response::new(stream))
+    }
+
+    type SubscribeBookDepthStream = UpdateStream<ProtoFullOrderBook>;
+
+    async fn subscribe_book_depth(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<Self::SubscribeBookDepthStream>, Status> {
+        let symbol = subscription_symbol(request)
+            .ok_or_else(|| Status::invalid_argument("Symbol is required"))?;
+
+        let updates = self.order_book_service.subscribe();
+        let initial = self.order_book_service.get_full_book(&symbol).await
+            .map(|full_book| to_proto_full_book(full_book, self.order_book_service.last_times(&symbol).unwrap_or_default()));
+
+        // Updates only announce the change, the depth is read from the service when it is sent
+        let order_book_service = self.order_book_service.clone();
+        let stream = forward_updates(updates, initial, CONFIG.default.grpc_stream_buffer, self.shutdown.clone(), move |update: OrderBookUpdate| {
+            let relevant = update.symbol == symbol;
+            let order_book_service = order_book_service.clone();
+            async move {
+                if !relevant {
+                    return None;
+                }
+                let times = order_book_service.last_times(&update.symbol).unwrap_or_default();
+                order_book_service.get_full_book(&update.symbol).await.map(|full_book| to_proto_full_book(full_book, times))
+            }
+        });
+        Ok(Response::new(stream))
+    }
+
+    async fn get_analytics(
+        &self,
+        request: Request<AnalyticsRequest>,
+    ) -> Result<Response<ProtoBookAnalytics>, Status> {
+        let request = request.into_inner();
+        // Empty strings leave the band unset
+        let band_bps = Some(request.band_bps).filter(|bps| !bps.is_empty()).map(|bps| bps.parse())
+            .transpose().map_err(|_| Status::invalid_argument("band_bps has to be a decimal"))?;
+        let band_pct = Some(request.band_pct).filter(|pct| !pct.is_empty()).map(|pct| pct.parse())
+            .transpose().map_err(|_| Status::invalid_argument("band_pct has to be a decimal"))?;
+        let params = BookAnalyticsParams::new(Some(request.levels as usize), band_bps, band_pct)
+            .map_err(Status::invalid_argument)?;
+
+        let times = self.order_book_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        match self.order_book_service.analytics(&request.symbol, &params).await {
+            Some(analytics) => Ok(Response::new(to_proto_analytics(normalize_symbol(&request.symbol), analytics, times))),
+            None => Err(Status::not_found("Order book is empty")),
+        }
+    }
+
+    async fn estimate_impact(
+        &self,
+        request: Request<ImpactRequest>,
+    ) -> Result<Response<ProtoImpactEstimate>, Status> {
+        let request = request.into_inner();
+        // Empty strings leave the amount unset
+        let qty = Some(request.qty).filter(|qty| !qty.is_empty()).map(|qty| qty.parse())
+            .transpose().map_err(|_| Status::invalid_argument("qty has to be a decimal"))?;
+        let quote_qty = Some(request.quote_qty).filter(|quote_qty| !quote_qty.is_empty()).map(|quote_qty| quote_qty.parse())
+            .transpose().map_err(|_| Status::invalid_argument("quote_qty has to be a decimal"))?;
+        let order = ImpactOrder::new(&request.side, qty, quote_qty)
+            .map_err(Status::invalid_argument)?;
+
+        let times = self.order_book_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let estimate = self.order_book_service.impact(&request.symbol, &order).await
+            .ok_or_else(|| Status::not_found("Order book is empty"))?;
+        Ok(Response::new(ProtoImpactEstimate {
+            symbol: normalize_symbol(&request.symbol),
+            side: estimate.side.to_string(),
+            filled_qty: estimate.filled_qty.to_string(),
+            filled_quote: estimate.filled_quote.to_string(),
+            average_price: estimate.average_price.to_string(),
+            worst_price: estimate.worst_price.to_string(),
+            mid: estimate.mid.to_string(),
+            slippage_bps: estimate.slippage_bps.to_string(),
+            levels_consumed: estimate.levels_consumed as u32,
+            insufficient_depth: estimate.insufficient_depth,
+            event_time: times.event_time,
+            received_at: times.received_at,
+        }))
+    }
 }
 
 // Funkcja do tworzenia serwisu gRPC z prawdziwą implementacją
 pub fn create_order_book_service(
-    order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>
+    order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+    shutdown: CancellationToken,
 ) -> OrderBookProtoServiceServer<MyOrderBookService> {
-    OrderBookProtoServiceServer::new(MyOrderBookService::new(order_book_service))
-}
\ No newline at end of file
+    OrderBookProtoServiceServer::new(MyOrderBookService::new(order_book_service).with_shutdown(shutdown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::order_book::DepthData;
+    use crate::domain::services::order_book_service::OrderBookService;
+
+    // Book of E2EOB: bids 2 at 100.5 and 3 at 100, asks 4 at 101.5
+    async fn book() -> Arc<dyn OrderBookServiceTrait + Send + Sync> {
+        let service = Arc::new(OrderBookService::new());
+        service.apply_snapshot(&DepthData {
+            last_update_id: 2,
+            bids: vec![["100.5".to_string(), "2".to_string()], ["100".to_string(), "3".to_string()]],
+            asks: vec![["101.5".to_string(), "4".to_string()]],
+        }.to_snapshot("E2EOB")).await;
+        service
+    }
+
+    #[tokio::test]
+    async fn test_book_analytics_are_served() {
+        let grpc = MyOrderBookService::new(book().await);
+
+        let request = AnalyticsRequest { symbol: "E2EOB".to_string(), levels: 2, band_bps: "10".to_string(), ..Default::default() };
+        let analytics = grpc.get_analytics(Request::new(request)).await.unwrap().into_inner();
+        assert_eq!(analytics.imbalance.unwrap().bid_qty, "5");
+        assert!(grpc.get_analytics(Request::new(AnalyticsRequest { symbol: "E2EOB".to_string(), band_bps: "x".to_string(), ..Default::default() })).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_impact_estimates_are_served() {
+        let grpc = MyOrderBookService::new(book().await);
+
+        // Buying 10 exhausts the 4 on the ask side
+        let request = ImpactRequest { symbol: "E2EOB".to_string(), side: "buy".to_string(), qty: "10".to_string(), ..Default::default() };
+        let estimate = grpc.estimate_impact(Request::new(request)).await.unwrap().into_inner();
+        assert_eq!((estimate.filled_qty.as_str(), estimate.insufficient_depth), ("4", true));
+    }
+}
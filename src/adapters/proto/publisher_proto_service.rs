@@ -0,0 +1,98 @@
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use crate::adapters::proto::publisher_proto::publisher_proto_service_server::{PublisherProtoService, PublisherProtoServiceServer};
+use crate::adapters::proto::publisher_proto::{Publication as ProtoPublication, PublicationLog, PublicationLogRequest, SymbolRequest};
+use crate::adapters::proto::update_stream::{forward_updates, UpdateStream};
+use crate::config::CONFIG;
+use crate::domain::entities::publication::Publication;
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::publisher_service::{PublisherService, DEFAULT_PUBLICATION_LIMIT};
+use tokio_util::sync::CancellationToken;
+
+pub struct MyPublisherService {
+    publisher_service: Arc<PublisherService>,
+    shutdown: CancellationToken, // Ends the update streams
+}
+
+impl MyPublisherService {
+    pub fn new(publisher_service: Arc<PublisherService>) -> Self {
+        Self { publisher_service, shutdown: CancellationToken::new() }
+    }
+
+    // Update streams end when `shutdown` is cancelled
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+// Map a Publication (Rust) to its proto message
+fn to_proto_publication(publication: Publication) -> ProtoPublication {
+    ProtoPublication {
+        symbol: publication.symbol,
+        sequence: publication.sequence,
+        source: publication.source.to_string(),
+        price: publication.price.to_string(),
+        reason: publication.reason.to_string(),
+        deviation_bps: publication.deviation_bps.to_string(),
+        published_at: publication.published_at,
+    }
+}
+
+#[tonic::async_trait]
+impl PublisherProtoService for MyPublisherService {
+    async fn get_latest_publication(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<ProtoPublication>, Status> {
+        let symbol = request.into_inner().symbol;
+        let publication = self.publisher_service.latest(&symbol).await
+            .ok_or_else(|| Status::not_found(format!("No publication for symbol {}", symbol)))?;
+        Ok(Response::new(to_proto_publication(publication)))
+    }
+
+    async fn get_publication_log(
+        &self,
+        request: Request<PublicationLogRequest>,
+    ) -> Result<Response<PublicationLog>, Status> {
+        let request = request.into_inner();
+        let limit = match request.limit {
+            0 => DEFAULT_PUBLICATION_LIMIT,
+            limit => limit as usize,
+        };
+        let publications = self.publisher_service.publication_log(&request.symbol, limit).await
+            .ok_or_else(|| Status::not_found(format!("No publisher for symbol {}", request.symbol)))?;
+        Ok(Response::new(PublicationLog {
+            publications: publications.into_iter().map(to_proto_publication).collect(),
+        }))
+    }
+
+    type SubscribePublicationsStream = UpdateStream<ProtoPublication>;
+
+    async fn subscribe_publications(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<Self::SubscribePublicationsStream>, Status> {
+        let symbol = normalize_symbol(&request.into_inner().symbol);
+        if self.publisher_service.policy(&symbol).await.is_none() {
+            return Err(Status::not_found(format!("No publisher for symbol {}", symbol)));
+        }
+
+        // Subscribe before reading the last publication so none is missed
+        let updates = self.publisher_service.subscribe();
+        let initial = self.publisher_service.latest(&symbol).await.map(to_proto_publication);
+
+        let stream = forward_updates(updates, initial, CONFIG.default.grpc_stream_buffer, self.shutdown.clone(), move |publication: Publication| {
+            let relevant = publication.symbol == symbol;
+            async move { relevant.then(|| to_proto_publication(publication)) }
+        });
+        Ok(Response::new(stream))
+    }
+}
+
+pub fn create_publisher_service(
+    publisher_service: Arc<PublisherService>,
+    shutdown: CancellationToken,
+) -> PublisherProtoServiceServer<MyPublisherService> {
+    PublisherProtoServiceServer::new(MyPublisherService::new(publisher_service).with_shutdown(shutdown))
+}
@@ -0,0 +1,13 @@
+use tonic::Status;
+use crate::config::CONFIG;
+use crate::domain::entities::freshness::Staleness;
+
+// Status of a request for market data that was never received or is too old to be served.
+// Symbols that are not one of the trading pairs are not found instead.
+pub fn staleness_status(symbol: &str, staleness: Staleness) -> Status {
+    match staleness {
+        Staleness::NoData if !CONFIG.default.is_trading_pair(symbol) => Status::not_found(format!("No data for symbol {}", symbol)),
+        Staleness::NoData => Status::unavailable(format!("No data has been received for symbol {}", symbol)),
+        stale => Status::failed_precondition(format!("{}: {}", symbol, stale)),
+    }
+}
@@ -2,6 +2,12 @@
 /// Empty message
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct Empty {}
+/// Message selecting the trading pair (e.g. BTCFDUSD)
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SymbolRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+}
 /// Message representing TradeSD (corresponding to TradeSD in the Rust entity)
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TradeSd {
@@ -32,23 +38,176 @@ pub struct TradeData {
     #[prost(bool, tag = "9")]
     pub ignore: bool,
 }
-/// Message representing the average trade volume
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+/// Message representing the average trade volume (decimal string)
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AverageVolume {
-    #[prost(double, tag = "1")]
-    pub average_volume: f64,
+    #[prost(string, tag = "1")]
+    pub average_volume: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub window_secs: u64,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "3")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "4")]
+    pub received_at: u64,
 }
-/// Message representing the total trade volume
-#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+/// Message representing the total trade volume (decimal string)
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TotalVolume {
-    #[prost(double, tag = "1")]
-    pub total_volume: f64,
+    #[prost(string, tag = "1")]
+    pub total_volume: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub window_secs: u64,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "3")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "4")]
+    pub received_at: u64,
+}
+/// Window in seconds, one of reference_price_windows_secs (VWAP) or trade_stats_windows_secs (volumes, aggressor flow),
+/// 0 means the first configured window
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WindowRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub window_secs: u64,
+}
+/// Message selecting the candles of a trading pair
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CandlesRequest {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    /// 1s, 1m, 5m, 15m or 1h
+    #[prost(string, tag = "2")]
+    pub interval: ::prost::alloc::string::String,
+    /// Maximum number of candles (GetCandles only), 0 means 100
+    #[prost(uint32, tag = "3")]
+    pub limit: u32,
+}
+/// OHLCV candle built from the trades, times are trade times in ms, numbers are decimal strings
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Candle {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub interval: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub open_time: u64,
+    #[prost(uint64, tag = "4")]
+    pub close_time: u64,
+    #[prost(string, tag = "5")]
+    pub open: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub high: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub low: ::prost::alloc::string::String,
+    #[prost(string, tag = "8")]
+    pub close: ::prost::alloc::string::String,
+    #[prost(string, tag = "9")]
+    pub volume: ::prost::alloc::string::String,
+    #[prost(string, tag = "10")]
+    pub quote_volume: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "11")]
+    pub trade_count: u64,
+    #[prost(string, tag = "12")]
+    pub taker_buy_volume: ::prost::alloc::string::String,
+    #[prost(bool, tag = "13")]
+    pub closed: bool,
+}
+/// Message representing a list of candles, oldest first
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Candles {
+    #[prost(message, repeated, tag = "1")]
+    pub candles: ::prost::alloc::vec::Vec<Candle>,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "2")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "3")]
+    pub received_at: u64,
+}
+/// Prices and volumes are decimal strings, coverage is the share of the window observed (0 to 1)
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Vwap {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub window_secs: u64,
+    #[prost(uint64, tag = "3")]
+    pub sample_count: u64,
+    #[prost(double, tag = "4")]
+    pub coverage: f64,
+    #[prost(string, tag = "5")]
+    pub vwap: ::prost::alloc::string::String,
+    #[prost(string, tag = "6")]
+    pub volume: ::prost::alloc::string::String,
+    /// Only set when signing is configured
+    #[prost(message, optional, tag = "7")]
+    pub attestation: ::core::option::Option<super::attestation_proto::Attestation>,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "8")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "9")]
+    pub received_at: u64,
+}
+/// Volumes and notionals are decimal strings. The imbalance is (buy - sell) / (buy + sell)
+/// from -1 to 1, empty when nothing was traded.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AggressorFlow {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub buy_volume: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub sell_volume: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "4")]
+    pub buy_count: u64,
+    #[prost(uint64, tag = "5")]
+    pub sell_count: u64,
+    #[prost(string, tag = "6")]
+    pub buy_notional: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub sell_notional: ::prost::alloc::string::String,
+    #[prost(string, tag = "8")]
+    pub volume_delta: ::prost::alloc::string::String,
+    #[prost(string, tag = "9")]
+    pub imbalance: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "10")]
+    pub window_secs: u64,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "11")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "12")]
+    pub received_at: u64,
+}
+/// Decimal string, since is the startup or reset time in ms since the UNIX epoch
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CumulativeVolumeDelta {
+    #[prost(string, tag = "1")]
+    pub symbol: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub cumulative_volume_delta: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub since: u64,
+    /// Exchange event time in ms, unset when the stream has none
+    #[prost(uint64, optional, tag = "4")]
+    pub event_time: ::core::option::Option<u64>,
+    /// Local receive time in ms
+    #[prost(uint64, tag = "5")]
+    pub received_at: u64,
 }
 /// Generated client implementations.
 pub mod trade_history_proto_service_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    /// Market data getters fail with UNAVAILABLE until the first update of a trading pair was received and with
+    /// FAILED_PRECONDITION once it is older than the configured maximum age, NOT_FOUND is left for unknown pairs
     /// gRPC service
     #[derive(Debug, Clone)]
     pub struct TradeHistoryProtoServiceClient<T> {
@@ -160,10 +319,10 @@ pub mod trade_history_proto_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
-        /// Returns the average volume per trade within the last 60 seconds
+        /// Returns the average volume per trade within a window
         pub async fn get_average_volume_per_trade(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::WindowRequest>,
         ) -> std::result::Result<tonic::Response<super::AverageVolume>, tonic::Status> {
             self.inner
                 .ready()
@@ -188,10 +347,10 @@ pub mod trade_history_proto_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
-        /// Returns the total volume of trades within the last 60 seconds
+        /// Returns the total volume of trades within a window
         pub async fn get_total_volume(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::WindowRequest>,
         ) -> std::result::Result<tonic::Response<super::TotalVolume>, tonic::Status> {
             self.inner
                 .ready()
@@ -216,6 +375,216 @@ pub mod trade_history_proto_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Streams every trade as it is added. Trades a slow client cannot keep up with
+        /// are dropped, the stream continues with the latest ones.
+        pub async fn subscribe_trades(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::TradeData>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/SubscribeTrades",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "SubscribeTrades",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Returns the most recent candles of an interval, oldest first. The last one may still be open.
+        pub async fn get_candles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CandlesRequest>,
+        ) -> std::result::Result<tonic::Response<super::Candles>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/GetCandles",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "GetCandles",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Streams the candles of an interval as they change: the open candle after every trade
+        /// and every candle once it closed. Slow clients lose the oldest updates.
+        pub async fn subscribe_candles(
+            &mut self,
+            request: impl tonic::IntoRequest<super::CandlesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::Candle>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/SubscribeCandles",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "SubscribeCandles",
+                    ),
+                );
+            self.inner.server_streaming(req, path, codec).await
+        }
+        /// Returns the volume-weighted average price of the trades in a window
+        pub async fn get_vwap(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WindowRequest>,
+        ) -> std::result::Result<tonic::Response<super::Vwap>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/GetVwap",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "GetVwap",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the volume of the trades within a window split by aggressor side
+        pub async fn get_aggressor_flow(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WindowRequest>,
+        ) -> std::result::Result<tonic::Response<super::AggressorFlow>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/GetAggressorFlow",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "GetAggressorFlow",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns the buy minus sell volume of every trade since startup or the last reset
+        pub async fn get_cumulative_volume_delta(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CumulativeVolumeDelta>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/GetCumulativeVolumeDelta",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "GetCumulativeVolumeDelta",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
+        /// Starts the cumulative volume delta over from zero, returns the delta up to the reset
+        pub async fn reset_cumulative_volume_delta(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CumulativeVolumeDelta>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/trade_history_proto.TradeHistoryProtoService/ResetCumulativeVolumeDelta",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "trade_history_proto.TradeHistoryProtoService",
+                        "ResetCumulativeVolumeDelta",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -230,17 +599,80 @@ pub mod trade_history_proto_service_server {
             &self,
             request: tonic::Request<super::TradeSd>,
         ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status>;
-        /// Returns the average volume per trade within the last 60 seconds
+        /// Returns the average volume per trade within a window
         async fn get_average_volume_per_trade(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::WindowRequest>,
         ) -> std::result::Result<tonic::Response<super::AverageVolume>, tonic::Status>;
-        /// Returns the total volume of trades within the last 60 seconds
+        /// Returns the total volume of trades within a window
         async fn get_total_volume(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::WindowRequest>,
         ) -> std::result::Result<tonic::Response<super::TotalVolume>, tonic::Status>;
+        /// Server streaming response type for the SubscribeTrades method.
+        type SubscribeTradesStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::TradeData, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams every trade as it is added. Trades a slow client cannot keep up with
+        /// are dropped, the stream continues with the latest ones.
+        async fn subscribe_trades(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeTradesStream>,
+            tonic::Status,
+        >;
+        /// Returns the most recent candles of an interval, oldest first. The last one may still be open.
+        async fn get_candles(
+            &self,
+            request: tonic::Request<super::CandlesRequest>,
+        ) -> std::result::Result<tonic::Response<super::Candles>, tonic::Status>;
+        /// Server streaming response type for the SubscribeCandles method.
+        type SubscribeCandlesStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::Candle, tonic::Status>,
+            >
+            + std::marker::Send
+            + 'static;
+        /// Streams the candles of an interval as they change: the open candle after every trade
+        /// and every candle once it closed. Slow clients lose the oldest updates.
+        async fn subscribe_candles(
+            &self,
+            request: tonic::Request<super::CandlesRequest>,
+        ) -> std::result::Result<
+            tonic::Response<Self::SubscribeCandlesStream>,
+            tonic::Status,
+        >;
+        /// Returns the volume-weighted average price of the trades in a window
+        async fn get_vwap(
+            &self,
+            request: tonic::Request<super::WindowRequest>,
+        ) -> std::result::Result<tonic::Response<super::Vwap>, tonic::Status>;
+        /// Returns the volume of the trades within a window split by aggressor side
+        async fn get_aggressor_flow(
+            &self,
+            request: tonic::Request<super::WindowRequest>,
+        ) -> std::result::Result<tonic::Response<super::AggressorFlow>, tonic::Status>;
+        /// Returns the buy minus sell volume of every trade since startup or the last reset
+        async fn get_cumulative_volume_delta(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CumulativeVolumeDelta>,
+            tonic::Status,
+        >;
+        /// Starts the cumulative volume delta over from zero, returns the delta up to the reset
+        async fn reset_cumulative_volume_delta(
+            &self,
+            request: tonic::Request<super::SymbolRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::CumulativeVolumeDelta>,
+            tonic::Status,
+        >;
     }
+    /// Market data getters fail with UNAVAILABLE until the first update of a trading pair was received and with
+    /// FAILED_PRECONDITION once it is older than the configured maximum age, NOT_FOUND is left for unknown pairs
     /// gRPC service
     #[derive(Debug)]
     pub struct TradeHistoryProtoServiceServer<T> {
@@ -371,7 +803,7 @@ pub mod trade_history_proto_service_server {
                     );
                     impl<
                         T: TradeHistoryProtoService,
-                    > tonic::server::UnaryService<super::Empty>
+                    > tonic::server::UnaryService<super::WindowRequest>
                     for GetAverageVolumePerTradeSvc<T> {
                         type Response = super::AverageVolume;
                         type Future = BoxFuture<
@@ -380,7 +812,7 @@ pub mod trade_history_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::WindowRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -420,7 +852,7 @@ pub mod trade_history_proto_service_server {
                     struct GetTotalVolumeSvc<T: TradeHistoryProtoService>(pub Arc<T>);
                     impl<
                         T: TradeHistoryProtoService,
-                    > tonic::server::UnaryService<super::Empty>
+                    > tonic::server::UnaryService<super::WindowRequest>
                     for GetTotalVolumeSvc<T> {
                         type Response = super::TotalVolume;
                         type Future = BoxFuture<
@@ -429,7 +861,7 @@ pub mod trade_history_proto_service_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::WindowRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -464,6 +896,352 @@ pub mod trade_history_proto_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/trade_history_proto.TradeHistoryProtoService/SubscribeTrades" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeTradesSvc<T: TradeHistoryProtoService>(pub Arc<T>);
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::ServerStreamingService<super::SymbolRequest>
+                    for SubscribeTradesSvc<T> {
+                        type Response = super::TradeData;
+                        type ResponseStream = T::SubscribeTradesStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::subscribe_trades(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SubscribeTradesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/trade_history_proto.TradeHistoryProtoService/GetCandles" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCandlesSvc<T: TradeHistoryProtoService>(pub Arc<T>);
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::UnaryService<super::CandlesRequest>
+                    for GetCandlesSvc<T> {
+                        type Response = super::Candles;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CandlesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::get_candles(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetCandlesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/trade_history_proto.TradeHistoryProtoService/SubscribeCandles" => {
+                    #[allow(non_camel_case_types)]
+                    struct SubscribeCandlesSvc<T: TradeHistoryProtoService>(pub Arc<T>);
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::ServerStreamingService<super::CandlesRequest>
+                    for SubscribeCandlesSvc<T> {
+                        type Response = super::Candle;
+                        type ResponseStream = T::SubscribeCandlesStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::CandlesRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::subscribe_candles(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SubscribeCandlesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/trade_history_proto.TradeHistoryProtoService/GetVwap" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetVwapSvc<T: TradeHistoryProtoService>(pub Arc<T>);
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::UnaryService<super::WindowRequest>
+                    for GetVwapSvc<T> {
+                        type Response = super::Vwap;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WindowRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::get_vwap(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetVwapSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/trade_history_proto.TradeHistoryProtoService/GetAggressorFlow" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetAggressorFlowSvc<T: TradeHistoryProtoService>(pub Arc<T>);
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::UnaryService<super::WindowRequest>
+                    for GetAggressorFlowSvc<T> {
+                        type Response = super::AggressorFlow;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WindowRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::get_aggressor_flow(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetAggressorFlowSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/trade_history_proto.TradeHistoryProtoService/GetCumulativeVolumeDelta" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetCumulativeVolumeDeltaSvc<T: TradeHistoryProtoService>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for GetCumulativeVolumeDeltaSvc<T> {
+                        type Response = super::CumulativeVolumeDelta;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::get_cumulative_volume_delta(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetCumulativeVolumeDeltaSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/trade_history_proto.TradeHistoryProtoService/ResetCumulativeVolumeDelta" => {
+                    #[allow(non_camel_case_types)]
+                    struct ResetCumulativeVolumeDeltaSvc<T: TradeHistoryProtoService>(
+                        pub Arc<T>,
+                    );
+                    impl<
+                        T: TradeHistoryProtoService,
+                    > tonic::server::UnaryService<super::SymbolRequest>
+                    for ResetCumulativeVolumeDeltaSvc<T> {
+                        type Response = super::CumulativeVolumeDelta;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SymbolRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as TradeHistoryProtoService>::reset_cumulative_volume_delta(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = ResetCumulativeVolumeDeltaSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
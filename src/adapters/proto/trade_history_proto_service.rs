@@ -1,9 +1,21 @@
 use tonic::{Request, Response, Status};
 use crate::adapters::proto::trade_history_proto::trade_history_proto_service_server::{TradeHistoryProtoService, TradeHistoryProtoServiceServer};
-use crate::adapters::proto::trade_history_proto::{Empty, TradeSd as ProtoTradeSD, AverageVolume, TotalVolume};
-use crate::domain::entities::trade::{TradeData, TradeSD};
-use crate::domain::services::trade_history_service::TradeHistoryService;
+use crate::adapters::proto::trade_history_proto::{Empty, SymbolRequest, TradeData as ProtoTradeData, TradeSd as ProtoTradeSD, AverageVolume, TotalVolume, CandlesRequest, Candle as ProtoCandle, Candles, WindowRequest, Vwap,
+    AggressorFlow as ProtoAggressorFlow, CumulativeVolumeDelta as ProtoCumulativeVolumeDelta};
+use crate::adapters::proto::attestation_proto;
+use crate::adapters::proto::attestation_proto_service::to_proto_attestation;
+use crate::adapters::proto::staleness::staleness_status;
+use crate::adapters::proto::update_stream::{forward_updates, UpdateStream};
+use crate::attestation::{self, AttestationPayload};
+use crate::config::CONFIG;
+use crate::domain::entities::candle::{Candle, CandleInterval};
+use crate::domain::entities::freshness::DataTimes;
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::entities::trade::{CumulativeVolumeDelta, TradeData, TradeSD};
+use crate::domain::services::trade_history_service::{TradeHistoryService, DEFAULT_CANDLE_LIMIT};
+use rust_decimal::Decimal;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 pub mod trade_history_proto {
     tonic::include_proto!("trade_history_proto"); // gRPC generated code
@@ -11,11 +23,62 @@ pub mod trade_history_proto {
 
 pub struct MyTradeHistoryService {
     trade_history_service: Arc<TradeHistoryService>,
+    shutdown: CancellationToken, // Ends the update streams
 }
 
 impl MyTradeHistoryService {
     pub fn new(trade_history_service: Arc<TradeHistoryService>) -> Self {
-        Self { trade_history_service }
+        Self { trade_history_service, shutdown: CancellationToken::new() }
+    }
+
+    // Update streams end when `shutdown` is cancelled
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+// Map TradeData (Rust) to TradeData (proto)
+fn to_proto_trade(data: TradeData) -> ProtoTradeData {
+    ProtoTradeData {
+        event_type: data.event_type,
+        event_time: data.event_time,
+        symbol: data.symbol,
+        trade_id: data.trade_id,
+        price: data.price.to_string(),
+        quantity: data.quantity.to_string(),
+        trade_time: data.trade_time,
+        is_buyer_market_maker: data.is_buyer_market_maker,
+        ignore: data.ignore,
+    }
+}
+
+// Map Candle (Rust) to Candle (proto)
+fn to_proto_candle(candle: Candle) -> ProtoCandle {
+    ProtoCandle {
+        symbol: candle.symbol,
+        interval: candle.interval.to_string(),
+        open_time: candle.open_time,
+        close_time: candle.close_time,
+        open: candle.open.to_string(),
+        high: candle.high.to_string(),
+        low: candle.low.to_string(),
+        close: candle.close.to_string(),
+        volume: candle.volume.to_string(),
+        quote_volume: candle.quote_volume.to_string(),
+        trade_count: candle.trade_count,
+        taker_buy_volume: candle.taker_buy_volume.to_string(),
+        closed: candle.closed,
+    }
+}
+
+fn to_proto_cvd(symbol: &str, cvd: CumulativeVolumeDelta, times: DataTimes) -> ProtoCumulativeVolumeDelta {
+    ProtoCumulativeVolumeDelta {
+        symbol: normalize_symbol(symbol),
+        cumulative_volume_delta: cvd.delta.to_string(),
+        since: cvd.since,
+        event_time: times.event_time,
+        received_at: times.received_at,
     }
 }
 
@@ -28,6 +91,11 @@ impl TradeHistoryProtoService for MyTradeHistoryService {
         let trade_sd = request.into_inner();
 
         if let Some(data) = trade_sd.data {
+            let price = data.price.parse::<Decimal>()
+                .map_err(|e| Status::invalid_argument(format!("Invalid trade price {}: {}", data.price, e)))?;
+            let quantity = data.quantity.parse::<Decimal>()
+                .map_err(|e| Status::invalid_argument(format!("Invalid trade quantity {}: {}", data.quantity, e)))?;
+
             // Map ProtoTradeSD to TradeSD
             let trade = TradeSD {
                 stream: trade_sd.stream,
@@ -36,8 +104,8 @@ impl TradeHistoryProtoService for MyTradeHistoryService {
                     event_time: data.event_time,
                     symbol: data.symbol,
                     trade_id: data.trade_id,
-                    price: data.price,
-                    quantity: data.quantity,
+                    price,
+                    quantity,
                     trade_time: data.trade_time,
                     is_buyer_market_maker: data.is_buyer_market_maker,
                     ignore: data.ignore,
@@ -45,7 +113,7 @@ impl TradeHistoryProtoService for MyTradeHistoryService {
             };
 
             // Add trade to history
-            self.trade_history_service.add_trade(trade).await;
+            self.trade_history_service.add_trade(&trade.data.to_event()).await;
             Ok(Response::new(Empty {}))
         } else {
             Err(Status::invalid_argument("Trade data is missing"))
@@ -54,28 +122,177 @@ impl TradeHistoryProtoService for MyTradeHistoryService {
 
     async fn get_average_volume_per_trade(
         &self,
-        _request: Request<Empty>,
+        request: Request<WindowRequest>,
     ) -> Result<Response<AverageVolume>, Status> {
-        let average_volume = self.trade_history_service.average_volume_per_trade().await;
+        let request = request.into_inner();
+        let window_secs = CONFIG.default.trade_stats_window(Some(request.window_secs))
+            .map_err(Status::invalid_argument)?;
+        let times = self.trade_history_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let average_volume = self.trade_history_service.average_volume_per_trade(&request.symbol, window_secs).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {}", request.symbol)))?;
         Ok(Response::new(AverageVolume {
-            average_volume,
+            average_volume: average_volume.to_string(),
+            window_secs,
+            event_time: times.event_time,
+            received_at: times.received_at,
         }))
     }
 
     async fn get_total_volume(
         &self,
-        _request: Request<Empty>,
+        request: Request<WindowRequest>,
     ) -> Result<Response<TotalVolume>, Status> {
-        let total_volume = self.trade_history_service.total_volume().await;
+        let request = request.into_inner();
+        let window_secs = CONFIG.default.trade_stats_window(Some(request.window_secs))
+            .map_err(Status::invalid_argument)?;
+        let times = self.trade_history_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let total_volume = self.trade_history_service.total_volume(&request.symbol, window_secs).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {}", request.symbol)))?;
         Ok(Response::new(TotalVolume {
-            total_volume,
+            total_volume: total_volume.to_string(),
+            window_secs,
+            event_time: times.event_time,
+            received_at: times.received_at,
+        }))
+    }
+
+    async fn get_vwap(
+        &self,
+        request: Request<WindowRequest>,
+    ) -> Result<Response<Vwap>, Status> {
+        let request = request.into_inner();
+        let window_secs = CONFIG.default.reference_price_window(Some(request.window_secs))
+            .map_err(Status::invalid_argument)?;
+        let times = self.trade_history_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let vwap = self.trade_history_service.vwap(&request.symbol, window_secs).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {} in the last {}s", request.symbol, window_secs)))?;
+        Ok(Response::new(Vwap {
+            symbol: normalize_symbol(&request.symbol),
+            window_secs: vwap.window_secs,
+            sample_count: vwap.sample_count,
+            coverage: vwap.coverage,
+            vwap: vwap.vwap.to_string(),
+            volume: vwap.volume.to_string(),
+            attestation: attestation::attest(|| AttestationPayload::vwap(&request.symbol, &vwap)).map(to_proto_attestation),
+            event_time: times.event_time,
+            received_at: times.received_at,
+        }))
+    }
+
+    async fn get_aggressor_flow(
+        &self,
+        request: Request<WindowRequest>,
+    ) -> Result<Response<ProtoAggressorFlow>, Status> {
+        let request = request.into_inner();
+        let window_secs = CONFIG.default.trade_stats_window(Some(request.window_secs))
+            .map_err(Status::invalid_argument)?;
+        let times = self.trade_history_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let flow = self.trade_history_service.aggressor_flow(&request.symbol, window_secs).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {}", request.symbol)))?;
+        Ok(Response::new(ProtoAggressorFlow {
+            symbol: normalize_symbol(&request.symbol),
+            buy_volume: flow.buy_volume.to_string(),
+            sell_volume: flow.sell_volume.to_string(),
+            buy_count: flow.buy_count,
+            sell_count: flow.sell_count,
+            buy_notional: flow.buy_notional.to_string(),
+            sell_notional: flow.sell_notional.to_string(),
+            volume_delta: flow.volume_delta().to_string(),
+            imbalance: flow.imbalance().map(|imbalance| imbalance.to_string()).unwrap_or_default(),
+            window_secs,
+            event_time: times.event_time,
+            received_at: times.received_at,
+        }))
+    }
+
+    async fn get_cumulative_volume_delta(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<ProtoCumulativeVolumeDelta>, Status> {
+        let symbol = request.into_inner().symbol;
+        let times = self.trade_history_service.data_times(&symbol).map_err(|e| staleness_status(&symbol, e))?;
+        let cvd = self.trade_history_service.cumulative_volume_delta(&symbol).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {}", symbol)))?;
+        Ok(Response::new(to_proto_cvd(&symbol, cvd, times)))
+    }
+
+    async fn reset_cumulative_volume_delta(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<ProtoCumulativeVolumeDelta>, Status> {
+        let symbol = request.into_inner().symbol;
+        let cvd = self.trade_history_service.reset_cumulative_volume_delta(&symbol).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {}", symbol)))?;
+        // A reset is not refused when the last trade is old, the delta up to the reset is returned either way
+        let times = self.trade_history_service.last_times(&symbol).unwrap_or_default();
+        Ok(Response::new(to_proto_cvd(&symbol, cvd, times)))
+    }
+
+    type SubscribeTradesStream = UpdateStream<ProtoTradeData>;
+
+    async fn subscribe_trades(
+        &self,
+        request: Request<SymbolRequest>,
+    ) -> Result<Response<Self::SubscribeTradesStream>, Status> {
+        let symbol = normalize_symbol(&request.into_inner().symbol);
+        if symbol.is_empty() {
+            return Err(Status::invalid_argument("Symbol is required"));
+        }
+
+        let updates = self.trade_history_service.subscribe();
+        let stream = forward_updates(updates, None, CONFIG.default.grpc_stream_buffer, self.shutdown.clone(), move |trade: TradeData| {
+            let relevant = normalize_symbol(&trade.symbol) == symbol;
+            async move { relevant.then(|| to_proto_trade(trade)) }
+        });
+        Ok(Response::new(stream))
+    }
+
+    async fn get_candles(
+        &self,
+        request: Request<CandlesRequest>,
+    ) -> Result<Response<Candles>, Status> {
+        let request = request.into_inner();
+        let interval = request.interval.parse::<CandleInterval>().map_err(Status::invalid_argument)?;
+        let limit = match request.limit {
+            0 => DEFAULT_CANDLE_LIMIT,
+            limit => limit as usize,
+        };
+        let times = self.trade_history_service.data_times(&request.symbol).map_err(|e| staleness_status(&request.symbol, e))?;
+        let candles = self.trade_history_service.candles(&request.symbol, interval, limit).await
+            .ok_or_else(|| Status::not_found(format!("No trades for symbol {}", request.symbol)))?;
+        Ok(Response::new(Candles {
+            candles: candles.into_iter().map(to_proto_candle).collect(),
+            event_time: times.event_time,
+            received_at: times.received_at,
         }))
     }
+
+    type SubscribeCandlesStream = UpdateStream<ProtoCandle>;
+
+    async fn subscribe_candles(
+        &self,
+        request: Request<CandlesRequest>,
+    ) -> Result<Response<Self::SubscribeCandlesStream>, Status> {
+        let request = request.into_inner();
+        let interval = request.interval.parse::<CandleInterval>().map_err(Status::invalid_argument)?;
+        let symbol = normalize_symbol(&request.symbol);
+        if symbol.is_empty() {
+            return Err(Status::invalid_argument("Symbol is required"));
+        }
+
+        let updates = self.trade_history_service.subscribe_candles();
+        let stream = forward_updates(updates, None, CONFIG.default.grpc_stream_buffer, self.shutdown.clone(), move |candle: Candle| {
+            let relevant = candle.symbol == symbol && candle.interval == interval;
+            async move { relevant.then(|| to_proto_candle(candle)) }
+        });
+        Ok(Response::new(stream))
+    }
 }
 
 // Function to create the gRPC service with the real implementation
 pub fn create_trade_history_service(
     trade_history_service: Arc<TradeHistoryService>,
+    shutdown: CancellationToken,
 ) -> TradeHistoryProtoServiceServer<MyTradeHistoryService> {
-    TradeHistoryProtoServiceServer::new(MyTradeHistoryService::new(trade_history_service))
+    TradeHistoryProtoServiceServer::new(MyTradeHistoryService::new(trade_history_service).with_shutdown(shutdown))
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+use std::future::Future;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tonic::Status;
+
+// Server-streaming response fed from one of the domain update channels
+pub type UpdateStream<T> = ReceiverStream<Result<T, Status>>;
+
+// Forward domain updates to a single gRPC subscriber.
+//
+// Lag/drop policy: every subscriber has its own bounded buffer of `buffer` messages.
+// A client that does not drain it falls behind on the domain channel, and the
+// updates it missed are dropped (oldest first) - the stream then continues with the
+// most recent updates. Ingestion is never blocked by slow clients.
+//
+// The stream ends when `shutdown` is cancelled so subscribers see a clean end instead of a cut connection.
+pub fn forward_updates<U, T, F, Fut>(
+    mut updates: broadcast::Receiver<U>,
+    initial: Option<T>,
+    buffer: usize,
+    shutdown: CancellationToken,
+    mut map: F,
+) -> UpdateStream<T>
+where
+    U: Clone + Send + 'static,
+    T: Send + 'static,
+    F: FnMut(U) -> Fut + Send + 'static,
+    Fut: Future<Output = Option<T>> + Send,
+{
+    let (tx, rx) = mpsc::channel(buffer.max(1));
+
+    tokio::spawn(async move {
+        if let Some(initial) = initial {
+            if tx.send(Ok(initial)).await.is_err() {
+                return;
+            }
+        }
+
+        loop {
+            let update = tokio::select! {
+                update = updates.recv() => update,
+                _ = shutdown.cancelled() => break,
+            };
+            match update {
+                Ok(update) => {
+                    // `None` means the update is not relevant for this subscriber
+                    if let Some(message) = map(update).await {
+                        if tx.send(Ok(message)).await.is_err() {
+                            // Client went away
+                            break;
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("gRPC subscriber is too slow, dropped {} updates", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    ReceiverStream::new(rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_slow_subscriber_drops_oldest_updates() {
+        let (sender, receiver) = broadcast::channel::<u32>(4);
+        let stream = forward_updates(receiver, Some(0), 1, CancellationToken::new(), |update: u32| async move { Some(update) });
+
+        // The subscriber does not read while 100 updates are published, sending must not block
+        for update in 1..=100 {
+            let _ = sender.send(update);
+        }
+        drop(sender);
+
+        let received: Vec<u32> = stream.map(|message| message.unwrap()).collect().await;
+        assert_eq!(received.first(), Some(&0));
+        assert_eq!(received.last(), Some(&100));
+        assert!(received.len() < 100);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_on_shutdown() {
+        let (sender, receiver) = broadcast::channel::<u32>(4);
+        let shutdown = CancellationToken::new();
+        let mut stream = forward_updates(receiver, Some(0), 4, shutdown.clone(), |update: u32| async move { Some(update) });
+        assert_eq!(stream.next().await.unwrap().unwrap(), 0);
+
+        // The domain channel is still open, only the shutdown ends the stream
+        shutdown.cancel();
+        assert!(stream.next().await.is_none());
+        drop(sender);
+    }
+}
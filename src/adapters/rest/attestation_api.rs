@@ -0,0 +1,67 @@
+use warp::Filter;
+use crate::adapters::rest::service_error::ServiceError;
+use crate::attestation::{self, Attestation, AttestationSigner, ALGORITHM};
+
+// Routes of the node's signer, `None` when signing is not configured
+pub fn create_attestation_api(
+    signer: Option<&'static AttestationSigner>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Route to get the public key attestations are signed with, consumers pin it to verify signatures
+    let public_key = warp::path!("attestation" / "public_key")
+        .and(warp::get())
+        .and_then(move || async move {
+            match signer {
+                Some(signer) => Ok(warp::reply::json(&serde_json::json!({
+                    "algorithm": ALGORITHM,
+                    "public_key": signer.public_key_hex(),
+                }))),
+                None => Err(warp::reject::custom(ServiceError::SigningDisabled))
+            }
+        });
+
+    // Route to check an attestation against the node's key, for consumers without an ed25519 implementation
+    let verify = warp::path!("attestation" / "verify")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(move |attestation: Attestation| async move {
+            let Some(signer) = signer else {
+                return Err(warp::reject::custom(ServiceError::SigningDisabled));
+            };
+            Ok(warp::reply::json(&match attestation::verify(&attestation, &signer.public_key_hex()) {
+                Ok(()) => serde_json::json!({"valid": true}),
+                Err(reason) => serde_json::json!({"valid": false, "reason": reason}),
+            }))
+        });
+
+    public_key.or(verify)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::http::StatusCode;
+    use crate::adapters::rest::service_error::handle_rejection;
+    use crate::attestation::{AttestationPayload, KIND_MID_PRICE};
+
+    #[tokio::test]
+    async fn test_attestations_are_verified_against_the_node_key() {
+        let signer: &'static AttestationSigner = Box::leak(Box::new(AttestationSigner::from_seed_hex(&"22".repeat(32)).unwrap()));
+        let api = create_attestation_api(Some(signer)).recover(handle_rejection);
+        let attestation = signer.sign(AttestationPayload::new(KIND_MID_PRICE, "BTCFDUSD", &[("mid_price", "100".to_string())], vec![7]));
+        let verify = |attestation: &Attestation| warp::test::request().method("POST").path("/attestation/verify").json(attestation).reply(&api);
+
+        let response = verify(&attestation).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(response.body()).unwrap(), serde_json::json!({"valid": true}));
+
+        // Signed by another key that claims to be its own: the node's key is the one checked
+        let other = AttestationSigner::from_seed_hex(&"33".repeat(32)).unwrap();
+        let forged = other.sign(attestation.payload.clone());
+        let body: serde_json::Value = serde_json::from_slice(verify(&forged).await.body()).unwrap();
+        assert_eq!((&body["valid"], &body["reason"]), (&false.into(), &"The signature does not match the payload".into()));
+
+        let disabled = create_attestation_api(None).recover(handle_rejection);
+        let response = warp::test::request().method("POST").path("/attestation/verify").json(&attestation).reply(&disabled).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,49 +1,103 @@
 use std::sync::Arc;
 use warp::Filter;
-use crate::domain::services::book_ticker_service::{BookTickerServiceTrait, BookTickerService};
+use crate::adapters::rest::service_error::ServiceError;
+use crate::adapters::rest::{fresh, with_attestation, with_service, with_times, WindowQuery};
+use crate::attestation::AttestationPayload;
+use crate::config::CONFIG;
+use crate::domain::entities::book_ticker::BookTickerData;
+use crate::domain::entities::reference_price::Twap;
+use crate::domain::services::book_ticker_service::BookTickerServiceTrait;
 
-pub fn create_book_ticker_rest_api() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    // Route to get the full book ticker data
-    let book_ticker_data = warp::path!("bookticker" / "data")
-        .and_then(move || async move {
-            let service = Arc::new(BookTickerService);
-            let ticker_data = service.get_ticker_data().await;
+// JSON shape of the book ticker data, shared with the WebSocket push API
+pub fn book_ticker_json(ticker_data: &BookTickerData) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": ticker_data.symbol,
+        "update_id": ticker_data.update_id,
+        "best_bid_price": ticker_data.best_bid_price,
+        "best_bid_qty": ticker_data.best_bid_qty,
+        "best_ask_price": ticker_data.best_ask_price,
+        "best_ask_qty": ticker_data.best_ask_qty,
+    })
+}
+
+// JSON shape of the time-weighted prices
+pub fn twap_json(symbol: &str, twap: &Twap) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": symbol,
+        "window_secs": twap.window_secs,
+        "sample_count": twap.sample_count,
+        "coverage": twap.coverage,
+        "twap_mid": twap.mid,
+        "twap_microprice": twap.microprice,
+    })
+}
 
-            // Return the full book ticker data
-            Ok(warp::reply::json(&serde_json::json!({
-                "symbol": ticker_data.symbol,
-                "update_id": ticker_data.update_id,
-                "best_bid_price": ticker_data.best_bid_price,
-                "best_bid_qty": ticker_data.best_bid_qty,
-                "best_ask_price": ticker_data.best_ask_price,
-                "best_ask_qty": ticker_data.best_ask_qty,
-            }))) as Result<_, warp::Rejection>
+pub fn create_book_ticker_rest_api(
+    book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Route to get the full book ticker data
+    let book_ticker_data = warp::path!("bookticker" / String / "data")
+        .and(with_service(book_ticker_service.clone()))
+        .and_then(move |symbol: String, service: Arc<dyn BookTickerServiceTrait + Send + Sync>| async move {
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.get_ticker_data(&symbol).await {
+                Some(ticker_data) => {
+                    // Return the full book ticker data
+                    Ok(warp::reply::json(&with_times(book_ticker_json(&ticker_data), times)))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
         });
 
     // Route to get the mid price only
-    let book_ticker_midprice = warp::path!("bookticker" / "midprice")
-        .and_then(move || async move {
-            let service = BookTickerService;
-            let mid_price = service.mid_price().await;
-
-            Ok(warp::reply::json(&serde_json::json!({
-                "mid_price": mid_price
-            }))) as Result<_, warp::Rejection>
+    let book_ticker_midprice = warp::path!("bookticker" / String / "midprice")
+        .and(with_service(book_ticker_service.clone()))
+        .and_then(move |symbol: String, service: Arc<dyn BookTickerServiceTrait + Send + Sync>| async move {
+            // The mid is read from the ticker so the attestation names the update it came from
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.get_ticker_data(&symbol).await {
+                Some(ticker_data) => {
+                    let body = with_times(serde_json::json!({
+                        "mid_price": ticker_data.mid_price()
+                    }), times);
+                    Ok(warp::reply::json(&with_attestation(body, || AttestationPayload::mid_price(&ticker_data))))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
         });
 
     // Route to get the mid-weighted price only
-    let book_ticker_midweightedprice = warp::path!("bookticker" / "midweightedprice")
-        .and_then(move || async move {
-            let service = BookTickerService;
-            let mid_weighted_price = service.mid_weighted_price().await;
+    let book_ticker_midweightedprice = warp::path!("bookticker" / String / "midweightedprice")
+        .and(with_service(book_ticker_service.clone()))
+        .and_then(move |symbol: String, service: Arc<dyn BookTickerServiceTrait + Send + Sync>| async move {
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.mid_weighted_price(&symbol).await {
+                Some(mid_weighted_price) => {
+                    Ok(warp::reply::json(&with_times(serde_json::json!({
+                        "mid_weighted_price": mid_weighted_price
+                    }), times)))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
 
-            Ok(warp::reply::json(&serde_json::json!({
-                "mid_weighted_price": mid_weighted_price
-            }))) as Result<_, warp::Rejection>
+    // Route to get the time-weighted mid and microprice, e.g. /bookticker/BTCFDUSD/twap?window=300
+    let book_ticker_twap = warp::path!("bookticker" / String / "twap")
+        .and(warp::query::<WindowQuery>())
+        .and(with_service(book_ticker_service))
+        .and_then(move |symbol: String, query: WindowQuery, service: Arc<dyn BookTickerServiceTrait + Send + Sync>| async move {
+            let window_secs = CONFIG.default.reference_price_window(query.window)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidWindow(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.twap(&symbol, window_secs).await {
+                Some(twap) => Ok(warp::reply::json(&with_times(twap_json(&symbol, &twap), times))),
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
         });
 
     // Combine all routes
     book_ticker_data
         .or(book_ticker_midprice)
         .or(book_ticker_midweightedprice)
-}
\ No newline at end of file
+        .or(book_ticker_twap)
+}
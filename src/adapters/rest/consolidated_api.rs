@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use warp::{Filter, Rejection};
+use crate::adapters::rest::service_error::ServiceError;
+use crate::adapters::rest::with_service;
+use crate::config::CONFIG;
+use crate::domain::entities::consolidated_price::{ConsolidationError, VenueQuote};
+use crate::domain::services::consolidation_service::ConsolidationService;
+
+// JSON shape of the quote of one venue
+fn venue_quote_json(quote: &VenueQuote) -> serde_json::Value {
+    serde_json::json!({
+        "venue": quote.venue,
+        "best_bid_price": quote.best_bid.price,
+        "best_bid_qty": quote.best_bid.qty,
+        "best_ask_price": quote.best_ask.price,
+        "best_ask_qty": quote.best_ask.qty,
+        "mid": quote.mid(),
+        "event_time": quote.times.event_time,
+        "received_at": quote.times.received_at,
+        "fresh": quote.fresh,
+    })
+}
+
+// 503 when no venue (or too few venues) can be consolidated, symbols that are not one of the trading pairs are unknown
+fn consolidation_rejection(symbol: String, error: ConsolidationError) -> Rejection {
+    warp::reject::custom(match error {
+        ConsolidationError::NoData if !CONFIG.default.is_trading_pair(&symbol) => ServiceError::UnknownSymbol(symbol),
+        ConsolidationError::NoData => ServiceError::NoData(symbol),
+        not_enough => ServiceError::StaleData(format!("{}: {}", symbol, not_enough)),
+    })
+}
+
+pub fn create_consolidated_api(
+    consolidation_service: Arc<ConsolidationService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Route to get the price across venues with the quotes of every venue
+    let consolidated = warp::path!("consolidated" / String)
+        .and(with_service(consolidation_service.clone()))
+        .and_then(move |symbol: String, service: Arc<ConsolidationService>| async move {
+            let price = service.consolidated(&symbol).map_err(|e| consolidation_rejection(symbol, e))?;
+            Ok::<_, Rejection>(warp::reply::json(&serde_json::json!({
+                "symbol": price.symbol,
+                "method": price.method,
+                "price": price.price,
+                "venues_used": price.venues_used,
+                "venues": price.quotes.iter().map(venue_quote_json).collect::<Vec<_>>(),
+            })))
+        });
+
+    // Route to get the quote of every venue, also while too few of them are fresh to be consolidated
+    let venues = warp::path!("consolidated" / String / "venues")
+        .and(with_service(consolidation_service))
+        .and_then(move |symbol: String, service: Arc<ConsolidationService>| async move {
+            let quotes = service.quotes(&symbol);
+            if quotes.is_empty() {
+                return Err(consolidation_rejection(symbol, ConsolidationError::NoData));
+            }
+            Ok(warp::reply::json(&quotes.iter().map(venue_quote_json).collect::<Vec<_>>()))
+        });
+
+    consolidated.or(venues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use rust_decimal_macros::dec;
+    use warp::http::StatusCode;
+    use crate::adapters::rest::service_error::handle_rejection;
+    use crate::domain::entities::market_event::TopOfBook;
+    use crate::domain::entities::order_book::OrderBookEntry;
+    use crate::domain::services::clock::ReplayClock;
+
+    fn top(bid: &str, ask: &str) -> TopOfBook {
+        TopOfBook {
+            symbol: "BTCFDUSD".to_string(),
+            update_id: 1,
+            best_bid: OrderBookEntry { price: bid.parse().unwrap(), qty: dec!(1) },
+            best_ask: OrderBookEntry { price: ask.parse().unwrap(), qty: dec!(1) },
+            event_time: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consolidated_price_needs_the_minimum_venues() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = Arc::new(ConsolidationService::with_clock(clock.clone()).with_min_venues(2).with_max_age(Duration::from_secs(5)));
+        let api = create_consolidated_api(service.clone()).recover(handle_rejection);
+        let request = |path: &str| warp::test::request().path(path).reply(&api);
+
+        // BTCFDUSD is a configured trading pair without quotes, other symbols are unknown
+        assert_eq!(request("/consolidated/BTCFDUSD").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(request("/consolidated/BTCFDUSD/venues").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(request("/consolidated/NOTTRADED").await.status(), StatusCode::NOT_FOUND);
+        assert_eq!(request("/consolidated/NOTTRADED/venues").await.status(), StatusCode::NOT_FOUND);
+
+        // One venue is not enough for the price, its quote is still served
+        clock.set(1_000);
+        service.update("binance", &top("99", "101"));
+        let response = request("/consolidated/BTCFDUSD").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("1 venues have fresh quotes, 2 are required"));
+        let response = request("/consolidated/BTCFDUSD/venues").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!((&body[0]["venue"], &body[0]["mid"], &body[0]["received_at"]), (&"binance".into(), &"100".into(), &1_000.into()));
+
+        service.update("coinbase", &top("101", "103"));
+        let response = request("/consolidated/BTCFDUSD").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!((&body["price"], &body["method"], &body["venues_used"]), (&"101".into(), &"median".into(), &2.into()));
+        assert_eq!(body["venues"].as_array().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+use warp::Filter;
+use crate::adapters::rest::service_error::ServiceError;
+use crate::adapters::rest::with_service;
+use crate::health::HealthRegistry;
+
+pub fn create_feed_api(health: Arc<HealthRegistry>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Route to get the lifecycle state of every feed (connecting, live, backing-off or dead)
+    let feeds = warp::path!("feeds")
+        .and(warp::get())
+        .and(with_service(health.clone()))
+        .and_then(move |health: Arc<HealthRegistry>| async move {
+            Ok(warp::reply::json(&health.feed_statuses())) as Result<_, warp::Rejection>
+        });
+
+    // Route to get the lifecycle state of a single feed (order_book, book_ticker or trade)
+    let feed = warp::path!("feeds" / String)
+        .and(warp::get())
+        .and(with_service(health))
+        .and_then(move |feed: String, health: Arc<HealthRegistry>| async move {
+            health.feed_status(&feed)
+                .map(|status| warp::reply::json(&status))
+                .ok_or_else(|| warp::reject::custom(ServiceError::UnknownFeed(feed)))
+        });
+
+    feeds.or(feed)
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Filter;
+use crate::adapters::rest::with_service;
+use crate::health::{self, FeedStatus, HealthRegistry};
+
+fn health_reply(healthy: bool, statuses: Vec<FeedStatus>) -> warp::reply::WithStatus<warp::reply::Json> {
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "status": if healthy { "ok" } else { "unavailable" },
+            "feeds": statuses
+        })),
+        status,
+    )
+}
+
+pub fn create_health_api(health: Arc<HealthRegistry>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Liveness: the process is serving. Feeds are reconnected by the feed supervisor, a dead one only fails the
+    // readiness or stops the process depending on `feed_dead_policy`
+    let healthz = warp::path!("healthz")
+        .and(warp::get())
+        .and(with_service(health.clone()))
+        .and_then(move |health: Arc<HealthRegistry>| async move {
+            Ok(health_reply(true, health.feed_statuses())) as Result<_, warp::Rejection>
+        });
+
+    // Readiness: every feed is live and has fresh data
+    let readyz = warp::path!("readyz")
+        .and(warp::get())
+        .and(with_service(health))
+        .and_then(move |health: Arc<HealthRegistry>| async move {
+            let statuses = health.feed_statuses();
+            Ok(health_reply(health::is_ready(&statuses), statuses)) as Result<_, warp::Rejection>
+        });
+
+    healthz.or(readyz)
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use warp::Filter;
+use warp::filters::log::{Info, Log};
+use crate::adapters::rest::with_service;
+use crate::config::CONFIG;
+use crate::health::HealthRegistry;
+use crate::metrics::{self, HTTP_REQUESTS, HTTP_REQUEST_DURATION, ORDER_BOOK_LEVELS, TRADE_HISTORY_LENGTH};
+use crate::domain::services::order_book_service::OrderBookServiceTrait;
+use crate::domain::services::trade_history_service::TradeHistoryService;
+
+// Refresh the gauges read from the services right before a scrape
+async fn refresh_state_gauges(order_book_service: &dyn OrderBookServiceTrait, trade_history_service: &TradeHistoryService,
+                              health: &HealthRegistry) {
+    for symbol in CONFIG.default.trading_pairs.iter() {
+        if let Some((bids, asks)) = order_book_service.book_levels(symbol).await {
+            ORDER_BOOK_LEVELS.with_label_values(&[symbol, "bid"]).set(bids as i64);
+            ORDER_BOOK_LEVELS.with_label_values(&[symbol, "ask"]).set(asks as i64);
+        }
+        if let Some(length) = trade_history_service.history_len(symbol).await {
+            TRADE_HISTORY_LENGTH.with_label_values(&[symbol]).set(length as i64);
+        }
+    }
+    metrics::refresh_update_ages(health);
+}
+
+pub fn create_metrics_api(
+    order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+    trade_history_service: Arc<TradeHistoryService>,
+    health: Arc<HealthRegistry>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("metrics")
+        .and(warp::get())
+        .and(with_service(order_book_service))
+        .and(with_service(trade_history_service))
+        .and(with_service(health))
+        .and_then(move |order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>, trade_history_service: Arc<TradeHistoryService>,
+                        health: Arc<HealthRegistry>| async move {
+            refresh_state_gauges(order_book_service.as_ref(), &trade_history_service, &health).await;
+            Ok(warp::reply::with_header(
+                metrics::encode(),
+                "content-type",
+                prometheus::TEXT_FORMAT,
+            )) as Result<_, warp::Rejection>
+        })
+}
+
+// Request counter and latency histogram for every REST request
+pub fn http_metrics() -> Log<impl Fn(Info<'_>) + Copy> {
+    warp::log::custom(|info: Info<'_>| {
+        let route = metrics::route_label(info.path(), info.status().as_u16());
+        let method = info.method().as_str();
+        HTTP_REQUESTS
+            .with_label_values(&[method, &route, info.status().as_str()])
+            .inc();
+        HTTP_REQUEST_DURATION
+            .with_label_values(&[method, &route])
+            .observe(info.elapsed().as_secs_f64());
+    })
+}
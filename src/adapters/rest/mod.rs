@@ -1,4 +1,56 @@
+pub mod attestation_api;
 pub mod book_ticker_api;
+pub mod consolidated_api;
+pub mod feed_api;
+pub mod health_api;
+pub mod metrics_api;
 pub mod order_book_api;
+pub mod publisher_api;
 pub mod service_error;
 pub mod trade_history_rest;
+pub mod ws_push_api;
+
+use std::convert::Infallible;
+use std::sync::Arc;
+use serde::Deserialize;
+use warp::{Filter, Rejection};
+use crate::adapters::rest::service_error::ServiceError;
+use crate::attestation::{self, AttestationPayload};
+use crate::config::CONFIG;
+use crate::domain::entities::freshness::{DataTimes, Staleness};
+
+// Hand the shared service built in main to every request of a route
+pub fn with_service<S: ?Sized + Send + Sync>(service: Arc<S>) -> impl Filter<Extract = (Arc<S>,), Error = Infallible> + Clone {
+    warp::any().map(move || service.clone())
+}
+
+// Add the signed `attestation` of the payload to a JSON response, unchanged when signing is not configured
+pub fn with_attestation(mut body: serde_json::Value, payload: impl FnOnce() -> AttestationPayload) -> serde_json::Value {
+    if let Some(attestation) = attestation::attest(payload) {
+        body["attestation"] = serde_json::json!(attestation);
+    }
+    body
+}
+
+// Times of the data behind a response, 503 when nothing was received yet or it is too old to be served.
+// Symbols that are not one of the trading pairs are unknown instead.
+pub fn fresh(symbol: &str, times: Result<DataTimes, Staleness>) -> Result<DataTimes, Rejection> {
+    times.map_err(|staleness| warp::reject::custom(match staleness {
+        Staleness::NoData if !CONFIG.default.is_trading_pair(symbol) => ServiceError::UnknownSymbol(symbol.to_string()),
+        Staleness::NoData => ServiceError::NoData(symbol.to_string()),
+        stale => ServiceError::StaleData(format!("{}: {}", symbol, stale)),
+    }))
+}
+
+// Add the exchange event time (null when the stream has none) and the local receive time to a JSON response
+pub fn with_times(mut body: serde_json::Value, times: DataTimes) -> serde_json::Value {
+    body["event_time"] = serde_json::json!(times.event_time);
+    body["received_at"] = serde_json::json!(times.received_at);
+    body
+}
+
+// Query selecting the window of the VWAP/TWAP routes in seconds, e.g. ?window=300
+#[derive(Debug, Deserialize)]
+pub struct WindowQuery {
+    pub window: Option<u64>,
+}
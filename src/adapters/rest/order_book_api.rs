@@ -1,23 +1,122 @@
 use std::sync::Arc;
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use warp::{Filter, Rejection};
 use warp::reply::Json;
 use crate::adapters::rest::service_error::ServiceError;
+use crate::adapters::rest::{fresh, with_attestation, with_service, with_times};
+use crate::attestation::AttestationPayload;
 use crate::domain::services::order_book_service::OrderBookServiceTrait;
-use crate::domain::services::order_book_service::OrderBookService;
-use crate::domain::entities::order_book::OrderBookEntry;
-
-pub fn create_order_book_api() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    let orderbook_top = warp::path!("orderbook" / "top")
-        .and_then(move || async move {
-            let service = OrderBookService;
-            match service.get_top_of_book().await {
+use crate::domain::entities::order_book::{FullOrderBook, OrderBookEntry, OrderBookTop};
+use crate::domain::entities::order_book_analytics::{BookAnalytics, BookAnalyticsParams, ImpactEstimate, ImpactOrder};
+
+// Query of the analytics route, the band is set either in bps or in pct
+#[derive(Debug, Deserialize)]
+struct AnalyticsQuery {
+    levels: Option<usize>,
+    bps: Option<Decimal>,
+    pct: Option<Decimal>,
+}
+
+// Query of the impact route, the amount is set either in the base asset (qty) or in the quote asset (quote)
+#[derive(Debug, Deserialize)]
+struct ImpactQuery {
+    side: String,
+    qty: Option<Decimal>,
+    quote: Option<Decimal>,
+}
+
+// JSON shape of the top of the book, shared with the WebSocket push API
+pub fn order_book_top_json(order_book_top: &OrderBookTop) -> serde_json::Value {
+    serde_json::json!({
+        "best_bid_price": order_book_top.best_bid.price,
+        "best_bid_qty": order_book_top.best_bid.qty,
+        "best_ask_price": order_book_top.best_ask.price,
+        "best_ask_qty": order_book_top.best_ask.qty,
+    })
+}
+
+// JSON shape of the full order book, shared with the WebSocket push API
+pub fn full_order_book_json(full_book: &FullOrderBook) -> serde_json::Value {
+    let levels = |entries: &Option<Vec<OrderBookEntry>>| {
+        entries.as_deref().unwrap_or_default().iter().map(|entry: &OrderBookEntry| {
+            serde_json::json!({
+                "price": entry.price,
+                "qty": entry.qty
+            })
+        }).collect::<Vec<_>>()
+    };
+    serde_json::json!({
+        "bids": levels(&full_book.bids),
+        "asks": levels(&full_book.asks)
+    })
+}
+
+// JSON shape of the order book analytics
+pub fn book_analytics_json(symbol: &str, analytics: &BookAnalytics) -> serde_json::Value {
+    let BookAnalytics { spread, imbalance, liquidity, weighted } = analytics;
+    serde_json::json!({
+        "symbol": symbol,
+        "spread": {
+            "best_bid": spread.best_bid,
+            "best_ask": spread.best_ask,
+            "mid": spread.mid,
+            "absolute": spread.absolute,
+            "bps": spread.bps,
+        },
+        "imbalance": {
+            "levels": imbalance.levels,
+            "bid_qty": imbalance.bid_qty,
+            "ask_qty": imbalance.ask_qty,
+            "imbalance": imbalance.imbalance,
+        },
+        "liquidity": {
+            "band_bps": liquidity.band_bps,
+            "lower_price": liquidity.lower_price,
+            "upper_price": liquidity.upper_price,
+            "bid_qty": liquidity.bid_qty,
+            "ask_qty": liquidity.ask_qty,
+            "bid_notional": liquidity.bid_notional,
+            "ask_notional": liquidity.ask_notional,
+        },
+        "weighted": {
+            "levels": weighted.levels,
+            "bid_price": weighted.bid_price,
+            "ask_price": weighted.ask_price,
+            "weighted_mid": weighted.weighted_mid,
+            "microprice": weighted.microprice,
+        },
+    })
+}
+
+// JSON shape of a market impact estimate
+pub fn impact_json(symbol: &str, estimate: &ImpactEstimate) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": symbol,
+        "side": estimate.side.to_string(),
+        "filled_qty": estimate.filled_qty,
+        "filled_quote": estimate.filled_quote,
+        "average_price": estimate.average_price,
+        "worst_price": estimate.worst_price,
+        "mid": estimate.mid,
+        "slippage_bps": estimate.slippage_bps,
+        "levels_consumed": estimate.levels_consumed,
+        "insufficient_depth": estimate.insufficient_depth,
+    })
+}
+
+pub fn create_order_book_api(
+    order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let orderbook_top = warp::path!("orderbook" / String / "top")
+        .and(with_service(order_book_service.clone()))
+        .and_then(move |symbol: String, service: Arc<dyn OrderBookServiceTrait + Send + Sync>| async move {
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.get_top_of_book(&symbol).await {
                 Some(order_book_top) => {
-                    Ok(warp::reply::json(&serde_json::json!({
-                        "best_bid_price": order_book_top.best_bid.price,
-                        "best_bid_qty": order_book_top.best_bid.qty,
-                        "best_ask_price": order_book_top.best_ask.price,
-                        "best_ask_qty": order_book_top.best_ask.qty,
-                    }))) as Result<Json, Rejection>
+                    let body = with_times(order_book_top_json(&order_book_top), times);
+                    let body = with_attestation(body, || AttestationPayload::top_of_book(&symbol, &order_book_top));
+                    Ok(warp::reply::json(&body)) as Result<Json, Rejection>
                 }
                 None => {
                     Err(warp::reject::custom(ServiceError::EmptyOrderBook))
@@ -35,25 +134,13 @@ pub fn create_order_book_api() -> impl Filter<Extract = impl warp::Reply, Error
             }*/
         });
 
-    let orderbook_full = warp::path!("orderbook" / "full")
-        .and_then(move || async move {
-            let service = Arc::new(OrderBookService);
-            match service.get_full_book().await{
+    let orderbook_full = warp::path!("orderbook" / String / "full")
+        .and(with_service(order_book_service.clone()))
+        .and_then(move |symbol: String, service: Arc<dyn OrderBookServiceTrait + Send + Sync>| async move {
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.get_full_book(&symbol).await{
                 Some(full_book) => {
-                    Ok(warp::reply::json(&serde_json::json!({
-                        "bids": full_book.bids.unwrap_or_default().iter().map(|entry: &OrderBookEntry| {
-                            serde_json::json!({
-                                "price": entry.price,
-                                "qty": entry.qty
-                            })
-                        }).collect::<Vec<_>>(),
-                        "asks": full_book.asks.unwrap_or_default().iter().map(|entry: &OrderBookEntry| {
-                             serde_json::json!({
-                                "price": entry.price,
-                                "qty": entry.qty
-                            })
-                        }).collect::<Vec<_>>()
-                    }))) as Result<_, warp::Rejection>
+                    Ok(warp::reply::json(&with_times(full_order_book_json(&full_book), times))) as Result<_, warp::Rejection>
                 }
                 None => {
                     Err(warp::reject::custom(ServiceError::EmptyOrderBook))
@@ -77,6 +164,76 @@ pub fn create_order_book_api() -> impl Filter<Extract = impl warp::Reply, Error
             }))) as Result<_, warp::Rejection>*/
         });
 
+    // Route to get the spread, imbalance, band liquidity and weighted prices, e.g. /orderbook/BTCFDUSD/analytics?levels=5&bps=25
+    let orderbook_analytics = warp::path!("orderbook" / String / "analytics")
+        .and(warp::query::<AnalyticsQuery>())
+        .and(with_service(order_book_service.clone()))
+        .and_then(move |symbol: String, query: AnalyticsQuery, service: Arc<dyn OrderBookServiceTrait + Send + Sync>| async move {
+            let params = BookAnalyticsParams::new(query.levels, query.bps, query.pct)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidQuery(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.analytics(&symbol, &params).await {
+                Some(analytics) => Ok(warp::reply::json(&with_times(book_analytics_json(&symbol, &analytics), times))),
+                None => Err(warp::reject::custom(ServiceError::EmptyOrderBook))
+            }
+        });
+
+    // Route to estimate what a market order would cost against the book, e.g. /orderbook/BTCFDUSD/impact?side=buy&qty=5
+    let orderbook_impact = warp::path!("orderbook" / String / "impact")
+        .and(warp::query::<ImpactQuery>())
+        .and(with_service(order_book_service))
+        .and_then(move |symbol: String, query: ImpactQuery, service: Arc<dyn OrderBookServiceTrait + Send + Sync>| async move {
+            let order = ImpactOrder::new(&query.side, query.qty, query.quote)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidQuery(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.impact(&symbol, &order).await {
+                Some(estimate) => Ok(warp::reply::json(&with_times(impact_json(&symbol, &estimate), times))),
+                None => Err(warp::reject::custom(ServiceError::EmptyOrderBook))
+            }
+        });
+
     // Combine the routes
-    orderbook_top.or(orderbook_full)
-}
\ No newline at end of file
+    orderbook_top.or(orderbook_full).or(orderbook_analytics).or(orderbook_impact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::order_book::DepthData;
+    use crate::domain::services::order_book_service::OrderBookService;
+
+    // Book of E2EOB: bids 2 at 100.5 and 3 at 100, asks 4 at 101.5
+    async fn book() -> Arc<dyn OrderBookServiceTrait + Send + Sync> {
+        let service = Arc::new(OrderBookService::new());
+        service.apply_snapshot(&DepthData {
+            last_update_id: 2,
+            bids: vec![["100.5".to_string(), "2".to_string()], ["100".to_string(), "3".to_string()]],
+            asks: vec![["101.5".to_string(), "4".to_string()]],
+        }.to_snapshot("E2EOB")).await;
+        service
+    }
+
+    #[tokio::test]
+    async fn test_book_analytics_are_served() {
+        let api = create_order_book_api(book().await);
+
+        // ±0.5% of the mid (101) holds the best level of each side only
+        let response = warp::test::request().path("/orderbook/E2EOB/analytics?levels=1&pct=0.5").reply(&api).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["spread"]["absolute"], "1.0");
+        assert_eq!((&body["liquidity"]["bid_qty"], &body["liquidity"]["ask_qty"]), (&serde_json::json!("2"), &serde_json::json!("4")));
+    }
+
+    #[tokio::test]
+    async fn test_impact_estimates_are_served() {
+        let api = create_order_book_api(book().await);
+
+        // Selling 4 takes the 2 at 100.5 and 2 of the 3 at 100
+        let response = warp::test::request().path("/orderbook/E2EOB/impact?side=sell&qty=4").reply(&api).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!((&body["worst_price"], &body["levels_consumed"], &body["insufficient_depth"]),
+            (&serde_json::json!("100"), &serde_json::json!(2), &serde_json::json!(false)));
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+use serde::Deserialize;
+use warp::Filter;
+use crate::adapters::rest::service_error::ServiceError;
+use crate::adapters::rest::with_service;
+use crate::domain::entities::publication::{Publication, PublisherPolicy};
+use crate::domain::services::publisher_service::{PublisherService, DEFAULT_PUBLICATION_LIMIT};
+
+// Query of the publication log route
+#[derive(Debug, Deserialize)]
+struct LogQuery {
+    limit: Option<usize>,
+}
+
+// JSON shape of a published price
+pub fn publication_json(publication: &Publication) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": publication.symbol,
+        "sequence": publication.sequence,
+        "source": publication.source,
+        "price": publication.price,
+        "reason": publication.reason,
+        "deviation_bps": publication.deviation_bps,
+        "published_at": publication.published_at,
+    })
+}
+
+// JSON shape of the policy a trading pair is published with
+fn policy_json(policy: &PublisherPolicy) -> serde_json::Value {
+    serde_json::json!({
+        "source": policy.source,
+        "deviation_bps": policy.deviation_bps,
+        "heartbeat_secs": policy.heartbeat_secs,
+    })
+}
+
+pub fn create_publisher_api(
+    publisher_service: Arc<PublisherService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Route to get the last published price with the publisher policy
+    let latest = warp::path!("publisher" / String / "latest")
+        .and(with_service(publisher_service.clone()))
+        .and_then(move |symbol: String, service: Arc<PublisherService>| async move {
+            let (Some(policy), Some(publication)) = (service.policy(&symbol).await, service.latest(&symbol).await) else {
+                return Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)));
+            };
+            let mut body = publication_json(&publication);
+            body["policy"] = policy_json(&policy);
+            Ok(warp::reply::json(&body))
+        });
+
+    // Route to get the audit log of the publications, oldest first, e.g. /publisher/BTCFDUSD/log?limit=50
+    let log = warp::path!("publisher" / String / "log")
+        .and(warp::query::<LogQuery>())
+        .and(with_service(publisher_service))
+        .and_then(move |symbol: String, query: LogQuery, service: Arc<PublisherService>| async move {
+            match service.publication_log(&symbol, query.limit.unwrap_or(DEFAULT_PUBLICATION_LIMIT)).await {
+                Some(publications) => {
+                    let publications: Vec<serde_json::Value> = publications.iter().map(publication_json).collect();
+                    Ok(warp::reply::json(&publications))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
+
+    latest.or(log)
+}
@@ -1,9 +1,19 @@
 use std::fmt;
+use warp::http::StatusCode;
 use warp::reject::Reject;
+use warp::{Rejection, Reply};
 
 #[derive(Debug)]
 pub enum ServiceError {
     EmptyOrderBook,
+    UnknownSymbol(String),
+    UnknownInterval(String),
+    InvalidWindow(String),
+    InvalidQuery(String),
+    SigningDisabled,
+    NoData(String),
+    StaleData(String),
+    UnknownFeed(String),
 //    OrderBookAccessError,
 //    TradeDataError,
 }
@@ -12,10 +22,84 @@ impl fmt::Display for ServiceError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ServiceError::EmptyOrderBook => write!(f, "The order book is empty"),
+            ServiceError::UnknownSymbol(symbol) => write!(f, "No data for symbol {}", symbol),
+            ServiceError::UnknownInterval(interval) => write!(f, "Unknown candle interval {}", interval),
+            ServiceError::InvalidWindow(reason) => write!(f, "{}", reason),
+            ServiceError::InvalidQuery(reason) => write!(f, "{}", reason),
+            ServiceError::SigningDisabled => write!(f, "Signing is not configured"),
+            ServiceError::NoData(symbol) => write!(f, "No data has been received for symbol {}", symbol),
+            ServiceError::StaleData(reason) => write!(f, "{}", reason),
+            ServiceError::UnknownFeed(feed) => write!(f, "Unknown feed {}", feed),
 //            ServiceError::OrderBookAccessError => write!(f, "Could not access the order book"),
 //            ServiceError::TradeDataError => write!(f, "Trade data error"),
         }
     }
 }
 
-impl Reject for ServiceError {}
\ No newline at end of file
+impl ServiceError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ServiceError::EmptyOrderBook | ServiceError::UnknownSymbol(_) | ServiceError::SigningDisabled
+                | ServiceError::UnknownFeed(_) => StatusCode::NOT_FOUND,
+            ServiceError::UnknownInterval(_) | ServiceError::InvalidWindow(_) | ServiceError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            ServiceError::NoData(_) | ServiceError::StaleData(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+impl Reject for ServiceError {}
+
+// Reply to a service error with its status and `{"error": "..."}`, other rejections keep warp's default handling
+pub async fn handle_rejection(rejection: Rejection) -> Result<warp::reply::Response, Rejection> {
+    match rejection.find::<ServiceError>() {
+        Some(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({ "error": error.to_string() })),
+            error.status(),
+        ).into_response()),
+        None => Err(rejection),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use warp::Filter;
+    use crate::adapters::rest::book_ticker_api::create_book_ticker_rest_api;
+    use crate::domain::entities::book_ticker::BookTickerData;
+    use crate::domain::services::book_ticker_service::{BookTickerService, BookTickerServiceTrait};
+    use crate::domain::services::clock::ReplayClock;
+
+    #[tokio::test]
+    async fn test_missing_and_stale_data_is_unavailable() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = Arc::new(BookTickerService::with_clock(clock.clone()).with_max_age(Duration::from_secs(10)));
+        let api = create_book_ticker_rest_api(service.clone()).recover(handle_rejection);
+        let request = |path: &str| warp::test::request().path(path).reply(&api);
+
+        // BTCFDUSD is a configured trading pair without data, other symbols are unknown
+        assert_eq!(request("/bookticker/BTCFDUSD/midprice").await.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(request("/bookticker/NOTTRADED/midprice").await.status(), StatusCode::NOT_FOUND);
+
+        clock.set(1_000_000);
+        service.update_ticker(&BookTickerData {
+            update_id: 1,
+            symbol: "BTCFDUSD".to_string(),
+            best_bid_price: "99".parse().unwrap(),
+            best_bid_qty: "1".parse().unwrap(),
+            best_ask_price: "101".parse().unwrap(),
+            best_ask_qty: "1".parse().unwrap(),
+        }.to_top_of_book()).await;
+        let response = request("/bookticker/BTCFDUSD/midprice").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!((&body["event_time"], &body["received_at"]), (&serde_json::Value::Null, &serde_json::json!(1_000_000)));
+
+        clock.set(1_010_001);
+        let response = request("/bookticker/BTCFDUSD/midprice").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("10001ms old"));
+    }
+}
@@ -1,31 +1,197 @@
 use std::sync::Arc;
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use warp::Filter;
-use crate::domain::services::trade_history_service::TradeHistoryService;
-
-pub fn create_trade_history_rest_api() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    // Route to get the average volume per trade
-    let average_volume = warp::path!("tradehistory" / "average_volume")
-        .and_then(move || async move {
-            let service = Arc::new(TradeHistoryService);
-            let avg_volume = service.average_volume_per_trade().await;
-
-            Ok(warp::reply::json(&serde_json::json!({
-                "average_volume_per_trade": avg_volume
-            }))) as Result<_, warp::Rejection>
+use crate::adapters::rest::service_error::ServiceError;
+use crate::adapters::rest::{fresh, with_attestation, with_service, with_times, WindowQuery};
+use crate::attestation::AttestationPayload;
+use crate::config::CONFIG;
+use crate::domain::entities::candle::CandleInterval;
+use crate::domain::entities::reference_price::Vwap;
+use crate::domain::entities::trade::{AggressorFlow, CumulativeVolumeDelta, TradeData};
+use crate::domain::services::trade_history_service::{TradeHistoryService, DEFAULT_CANDLE_LIMIT};
+
+// Query of the candles route
+#[derive(Debug, Deserialize)]
+struct CandlesQuery {
+    limit: Option<usize>,
+}
+
+// JSON shape of a single trade pushed over the WebSocket API
+pub fn trade_json(trade: &TradeData) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": trade.symbol,
+        "trade_id": trade.trade_id,
+        "price": trade.price,
+        "quantity": trade.quantity,
+        "trade_time": trade.trade_time,
+        "event_time": trade.event_time,
+        "is_buyer_market_maker": trade.is_buyer_market_maker,
+    })
+}
+
+// JSON shape of the volume statistics, the union of the average_volume and total_volume routes
+pub fn volume_stats_json(average_volume_per_trade: Decimal, total_volume: Decimal) -> serde_json::Value {
+    serde_json::json!({
+        "average_volume_per_trade": average_volume_per_trade,
+        "total_volume": total_volume
+    })
+}
+
+// JSON shape of the volume-weighted average price
+pub fn vwap_json(symbol: &str, vwap: &Vwap) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": symbol,
+        "window_secs": vwap.window_secs,
+        "sample_count": vwap.sample_count,
+        "coverage": vwap.coverage,
+        "vwap": vwap.vwap,
+        "volume": vwap.volume,
+    })
+}
+
+// JSON shape of the traded volume split by aggressor side
+pub fn aggressor_flow_json(symbol: &str, window_secs: u64, flow: &AggressorFlow) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": symbol,
+        "window_secs": window_secs,
+        "buy_volume": flow.buy_volume,
+        "sell_volume": flow.sell_volume,
+        "buy_count": flow.buy_count,
+        "sell_count": flow.sell_count,
+        "buy_notional": flow.buy_notional,
+        "sell_notional": flow.sell_notional,
+        "volume_delta": flow.volume_delta(),
+        "imbalance": flow.imbalance(),
+    })
+}
+
+// JSON shape of the cumulative volume delta
+pub fn cumulative_volume_delta_json(symbol: &str, cvd: &CumulativeVolumeDelta) -> serde_json::Value {
+    serde_json::json!({
+        "symbol": symbol,
+        "cumulative_volume_delta": cvd.delta,
+        "since": cvd.since,
+    })
+}
+
+pub fn create_trade_history_rest_api(
+    trade_history_service: Arc<TradeHistoryService>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Route to get the average volume per trade, e.g. /tradehistory/BTCFDUSD/average_volume?window=10
+    let average_volume = warp::path!("tradehistory" / String / "average_volume")
+        .and(warp::query::<WindowQuery>())
+        .and(with_service(trade_history_service.clone()))
+        .and_then(move |symbol: String, query: WindowQuery, service: Arc<TradeHistoryService>| async move {
+            let window_secs = CONFIG.default.trade_stats_window(query.window)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidWindow(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.average_volume_per_trade(&symbol, window_secs).await {
+                Some(avg_volume) => {
+                    Ok(warp::reply::json(&with_times(serde_json::json!({
+                        "average_volume_per_trade": avg_volume,
+                        "window_secs": window_secs
+                    }), times)))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
+
+    // Route to get the total volume of trades in a rolling window, e.g. /tradehistory/BTCFDUSD/total_volume?window=3600
+    let total_volume = warp::path!("tradehistory" / String / "total_volume")
+        .and(warp::query::<WindowQuery>())
+        .and(with_service(trade_history_service.clone()))
+        .and_then(move |symbol: String, query: WindowQuery, service: Arc<TradeHistoryService>| async move {
+            let window_secs = CONFIG.default.trade_stats_window(query.window)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidWindow(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.total_volume(&symbol, window_secs).await {
+                Some(total_volume) => {
+                    Ok(warp::reply::json(&with_times(serde_json::json!({
+                        "total_volume": total_volume,
+                        "window_secs": window_secs
+                    }), times)))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
+
+    // Route to get the most recent candles of an interval, e.g. /tradehistory/BTCFDUSD/candles/1m?limit=50
+    let candles = warp::path!("tradehistory" / String / "candles" / String)
+        .and(warp::query::<CandlesQuery>())
+        .and(with_service(trade_history_service.clone()))
+        .and_then(move |symbol: String, interval: String, query: CandlesQuery, service: Arc<TradeHistoryService>| async move {
+            let interval = interval.parse::<CandleInterval>()
+                .map_err(|_| warp::reject::custom(ServiceError::UnknownInterval(interval)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.candles(&symbol, interval, query.limit.unwrap_or(DEFAULT_CANDLE_LIMIT)).await {
+                Some(candles) => Ok(warp::reply::json(&with_times(serde_json::json!({ "candles": candles }), times))),
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
+
+    // Route to get the volume-weighted average price, e.g. /tradehistory/BTCFDUSD/vwap?window=300
+    let vwap = warp::path!("tradehistory" / String / "vwap")
+        .and(warp::query::<WindowQuery>())
+        .and(with_service(trade_history_service.clone()))
+        .and_then(move |symbol: String, query: WindowQuery, service: Arc<TradeHistoryService>| async move {
+            let window_secs = CONFIG.default.reference_price_window(query.window)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidWindow(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.vwap(&symbol, window_secs).await {
+                Some(vwap) => {
+                    let body = with_times(vwap_json(&symbol, &vwap), times);
+                    Ok(warp::reply::json(&with_attestation(body, || AttestationPayload::vwap(&symbol, &vwap))))
+                }
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
+
+    // Route to get the volume of a rolling window split by aggressor side, e.g. /tradehistory/BTCFDUSD/aggressor_flow?window=300
+    let aggressor_flow = warp::path!("tradehistory" / String / "aggressor_flow")
+        .and(warp::query::<WindowQuery>())
+        .and(with_service(trade_history_service.clone()))
+        .and_then(move |symbol: String, query: WindowQuery, service: Arc<TradeHistoryService>| async move {
+            let window_secs = CONFIG.default.trade_stats_window(query.window)
+                .map_err(|e| warp::reject::custom(ServiceError::InvalidWindow(e)))?;
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.aggressor_flow(&symbol, window_secs).await {
+                Some(flow) => Ok(warp::reply::json(&with_times(aggressor_flow_json(&symbol, window_secs, &flow), times))),
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
         });
 
-    // Route to get the total volume of trades in the rolling window
-    let total_volume = warp::path!("tradehistory" / "total_volume")
-        .and_then(move || async move {
-            let service = TradeHistoryService;
-            let total_volume = service.total_volume().await;
+    // Route to get the cumulative volume delta since startup or the last reset
+    let cumulative_volume_delta = warp::path!("tradehistory" / String / "cvd")
+        .and(warp::get())
+        .and(with_service(trade_history_service.clone()))
+        .and_then(move |symbol: String, service: Arc<TradeHistoryService>| async move {
+            let times = fresh(&symbol, service.data_times(&symbol))?;
+            match service.cumulative_volume_delta(&symbol).await {
+                Some(cvd) => Ok(warp::reply::json(&with_times(cumulative_volume_delta_json(&symbol, &cvd), times))),
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
+        });
 
-            Ok(warp::reply::json(&serde_json::json!({
-                "total_volume": total_volume
-            }))) as Result<_, warp::Rejection>
+    // Route to start the cumulative volume delta over, returns the delta up to the reset
+    let reset_cumulative_volume_delta = warp::path!("tradehistory" / String / "cvd" / "reset")
+        .and(warp::post())
+        .and(with_service(trade_history_service))
+        .and_then(move |symbol: String, service: Arc<TradeHistoryService>| async move {
+            // A reset is not refused when the last trade is old, the delta up to the reset is returned either way
+            let times = service.last_times(&symbol).unwrap_or_default();
+            match service.reset_cumulative_volume_delta(&symbol).await {
+                Some(cvd) => Ok(warp::reply::json(&with_times(cumulative_volume_delta_json(&symbol, &cvd), times))),
+                None => Err(warp::reject::custom(ServiceError::UnknownSymbol(symbol)))
+            }
         });
 
-    // Combine both routes
+    // Combine all routes
     average_volume
         .or(total_volume)
-}
\ No newline at end of file
+        .or(candles)
+        .or(vwap)
+        .or(aggressor_flow)
+        .or(cumulative_volume_delta)
+        .or(reset_cumulative_volume_delta)
+}
@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+use crate::adapters::rest::book_ticker_api::book_ticker_json;
+use crate::adapters::rest::order_book_api::{full_order_book_json, order_book_top_json};
+use crate::adapters::rest::trade_history_rest::{trade_json, volume_stats_json};
+use crate::adapters::rest::{with_service, with_times};
+use crate::config::CONFIG;
+use crate::domain::entities::freshness::{DataTimes, Staleness};
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::book_ticker_service::BookTickerServiceTrait;
+use crate::domain::services::order_book_service::OrderBookServiceTrait;
+use crate::domain::services::trade_history_service::TradeHistoryService;
+
+// Channels a WebSocket client can subscribe to
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Ticker,
+    Top,
+    Depth,
+    Trades,
+    Volume,
+}
+
+impl Channel {
+    fn as_str(&self) -> &str {
+        match self {
+            Channel::Ticker => "ticker",
+            Channel::Top => "top",
+            Channel::Depth => "depth",
+            Channel::Trades => "trades",
+            Channel::Volume => "volume",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Method {
+    Subscribe,
+    Unsubscribe,
+}
+
+// Client request, e.g. {"method": "subscribe", "channel": "ticker", "symbol": "BTCFDUSD"}
+#[derive(Debug, Deserialize)]
+struct ClientRequest {
+    method: Method,
+    channel: Channel,
+    symbol: String,
+}
+
+type Subscriptions = HashSet<(Channel, String)>;
+
+pub fn create_ws_push_api(
+    book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+    order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+    trade_history_service: Arc<TradeHistoryService>,
+    shutdown: CancellationToken,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("ws")
+        .and(warp::ws())
+        .and(with_service(book_ticker_service))
+        .and(with_service(order_book_service))
+        .and(with_service(trade_history_service))
+        .map(move |ws: warp::ws::Ws,
+              book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+              order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+              trade_history_service: Arc<TradeHistoryService>| {
+            let shutdown = shutdown.clone();
+            ws.on_upgrade(move |socket| handle_client(socket, book_ticker_service, order_book_service, trade_history_service, shutdown))
+        })
+}
+
+// Envelope of every pushed frame: {"channel": "...", "symbol": "...", "data": <REST shape>}
+// Streams are never refused: the data carries its times like the REST responses and the frame is marked stale once
+// the data is older than the maximum age a REST route would serve
+fn frame(channel: Channel, symbol: &str, data: serde_json::Value, times: Option<DataTimes>, freshness: Result<DataTimes, Staleness>) -> Message {
+    let data = match times {
+        Some(times) => with_times(data, times),
+        None => data,
+    };
+    Message::text(serde_json::json!({
+        "channel": channel.as_str(),
+        "symbol": symbol,
+        "data": data,
+        "stale": freshness.is_err()
+    }).to_string())
+}
+
+fn error_frame(error: &str) -> Message {
+    Message::text(serde_json::json!({ "error": error }).to_string())
+}
+
+// Apply a subscribe/unsubscribe request and build the acknowledgement
+fn handle_request(subscriptions: &mut Subscriptions, text: &str) -> Message {
+    match serde_json::from_str::<ClientRequest>(text) {
+        Ok(request) => {
+            let symbol = normalize_symbol(&request.symbol);
+            if symbol.is_empty() {
+                return error_frame("Symbol is required");
+            }
+            let result = match request.method {
+                Method::Subscribe => {
+                    subscriptions.insert((request.channel, symbol.clone()));
+                    "subscribed"
+                }
+                Method::Unsubscribe => {
+                    subscriptions.remove(&(request.channel, symbol.clone()));
+                    "unsubscribed"
+                }
+            };
+            Message::text(serde_json::json!({
+                "result": result,
+                "channel": request.channel.as_str(),
+                "symbol": symbol
+            }).to_string())
+        }
+        Err(e) => error_frame(&format!("Invalid request: {}", e)),
+    }
+}
+
+// Receive the next update, updates missed by a slow client are dropped
+async fn next_update<T: Clone>(updates: &mut broadcast::Receiver<T>) -> Option<T> {
+    loop {
+        match updates.recv().await {
+            Ok(update) => return Some(update),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                log::warn!("WebSocket client is too slow, dropped {} updates", skipped);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+async fn handle_client(
+    socket: WebSocket,
+    book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+    order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+    trade_history_service: Arc<TradeHistoryService>,
+    shutdown: CancellationToken,
+) {
+    let (mut client_tx, mut client_rx) = socket.split();
+
+    let mut tickers = book_ticker_service.subscribe();
+    let mut books = order_book_service.subscribe();
+    let mut trades = trade_history_service.subscribe();
+    let mut subscriptions = Subscriptions::new();
+
+    loop {
+        let mut frames = Vec::new();
+
+        tokio::select! {
+            message = client_rx.next() => {
+                match message {
+                    Some(Ok(message)) if message.is_text() => {
+                        frames.push(handle_request(&mut subscriptions, message.to_str().unwrap_or_default()));
+                    }
+                    Some(Ok(message)) if message.is_close() => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        log::info!("WebSocket client error: {}", e);
+                        break;
+                    }
+                    None => break,
+                }
+            }
+            _ = shutdown.cancelled() => {
+                // Going away: the client should reconnect to another instance
+                let _ = client_tx.send(Message::close_with(1001u16, "Server shutting down")).await;
+                log::info!("WebSocket client disconnected by the shutdown");
+                return;
+            }
+            Some(ticker_data) = next_update(&mut tickers) => {
+                let symbol = normalize_symbol(&ticker_data.symbol);
+                if subscriptions.contains(&(Channel::Ticker, symbol.clone())) {
+                    let (times, freshness) = (book_ticker_service.last_times(&symbol), book_ticker_service.data_times(&symbol));
+                    frames.push(frame(Channel::Ticker, &symbol, book_ticker_json(&ticker_data), times, freshness));
+                }
+            }
+            Some(update) = next_update(&mut books) => {
+                let (times, freshness) = (order_book_service.last_times(&update.symbol), order_book_service.data_times(&update.symbol));
+                if subscriptions.contains(&(Channel::Top, update.symbol.clone())) {
+                    if let Some(top) = &update.top {
+                        frames.push(frame(Channel::Top, &update.symbol, order_book_top_json(top), times, freshness.clone()));
+                    }
+                }
+                if subscriptions.contains(&(Channel::Depth, update.symbol.clone())) {
+                    if let Some(full_book) = order_book_service.get_full_book(&update.symbol).await {
+                        frames.push(frame(Channel::Depth, &update.symbol, full_order_book_json(&full_book), times, freshness));
+                    }
+                }
+            }
+            Some(trade) = next_update(&mut trades) => {
+                let symbol = normalize_symbol(&trade.symbol);
+                let (times, freshness) = (trade_history_service.last_times(&symbol), trade_history_service.data_times(&symbol));
+                if subscriptions.contains(&(Channel::Trades, symbol.clone())) {
+                    frames.push(frame(Channel::Trades, &symbol, trade_json(&trade), times, freshness.clone()));
+                }
+                if subscriptions.contains(&(Channel::Volume, symbol.clone())) {
+                    // Volume statistics of the default window
+                    let window_secs = CONFIG.default.trade_stats_windows_secs[0];
+                    let average_volume = trade_history_service.average_volume_per_trade(&symbol, window_secs).await;
+                    let total_volume = trade_history_service.total_volume(&symbol, window_secs).await;
+                    if let (Some(average_volume), Some(total_volume)) = (average_volume, total_volume) {
+                        frames.push(frame(Channel::Volume, &symbol, volume_stats_json(average_volume, total_volume), times, freshness));
+                    }
+                }
+            }
+        }
+
+        for message in frames {
+            if let Err(e) = client_tx.send(message).await {
+                log::info!("WebSocket client disconnected: {}", e);
+                return;
+            }
+        }
+    }
+
+    log::info!("WebSocket client closed the connection");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::book_ticker::BookTickerData;
+    use std::time::Duration;
+    use crate::domain::services::book_ticker_service::BookTickerService;
+    use crate::domain::services::clock::ReplayClock;
+    use crate::domain::services::order_book_service::OrderBookService;
+    use rust_decimal_macros::dec;
+
+    fn api_with(book_ticker_service: Arc<BookTickerService>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        create_ws_push_api(book_ticker_service, Arc::new(OrderBookService::new()), Arc::new(TradeHistoryService::new()),
+            CancellationToken::new())
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_client_receives_ticker_frames() {
+        let book_ticker_service = Arc::new(BookTickerService::new());
+        let api = api_with(book_ticker_service.clone());
+        let mut client = warp::test::ws().path("/ws").handshake(api).await.unwrap();
+
+        client.send_text(r#"{"method":"subscribe","channel":"ticker","symbol":"wstest"}"#).await;
+        let ack: serde_json::Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+        assert_eq!(ack["result"], "subscribed");
+        assert_eq!(ack["symbol"], "WSTEST");
+
+        book_ticker_service.update_ticker(&BookTickerData {
+            update_id: 7,
+            symbol: "WSTEST".to_string(),
+            best_bid_price: dec!(1.0),
+            best_bid_qty: dec!(2.0),
+            best_ask_price: dec!(3.0),
+            best_ask_qty: dec!(4.0),
+        }.to_top_of_book()).await;
+
+        let pushed: serde_json::Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+        assert_eq!(pushed["channel"], "ticker");
+        assert_eq!(pushed["data"]["update_id"], 7);
+        assert_eq!(pushed["data"]["best_ask_price"], "3.0");
+        assert!(pushed["data"]["received_at"].is_u64());
+        assert_eq!(pushed["stale"], false);
+    }
+
+    #[tokio::test]
+    async fn test_frames_of_data_older_than_the_maximum_age_are_marked_stale() {
+        let clock = Arc::new(ReplayClock::default());
+        let book_ticker_service = Arc::new(BookTickerService::with_clock(clock.clone()).with_max_age(Duration::ZERO));
+        let api = api_with(book_ticker_service.clone());
+        let mut client = warp::test::ws().path("/ws").handshake(api).await.unwrap();
+        client.send_text(r#"{"method":"subscribe","channel":"ticker","symbol":"WSSTALE"}"#).await;
+        client.recv().await.unwrap();
+
+        // The ticker is received at 1000 and pushed after the clock moved on
+        clock.set(1_000);
+        book_ticker_service.update_ticker(&BookTickerData {
+            update_id: 1,
+            symbol: "WSSTALE".to_string(),
+            ..Default::default()
+        }.to_top_of_book()).await;
+        clock.set(1_001);
+
+        let pushed: serde_json::Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+        assert_eq!((&pushed["data"]["received_at"], &pushed["data"]["event_time"]), (&serde_json::json!(1_000), &serde_json::Value::Null));
+        assert_eq!(pushed["stale"], true);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_request_returns_error_frame() {
+        let api = api_with(Arc::new(BookTickerService::new()));
+        let mut client = warp::test::ws().path("/ws").handshake(api).await.unwrap();
+
+        client.send_text(r#"{"method":"subscribe","channel":"candles","symbol":"BTCFDUSD"}"#).await;
+        let reply: serde_json::Value = serde_json::from_str(client.recv().await.unwrap().to_str().unwrap()).unwrap();
+        assert!(reply["error"].as_str().unwrap().starts_with("Invalid request"));
+    }
+
+    #[tokio::test]
+    async fn test_clients_are_closed_on_shutdown() {
+        let shutdown = CancellationToken::new();
+        let api = create_ws_push_api(Arc::new(BookTickerService::new()), Arc::new(OrderBookService::new()),
+            Arc::new(TradeHistoryService::new()), shutdown.clone());
+        let mut client = warp::test::ws().path("/ws").handshake(api).await.unwrap();
+
+        shutdown.cancel();
+        client.recv_closed().await.unwrap();
+    }
+}
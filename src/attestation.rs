@@ -0,0 +1,188 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ed25519_dalek::{Signature, Signer as _, SigningKey, Verifier, VerifyingKey};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use crate::config::CONFIG;
+use crate::domain::entities::book_ticker::BookTickerData;
+use crate::domain::entities::order_book::OrderBookTop;
+use crate::domain::entities::reference_price::Vwap;
+use crate::domain::entities::symbol::normalize_symbol;
+
+pub const ALGORITHM: &str = "ed25519";
+
+// What is attested, the signed values depend on the kind
+pub const KIND_MID_PRICE: &str = "mid_price";
+pub const KIND_VWAP: &str = "vwap";
+pub const KIND_TOP_OF_BOOK: &str = "top_of_book";
+
+// Signed content of an attestation. The signature covers its canonical form: the JSON serialization
+// without whitespace, fields in this order and the values sorted by name (see `canonical_bytes`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestationPayload {
+    pub kind: String,
+    pub symbol: String,
+    pub values: BTreeMap<String, String>, // Decimal strings with the exchange precision
+    pub source_update_ids: Vec<u64>,      // Ticker update id, order book update id or first and last trade id
+    pub timestamp: u64,                   // Signing time in milliseconds since the UNIX epoch
+}
+
+impl AttestationPayload {
+    // Payload signed now
+    pub fn new(kind: &str, symbol: &str, values: &[(&str, String)], source_update_ids: Vec<u64>) -> Self {
+        AttestationPayload {
+            kind: kind.to_string(),
+            symbol: symbol.to_string(),
+            values: values.iter().map(|(name, value)| (name.to_string(), value.clone())).collect(),
+            source_update_ids,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+        }
+    }
+
+    // Mid price of the ticker with its update id
+    pub fn mid_price(ticker: &BookTickerData) -> Self {
+        Self::new(KIND_MID_PRICE, &normalize_symbol(&ticker.symbol),
+            &[("mid_price", ticker.mid_price().to_string())], vec![ticker.update_id])
+    }
+
+    // VWAP of a window with the first and last trade id in it
+    pub fn vwap(symbol: &str, vwap: &Vwap) -> Self {
+        Self::new(KIND_VWAP, &normalize_symbol(symbol), &[
+            ("vwap", vwap.vwap.to_string()),
+            ("volume", vwap.volume.to_string()),
+            ("window_secs", vwap.window_secs.to_string()),
+        ], vec![vwap.trade_ids.0, vwap.trade_ids.1])
+    }
+
+    // Best bid and ask with the update id of the order book
+    pub fn top_of_book(symbol: &str, top: &OrderBookTop) -> Self {
+        Self::new(KIND_TOP_OF_BOOK, &normalize_symbol(symbol), &[
+            ("best_bid_price", top.best_bid.price.to_string()),
+            ("best_bid_qty", top.best_bid.qty.to_string()),
+            ("best_ask_price", top.best_ask.price.to_string()),
+            ("best_ask_qty", top.best_ask.qty.to_string()),
+        ], vec![top.last_update_id])
+    }
+
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Attestation payloads always serialize")
+    }
+}
+
+// A payload with its signature and the public key to check it with, both hex encoded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub payload: AttestationPayload,
+    pub signature: String,
+    pub public_key: String,
+}
+
+// Signs payloads with the node's ed25519 key
+pub struct AttestationSigner {
+    key: SigningKey,
+}
+
+impl AttestationSigner {
+    // The key is the hex encoded 32 byte seed, e.g. from `openssl rand -hex 32`
+    pub fn from_seed_hex(seed_hex: &str) -> Result<Self, String> {
+        let seed: [u8; 32] = hex::decode(seed_hex.trim())
+            .map_err(|e| format!("The signing key is not hex encoded: {}", e))?
+            .try_into()
+            .map_err(|seed: Vec<u8>| format!("The signing key has to be 32 bytes, got {}", seed.len()))?;
+        Ok(AttestationSigner { key: SigningKey::from_bytes(&seed) })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let seed_hex = fs::read_to_string(path).map_err(|e| format!("Cannot read the signing key {}: {}", path, e))?;
+        Self::from_seed_hex(&seed_hex)
+    }
+
+    pub fn public_key_hex(&self) -> String {
+        hex::encode(self.key.verifying_key().as_bytes())
+    }
+
+    pub fn sign(&self, payload: AttestationPayload) -> Attestation {
+        let signature = self.key.sign(&payload.canonical_bytes());
+        Attestation {
+            payload,
+            signature: hex::encode(signature.to_bytes()),
+            public_key: self.public_key_hex(),
+        }
+    }
+}
+
+// Check that an attestation was signed by the holder of `public_key_hex` and has not been altered.
+// The public key of the attestation itself is not trusted, consumers pin the key of the node.
+pub fn verify(attestation: &Attestation, public_key_hex: &str) -> Result<(), String> {
+    let public_key: [u8; 32] = hex::decode(public_key_hex)
+        .map_err(|e| format!("The public key is not hex encoded: {}", e))?
+        .try_into()
+        .map_err(|_| "The public key has to be 32 bytes".to_string())?;
+    let public_key = VerifyingKey::from_bytes(&public_key).map_err(|e| format!("Invalid public key: {}", e))?;
+    let signature: [u8; 64] = hex::decode(&attestation.signature)
+        .map_err(|e| format!("The signature is not hex encoded: {}", e))?
+        .try_into()
+        .map_err(|_| "The signature has to be 64 bytes".to_string())?;
+
+    public_key.verify(&attestation.payload.canonical_bytes(), &Signature::from_bytes(&signature))
+        .map_err(|_| "The signature does not match the payload".to_string())
+}
+
+// Signer of the node, `None` when no signing key is configured
+pub static SIGNER: Lazy<Option<AttestationSigner>> = Lazy::new(|| {
+    let path = &CONFIG.default.signing_key_file;
+    if path.is_empty() {
+        return None;
+    }
+    match AttestationSigner::from_file(path) {
+        Ok(signer) => {
+            log::info!("Signing attestations with public key {}", signer.public_key_hex());
+            Some(signer)
+        }
+        Err(e) => {
+            log::error!("Failed to load the signing key: {}", e);
+            panic!("Failed to load the signing key: {}", e);
+        }
+    }
+});
+
+// Sign the payload built by `payload` if signing is configured
+pub fn attest(payload: impl FnOnce() -> AttestationPayload) -> Option<Attestation> {
+    SIGNER.as_ref().map(|signer| signer.sign(payload()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+    #[test]
+    fn test_signed_payload_verifies() {
+        let signer = AttestationSigner::from_seed_hex(SEED).unwrap();
+        // Public key of the RFC 8032 test vector 1
+        assert_eq!(signer.public_key_hex(), "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+
+        let payload = AttestationPayload::new(KIND_MID_PRICE, "BTCFDUSD", &[("mid_price", "50000.005".to_string())], vec![42]);
+        let attestation = signer.sign(payload);
+        assert!(verify(&attestation, &signer.public_key_hex()).is_ok());
+
+        let canonical = String::from_utf8(attestation.payload.canonical_bytes()).unwrap();
+        assert!(canonical.starts_with(r#"{"kind":"mid_price","symbol":"BTCFDUSD","values":{"mid_price":"50000.005"},"source_update_ids":[42],"timestamp":"#));
+    }
+
+    #[test]
+    fn test_altered_attestations_are_rejected() {
+        let signer = AttestationSigner::from_seed_hex(SEED).unwrap();
+        let attestation = signer.sign(AttestationPayload::new(KIND_VWAP, "BTCFDUSD", &[("vwap", "1.5".to_string())], vec![1, 9]));
+
+        let mut altered = attestation.clone();
+        altered.payload.values.insert("vwap".to_string(), "1.6".to_string());
+        assert!(verify(&altered, &signer.public_key_hex()).is_err());
+
+        let other = AttestationSigner::from_seed_hex(&"11".repeat(32)).unwrap();
+        assert!(verify(&attestation, &other.public_key_hex()).is_err());
+        assert!(AttestationSigner::from_seed_hex("abcd").is_err());
+    }
+}
@@ -2,24 +2,192 @@ use std::env;
 use config::{Config, File};
 use serde::Deserialize;
 use std::error::Error;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use once_cell::sync::Lazy;
+use crate::domain::entities::consolidated_price::ConsolidationMethod;
+use crate::domain::entities::publication::PublisherPolicy;
+use crate::domain::entities::symbol::{normalize_symbol, VenueProduct};
 
 #[derive(Debug, Deserialize)]
 pub struct DefaultConfig {
     pub server_port_http: u16,
     pub server_port_grpc: u16,
-    pub trading_pair: String,
+    pub trading_pairs: Vec<String>,
+    pub ws_url: String,
+    pub ws_max_streams: usize,
     pub book_depth: u16,
     pub ws_config_retry_max: u16,
+    pub ws_backoff_initial_ms: u64,
+    pub ws_backoff_max_secs: u64,
+    pub feed_dead_policy: DeadFeedPolicy,
+    pub order_book_mode: OrderBookMode,
+    pub depth_snapshot_url: String,
+    pub depth_snapshot_limit: u16,
+    pub grpc_stream_buffer: usize,
+    pub readiness_max_staleness_secs: u64,
+    pub recorder_streams: Vec<String>,
+    pub recorder_dir: String,
+    pub recorder_max_file_mb: u64,
+    pub recorder_rotate_secs: u64,
+    pub recorder_compress: bool,
+    pub ingestion_mode: IngestionMode,
+    pub replay_dir: String,
+    pub replay_speed: f64,
+    pub reference_price_windows_secs: Vec<u64>,
+    pub trade_stats_windows_secs: Vec<u64>,
+    pub signing_key_file: String,
+    pub publishers: Vec<PublisherPolicy>,
+    pub max_data_age_secs: u64,
+    pub max_trade_age_secs: u64,
+    pub shutdown_drain_secs: u64,
+    pub coinbase_ws_url: String,
+    pub coinbase_products: Vec<VenueProduct>,
+    pub consolidation_method: ConsolidationMethod,
+    pub consolidation_min_venues: usize,
+}
+
+impl DefaultConfig {
+    // Window of a VWAP/TWAP query in seconds, `None` (or 0) picks the first configured window
+    pub fn reference_price_window(&self, window_secs: Option<u64>) -> Result<u64, String> {
+        match window_secs.filter(|secs| *secs > 0) {
+            None => Ok(self.reference_price_windows_secs[0]),
+            Some(secs) if self.reference_price_windows_secs.contains(&secs) => Ok(secs),
+            Some(secs) => Err(format!("Window {}s is not one of the configured windows {:?}", secs, self.reference_price_windows_secs)),
+        }
+    }
+
+    // The services keep trades and ticker samples for the longest window
+    pub fn max_reference_price_window(&self) -> Duration {
+        Duration::from_secs(self.reference_price_windows_secs.iter().copied().max().unwrap_or_default())
+    }
+
+    // Window of a trade volume/flow query in seconds, `None` (or 0) picks the first configured window
+    pub fn trade_stats_window(&self, window_secs: Option<u64>) -> Result<u64, String> {
+        match window_secs.filter(|secs| *secs > 0) {
+            None => Ok(self.trade_stats_windows_secs[0]),
+            Some(secs) if self.trade_stats_windows_secs.contains(&secs) => Ok(secs),
+            Some(secs) => Err(format!("Window {}s is not one of the configured windows {:?}", secs, self.trade_stats_windows_secs)),
+        }
+    }
+
+    // Whether the symbol is one of the configured trading pairs, data missing for one of them is unavailable
+    pub fn is_trading_pair(&self, symbol: &str) -> bool {
+        self.trading_pairs.contains(&normalize_symbol(symbol))
+    }
+
+    // Trades are kept for the longest trade statistics or VWAP window
+    pub fn trade_retention(&self) -> Duration {
+        let max_stats_window = Duration::from_secs(self.trade_stats_windows_secs.iter().copied().max().unwrap_or_default());
+        max_stats_window.max(self.max_reference_price_window())
+    }
+}
+
+// How the local order book is maintained
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderBookMode {
+    // Partial depth stream (5/10/20 levels), every message replaces the book
+    Partial,
+    // Diff depth stream synchronised with a REST snapshot, full depth
+    Diff,
+}
+
+impl FromStr for OrderBookMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "partial" => Ok(OrderBookMode::Partial),
+            "diff" => Ok(OrderBookMode::Diff),
+            other => Err(format!("Unknown order book mode: {}", other)),
+        }
+    }
+}
+
+// Where the market data comes from
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IngestionMode {
+    // Binance WebSocket streams
+    Live,
+    // Files written by the recorder, no network access needed
+    Replay,
+}
+
+impl FromStr for IngestionMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "live" => Ok(IngestionMode::Live),
+            "replay" => Ok(IngestionMode::Replay),
+            other => Err(format!("Unknown ingestion mode: {}", other)),
+        }
+    }
+}
+
+// What happens to a feed whose connection failed `ws_config_retry_max` times in a row
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadFeedPolicy {
+    // Keep reconnecting at the maximum backoff delay
+    Retry,
+    // Stop the application with a non-zero exit code so it gets restarted
+    Exit,
+    // Stop reconnecting, the feed is reported dead and not ready
+    Unready,
+}
+
+impl FromStr for DeadFeedPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "retry" => Ok(DeadFeedPolicy::Retry),
+            "exit" => Ok(DeadFeedPolicy::Exit),
+            "unready" => Ok(DeadFeedPolicy::Unready),
+            other => Err(format!("Unknown dead feed policy: {}", other)),
+        }
+    }
 }
 
 enum EnvVar {
     ServerPortHTTP,
     ServerPortGRPC,
-    TradingPair,
+    TradingPairs,
+    WsUrl,
+    WsMaxStreams,
     BookDepth,
-    WSConfigRetryMax
+    WSConfigRetryMax,
+    WsBackoffInitialMs,
+    WsBackoffMaxSecs,
+    FeedDeadPolicy,
+    OrderBookMode,
+    DepthSnapshotUrl,
+    DepthSnapshotLimit,
+    GrpcStreamBuffer,
+    ReadinessMaxStalenessSecs,
+    RecorderStreams,
+    RecorderDir,
+    RecorderMaxFileMb,
+    RecorderRotateSecs,
+    RecorderCompress,
+    IngestionMode,
+    ReplayDir,
+    ReplaySpeed,
+    ReferencePriceWindowsSecs,
+    TradeStatsWindowsSecs,
+    SigningKeyFile,
+    Publishers,
+    MaxDataAgeSecs,
+    MaxTradeAgeSecs,
+    ShutdownDrainSecs,
+    CoinbaseWsUrl,
+    CoinbaseProducts,
+    ConsolidationMethod,
+    ConsolidationMinVenues,
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,9 +213,38 @@ impl EnvVar {
         match self {
             EnvVar::ServerPortHTTP => "SERVER_PORT_HTTP",
             EnvVar::ServerPortGRPC => "SERVER_PORT_GRPC",
-            EnvVar::TradingPair => "TRADING_PAIR",
+            EnvVar::TradingPairs => "TRADING_PAIRS",
+            EnvVar::WsUrl => "WS_URL",
+            EnvVar::WsMaxStreams => "WS_MAX_STREAMS",
             EnvVar::BookDepth => "BOOK_DEPTH",
-            EnvVar::WSConfigRetryMax => "WS_CONFIG_RETRY_MAX"
+            EnvVar::WSConfigRetryMax => "WS_CONFIG_RETRY_MAX",
+            EnvVar::WsBackoffInitialMs => "WS_BACKOFF_INITIAL_MS",
+            EnvVar::WsBackoffMaxSecs => "WS_BACKOFF_MAX_SECS",
+            EnvVar::FeedDeadPolicy => "FEED_DEAD_POLICY",
+            EnvVar::OrderBookMode => "ORDER_BOOK_MODE",
+            EnvVar::DepthSnapshotUrl => "DEPTH_SNAPSHOT_URL",
+            EnvVar::DepthSnapshotLimit => "DEPTH_SNAPSHOT_LIMIT",
+            EnvVar::GrpcStreamBuffer => "GRPC_STREAM_BUFFER",
+            EnvVar::ReadinessMaxStalenessSecs => "READINESS_MAX_STALENESS_SECS",
+            EnvVar::RecorderStreams => "RECORDER_STREAMS",
+            EnvVar::RecorderDir => "RECORDER_DIR",
+            EnvVar::RecorderMaxFileMb => "RECORDER_MAX_FILE_MB",
+            EnvVar::RecorderRotateSecs => "RECORDER_ROTATE_SECS",
+            EnvVar::RecorderCompress => "RECORDER_COMPRESS",
+            EnvVar::IngestionMode => "INGESTION_MODE",
+            EnvVar::ReplayDir => "REPLAY_DIR",
+            EnvVar::ReplaySpeed => "REPLAY_SPEED",
+            EnvVar::ReferencePriceWindowsSecs => "REFERENCE_PRICE_WINDOWS_SECS",
+            EnvVar::TradeStatsWindowsSecs => "TRADE_STATS_WINDOWS_SECS",
+            EnvVar::SigningKeyFile => "SIGNING_KEY_FILE",
+            EnvVar::Publishers => "PUBLISHERS",
+            EnvVar::MaxDataAgeSecs => "MAX_DATA_AGE_SECS",
+            EnvVar::MaxTradeAgeSecs => "MAX_TRADE_AGE_SECS",
+            EnvVar::ShutdownDrainSecs => "SHUTDOWN_DRAIN_SECS",
+            EnvVar::CoinbaseWsUrl => "COINBASE_WS_URL",
+            EnvVar::CoinbaseProducts => "COINBASE_PRODUCTS",
+            EnvVar::ConsolidationMethod => "CONSOLIDATION_METHOD",
+            EnvVar::ConsolidationMinVenues => "CONSOLIDATION_MIN_VENUES",
         }
     }
 
@@ -61,6 +258,18 @@ impl EnvVar {
             .and_then(|val| val.parse::<T>().ok()) // Try to parse the value to type T
             .unwrap_or_else(|| default.clone()) // Clone the default value if parsing fails
     }
+
+    // Fetches a comma separated list from the environment (e.g. "BTCUSDT,ETHUSDT"), or returns the default list
+    fn get_list(&self, default: &[String]) -> Vec<String> {
+        env::var(self.as_str())
+            .ok()
+            .map(|val| val.split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<String>>())
+            .filter(|items| !items.is_empty())
+            .unwrap_or_else(|| default.to_vec())
+    }
 }
 
 // Loads the configuration from a file (config.toml)
@@ -89,8 +298,21 @@ pub fn load_config_from_env_or_file() -> Result<AppConfig, Box<dyn Error>> {
     config.default.server_port_grpc = EnvVar::ServerPortGRPC
         .get_value(&config.default.server_port_grpc); // u16 for server_port_grpc
 
-    config.default.trading_pair = EnvVar::TradingPair
-        .get_value(&config.default.trading_pair); // String for trading_pair
+    config.default.trading_pairs = EnvVar::TradingPairs
+        .get_list(&config.default.trading_pairs) // Vec<String> for trading_pairs
+        .iter()
+        .map(|pair| normalize_symbol(pair))
+        .collect();
+
+    if config.default.trading_pairs.is_empty() {
+        return Err("At least one trading pair has to be configured".into());
+    }
+
+    config.default.ws_url = EnvVar::WsUrl
+        .get_value(&config.default.ws_url); // String for the combined stream WebSocket endpoint
+
+    config.default.ws_max_streams = EnvVar::WsMaxStreams
+        .get_value(&config.default.ws_max_streams); // usize for the streams subscribed to on a single connection
 
     config.default.book_depth = EnvVar::BookDepth
         .get_value(&config.default.book_depth); // u16 for book_depth
@@ -98,6 +320,143 @@ pub fn load_config_from_env_or_file() -> Result<AppConfig, Box<dyn Error>> {
     config.default.ws_config_retry_max = EnvVar::WSConfigRetryMax
         .get_value(&config.default.ws_config_retry_max); //u16 for ws retry max
 
+    config.default.ws_backoff_initial_ms = EnvVar::WsBackoffInitialMs
+        .get_value(&config.default.ws_backoff_initial_ms); // u64 for the delay after the first failed connection
+
+    config.default.ws_backoff_max_secs = EnvVar::WsBackoffMaxSecs
+        .get_value(&config.default.ws_backoff_max_secs); // u64 for the longest delay between connection attempts
+
+    if config.default.ws_backoff_initial_ms == 0 || config.default.ws_backoff_initial_ms > config.default.ws_backoff_max_secs * 1000 {
+        return Err("ws_backoff_initial_ms has to be positive and at most ws_backoff_max_secs".into());
+    }
+
+    config.default.feed_dead_policy = EnvVar::FeedDeadPolicy
+        .get_value(&config.default.feed_dead_policy); // retry, exit or unready
+
+    config.default.order_book_mode = EnvVar::OrderBookMode
+        .get_value(&config.default.order_book_mode); // partial or diff
+
+    config.default.depth_snapshot_url = EnvVar::DepthSnapshotUrl
+        .get_value(&config.default.depth_snapshot_url); // String for REST snapshot base url
+
+    config.default.depth_snapshot_limit = EnvVar::DepthSnapshotLimit
+        .get_value(&config.default.depth_snapshot_limit); // u16 for REST snapshot depth
+
+    config.default.grpc_stream_buffer = EnvVar::GrpcStreamBuffer
+        .get_value(&config.default.grpc_stream_buffer); // usize for per-subscriber stream buffer
+
+    config.default.readiness_max_staleness_secs = EnvVar::ReadinessMaxStalenessSecs
+        .get_value(&config.default.readiness_max_staleness_secs); // u64 for feed freshness threshold
+
+    config.default.recorder_streams = EnvVar::RecorderStreams
+        .get_list(&config.default.recorder_streams); // Vec<String> of recorded streams (order_book, book_ticker, trade)
+
+    config.default.recorder_dir = EnvVar::RecorderDir
+        .get_value(&config.default.recorder_dir); // String for the recordings directory
+
+    config.default.recorder_max_file_mb = EnvVar::RecorderMaxFileMb
+        .get_value(&config.default.recorder_max_file_mb); // u64 for size based rotation
+
+    config.default.recorder_rotate_secs = EnvVar::RecorderRotateSecs
+        .get_value(&config.default.recorder_rotate_secs); // u64 for time based rotation
+
+    config.default.recorder_compress = EnvVar::RecorderCompress
+        .get_value(&config.default.recorder_compress); // bool for zstd compression of recordings
+
+    config.default.ingestion_mode = EnvVar::IngestionMode
+        .get_value(&config.default.ingestion_mode); // live or replay
+
+    config.default.replay_dir = EnvVar::ReplayDir
+        .get_value(&config.default.replay_dir); // String for the directory with recordings to replay
+
+    config.default.replay_speed = EnvVar::ReplaySpeed
+        .get_value(&config.default.replay_speed); // f64 pacing factor, 0 replays as fast as possible
+
+    if config.default.replay_speed < 0.0 || !config.default.replay_speed.is_finite() {
+        return Err("replay_speed has to be a non-negative number".into());
+    }
+
+    let default_windows: Vec<String> = config.default.reference_price_windows_secs.iter().map(u64::to_string).collect();
+    config.default.reference_price_windows_secs = EnvVar::ReferencePriceWindowsSecs
+        .get_list(&default_windows) // Vec<u64> of VWAP/TWAP windows in seconds
+        .iter()
+        .map(|window| window.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("reference_price_windows_secs has to be a list of seconds: {}", e))?;
+
+    if config.default.reference_price_windows_secs.is_empty() || config.default.reference_price_windows_secs.contains(&0) {
+        return Err("At least one non-zero reference price window has to be configured".into());
+    }
+
+    let default_windows: Vec<String> = config.default.trade_stats_windows_secs.iter().map(u64::to_string).collect();
+    config.default.trade_stats_windows_secs = EnvVar::TradeStatsWindowsSecs
+        .get_list(&default_windows) // Vec<u64> of trade volume/flow windows in seconds
+        .iter()
+        .map(|window| window.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("trade_stats_windows_secs has to be a list of seconds: {}", e))?;
+
+    if config.default.trade_stats_windows_secs.is_empty() || config.default.trade_stats_windows_secs.contains(&0) {
+        return Err("At least one non-zero trade statistics window has to be configured".into());
+    }
+
+    config.default.signing_key_file = EnvVar::SigningKeyFile
+        .get_value(&config.default.signing_key_file); // String path of the hex encoded ed25519 seed, empty disables signing
+
+    let default_publishers: Vec<String> = config.default.publishers.iter().map(PublisherPolicy::to_string).collect();
+    config.default.publishers = EnvVar::Publishers
+        .get_list(&default_publishers) // Vec<PublisherPolicy> written as SYMBOL:source:deviation_bps:heartbeat_secs
+        .iter()
+        .map(|publisher| publisher.parse::<PublisherPolicy>())
+        .collect::<Result<_, _>>()?;
+
+    for (index, publisher) in config.default.publishers.iter().enumerate() {
+        if !config.default.trading_pairs.contains(&publisher.symbol) {
+            return Err(format!("Publisher {} is not for one of the trading pairs", publisher).into());
+        }
+        if config.default.publishers[..index].iter().any(|other| other.symbol == publisher.symbol) {
+            return Err(format!("More than one publisher is configured for {}", publisher.symbol).into());
+        }
+    }
+
+    config.default.max_data_age_secs = EnvVar::MaxDataAgeSecs
+        .get_value(&config.default.max_data_age_secs); // u64 for the maximum age of served ticker and order book data
+
+    config.default.max_trade_age_secs = EnvVar::MaxTradeAgeSecs
+        .get_value(&config.default.max_trade_age_secs); // u64 for the maximum age of the last trade behind served statistics
+
+    config.default.shutdown_drain_secs = EnvVar::ShutdownDrainSecs
+        .get_value(&config.default.shutdown_drain_secs); // u64 for the time open connections get to finish on shutdown
+
+    config.default.coinbase_ws_url = EnvVar::CoinbaseWsUrl
+        .get_value(&config.default.coinbase_ws_url); // String for the Coinbase Advanced Trade WebSocket endpoint
+
+    let default_products: Vec<String> = config.default.coinbase_products.iter().map(VenueProduct::to_string).collect();
+    config.default.coinbase_products = EnvVar::CoinbaseProducts
+        .get_list(&default_products) // Vec<VenueProduct> written as SYMBOL:PRODUCT_ID
+        .iter()
+        .map(|product| product.parse::<VenueProduct>())
+        .collect::<Result<_, _>>()?;
+
+    for (index, product) in config.default.coinbase_products.iter().enumerate() {
+        if !config.default.trading_pairs.contains(&product.symbol) {
+            return Err(format!("Coinbase product {} is not for one of the trading pairs", product).into());
+        }
+        if config.default.coinbase_products[..index].iter().any(|other| other.symbol == product.symbol) {
+            return Err(format!("More than one Coinbase product is configured for {}", product.symbol).into());
+        }
+    }
+
+    config.default.consolidation_method = EnvVar::ConsolidationMethod
+        .get_value(&config.default.consolidation_method); // median or volume_weighted
+
+    config.default.consolidation_min_venues = EnvVar::ConsolidationMinVenues
+        .get_value(&config.default.consolidation_min_venues); // usize for the venues with fresh quotes a consolidated price needs
+
+    if config.default.consolidation_min_venues == 0 {
+        return Err("consolidation_min_venues has to be at least 1".into());
+    }
+
     log::info!("Config loaded: {:?}",config);
 
     Ok(config)
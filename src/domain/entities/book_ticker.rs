@@ -1,4 +1,7 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::domain::entities::market_event::TopOfBook;
+use crate::domain::entities::order_book::OrderBookEntry;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BookTickerSD {
@@ -13,16 +16,27 @@ pub struct BookTickerData {
     #[serde(rename = "s")]
     pub symbol: String,   // Symbol (e.g., BTCFDUSD)
     #[serde(rename = "b")]
-    pub best_bid_price: String,   // Best bid price
+    pub best_bid_price: Decimal,   // Best bid price
     #[serde(rename = "B")]
-    pub best_bid_qty: String,   // Best bid quantity
+    pub best_bid_qty: Decimal,   // Best bid quantity
     #[serde(rename = "a")]
-    pub best_ask_price: String,   // Best ask price
+    pub best_ask_price: Decimal,   // Best ask price
     #[serde(rename = "A")]
-    pub best_ask_qty: String,   // Best ask quantity
+    pub best_ask_qty: Decimal,   // Best ask quantity
 }
 
 impl BookTickerData {
+    // Normalized top of book, the spot book ticker carries no event time
+    pub fn to_top_of_book(&self) -> TopOfBook {
+        TopOfBook {
+            symbol: self.symbol.clone(),
+            update_id: self.update_id,
+            best_bid: OrderBookEntry { price: self.best_bid_price, qty: self.best_bid_qty },
+            best_ask: OrderBookEntry { price: self.best_ask_price, qty: self.best_ask_qty },
+            event_time: None,
+        }
+    }
+
     // Method to print the data in a formatted way
     pub fn print(&self) {
         log::info!("Symbol: {}", self.symbol);
@@ -31,45 +45,52 @@ impl BookTickerData {
         log::info!("Best Ask: {} @ {}", self.best_ask_qty, self.best_ask_price);
     }
 
-    pub fn mid_price(&self) -> f64 {
-        let best_bid = self.best_bid_price.parse::<f64>().unwrap_or(0.0);
-        let best_ask = self.best_ask_price.parse::<f64>().unwrap_or(0.0);
-        (best_bid + best_ask) / 2.0
+    pub fn mid_price(&self) -> Decimal {
+        (self.best_bid_price + self.best_ask_price) / Decimal::TWO
     }
 
     // Method to calculate the mid-weighted price (weighted by bid and ask quantities)
-    pub fn mid_weighted_price(&self) -> f64 {
-        let best_bid = self.best_bid_price.parse::<f64>().unwrap_or(0.0);
-        let best_ask = self.best_ask_price.parse::<f64>().unwrap_or(0.0);
-        let bid_qty = self.best_bid_qty.parse::<f64>().unwrap_or(0.0);
-        let ask_qty = self.best_ask_qty.parse::<f64>().unwrap_or(0.0);
-
-        if bid_qty + ask_qty == 0.0 {
-            return 0.0; // Avoid division by zero
+    pub fn mid_weighted_price(&self) -> Decimal {
+        let total_qty = self.best_bid_qty + self.best_ask_qty;
+
+        if total_qty.is_zero() {
+            return Decimal::ZERO; // Avoid division by zero
         }
 
         // Weighted average formula
-        ((best_bid * bid_qty) + (best_ask * ask_qty)) / (bid_qty + ask_qty)
+        ((self.best_bid_price * self.best_bid_qty) + (self.best_ask_price * self.best_ask_qty)) / total_qty
+    }
+
+    // Microprice: the mid leaning towards the side with less quantity, which is the side more likely to be taken out
+    pub fn microprice(&self) -> Decimal {
+        let total_qty = self.best_bid_qty + self.best_ask_qty;
+
+        if total_qty.is_zero() {
+            return self.mid_price();
+        }
+
+        ((self.best_bid_price * self.best_ask_qty) + (self.best_ask_price * self.best_bid_qty)) / total_qty
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn test_mid_price() {
         let data = BookTickerData {
             update_id: 123,
             symbol: "BTCUSD".to_string(),
-            best_bid_price: "50000.0".to_string(),
-            best_bid_qty: "2.0".to_string(),
-            best_ask_price: "51000.0".to_string(),
-            best_ask_qty: "3.0".to_string(),
+            best_bid_price: dec!(50000.0),
+            best_bid_qty: dec!(2.0),
+            best_ask_price: dec!(51000.0),
+            best_ask_qty: dec!(3.0),
         };
 
         let mid_price = data.mid_price();
-        assert_eq!(mid_price, 50500.0); // (50000 + 51000) / 2
+        assert_eq!(mid_price, dec!(50500)); // (50000 + 51000) / 2
     }
 
     #[test]
@@ -77,15 +98,30 @@ mod tests {
         let data = BookTickerData {
             update_id: 123,
             symbol: "BTCUSD".to_string(),
-            best_bid_price: "50000.0".to_string(),
-            best_bid_qty: "2.0".to_string(),
-            best_ask_price: "51000.0".to_string(),
-            best_ask_qty: "3.0".to_string(),
+            best_bid_price: dec!(50000.0),
+            best_bid_qty: dec!(2.0),
+            best_ask_price: dec!(51000.0),
+            best_ask_qty: dec!(3.0),
         };
 
         let mid_weighted_price = data.mid_weighted_price();
         // Weighted average: (50000 * 2 + 51000 * 3) / (2 + 3) = 50600
-        assert_eq!(mid_weighted_price, 50600.0);
+        assert_eq!(mid_weighted_price, dec!(50600));
+    }
+
+    #[test]
+    fn test_microprice_leans_towards_the_thinner_side() {
+        let data = BookTickerData {
+            update_id: 123,
+            symbol: "BTCUSD".to_string(),
+            best_bid_price: dec!(50000.0),
+            best_bid_qty: dec!(2.0),
+            best_ask_price: dec!(51000.0),
+            best_ask_qty: dec!(3.0),
+        };
+
+        // (50000 * 3 + 51000 * 2) / (2 + 3) = 50400, closer to the bid which has less quantity
+        assert_eq!(data.microprice(), dec!(50400));
     }
 
     #[test]
@@ -93,15 +129,15 @@ mod tests {
         let data = BookTickerData {
             update_id: 123,
             symbol: "BTCUSD".to_string(),
-            best_bid_price: "50000.0".to_string(),
-            best_bid_qty: "0.0".to_string(),
-            best_ask_price: "51000.0".to_string(),
-            best_ask_qty: "0.0".to_string(),
+            best_bid_price: dec!(50000.0),
+            best_bid_qty: dec!(0.0),
+            best_ask_price: dec!(51000.0),
+            best_ask_qty: dec!(0.0),
         };
 
         let mid_weighted_price = data.mid_weighted_price();
-        // Both bid and ask quantities are zero, should return 0.0
-        assert_eq!(mid_weighted_price, 0.0);
+        // Both bid and ask quantities are zero, should return zero
+        assert_eq!(mid_weighted_price, Decimal::ZERO);
     }
 
     #[test]
@@ -109,14 +145,25 @@ mod tests {
         let data = BookTickerData {
             update_id: 123,
             symbol: "BTCUSD".to_string(),
-            best_bid_price: "0.0".to_string(),
-            best_bid_qty: "2.0".to_string(),
-            best_ask_price: "0.0".to_string(),
-            best_ask_qty: "3.0".to_string(),
+            best_bid_price: dec!(0.0),
+            best_bid_qty: dec!(2.0),
+            best_ask_price: dec!(0.0),
+            best_ask_qty: dec!(3.0),
         };
 
         let mid_price = data.mid_price();
-        // Both bid and ask prices are zero, mid price should be 0.0
-        assert_eq!(mid_price, 0.0);
+        // Both bid and ask prices are zero, mid price should be zero
+        assert_eq!(mid_price, Decimal::ZERO);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_decimal_fields_keep_exchange_precision() {
+        let data: BookTickerData = serde_json::from_str(
+            r#"{"u":1,"s":"BTCFDUSD","b":"0.10000000","B":"1.00000000","a":"0.20000000","A":"1.00000000"}"#
+        ).unwrap();
+
+        // 0.1 + 0.2 has no exact binary floating point representation
+        assert_eq!(data.mid_price(), dec!(0.15));
+        assert_eq!(serde_json::to_value(&data).unwrap()["b"], "0.10000000");
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use crate::domain::entities::trade::TradeData;
+
+// Candle intervals built from the trade stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum CandleInterval {
+    #[serde(rename = "1s")]
+    OneSecond,
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+}
+
+impl CandleInterval {
+    pub const ALL: [CandleInterval; 5] = [
+        CandleInterval::OneSecond,
+        CandleInterval::OneMinute,
+        CandleInterval::FiveMinutes,
+        CandleInterval::FifteenMinutes,
+        CandleInterval::OneHour,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneSecond => "1s",
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::OneHour => "1h",
+        }
+    }
+
+    pub fn millis(&self) -> u64 {
+        match self {
+            CandleInterval::OneSecond => 1_000,
+            CandleInterval::OneMinute => 60_000,
+            CandleInterval::FiveMinutes => 300_000,
+            CandleInterval::FifteenMinutes => 900_000,
+            CandleInterval::OneHour => 3_600_000,
+        }
+    }
+
+    // Start of the interval the given time falls into, intervals are aligned to the UNIX epoch
+    pub fn open_time(&self, time: u64) -> u64 {
+        time - time % self.millis()
+    }
+}
+
+impl FromStr for CandleInterval {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        CandleInterval::ALL.into_iter()
+            .find(|interval| interval.as_str() == value)
+            .ok_or_else(|| format!("Unknown candle interval: {}", value))
+    }
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// OHLCV candle of one trading pair, times are trade times in milliseconds since the UNIX epoch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candle {
+    pub symbol: String,
+    pub interval: CandleInterval,
+    pub open_time: u64,
+    pub close_time: u64,          // Last millisecond of the interval
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,          // Base asset volume
+    pub quote_volume: Decimal,    // Sum of price * quantity
+    pub trade_count: u64,
+    pub taker_buy_volume: Decimal, // Base asset volume of trades where the buyer was the taker
+    pub closed: bool,             // No more trades will be added
+}
+
+impl Candle {
+    // Candle opened by the first trade of its interval
+    pub fn open(symbol: &str, interval: CandleInterval, trade: &TradeData) -> Self {
+        let open_time = interval.open_time(trade.trade_time);
+        let mut candle = Candle {
+            symbol: symbol.to_string(),
+            interval,
+            open_time,
+            close_time: open_time + interval.millis() - 1,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: Decimal::ZERO,
+            quote_volume: Decimal::ZERO,
+            trade_count: 0,
+            taker_buy_volume: Decimal::ZERO,
+            closed: false,
+        };
+        candle.add_trade(trade);
+        candle
+    }
+
+    fn add_trade(&mut self, trade: &TradeData) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.quantity;
+        self.quote_volume += trade.price * trade.quantity;
+        self.trade_count += 1;
+        // The seller is the maker, so the buyer took liquidity
+        if !trade.is_buyer_market_maker {
+            self.taker_buy_volume += trade.quantity;
+        }
+    }
+}
+
+// Candles of one trading pair and interval: the closed ones (oldest first, bounded) and the one being built.
+// A candle closes on the first trade of a later interval or when `close_elapsed` finds its interval over.
+// Intervals without trades produce no candle.
+#[derive(Debug)]
+pub struct CandleSeries {
+    interval: CandleInterval,
+    max_closed: usize,
+    closed: VecDeque<Candle>,
+    current: Option<Candle>,
+}
+
+impl CandleSeries {
+    pub fn new(interval: CandleInterval, max_closed: usize) -> Self {
+        CandleSeries {
+            interval,
+            max_closed,
+            closed: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    // Apply a trade, returns the candles that changed (a closed one first, then the current one).
+    // Trades older than the current candle are ignored.
+    pub fn add_trade(&mut self, symbol: &str, trade: &TradeData) -> Vec<Candle> {
+        let open_time = self.interval.open_time(trade.trade_time);
+        let mut changed = Vec::new();
+
+        // The candle closed by the timer counts as the latest one, its interval takes no more trades
+        let latest_open_time = self.current.as_ref().or(self.closed.back()).map(|candle| candle.open_time);
+        match self.current.as_mut() {
+            Some(current) if current.open_time == open_time => current.add_trade(trade),
+            _ if latest_open_time.is_some_and(|latest| latest >= open_time) => {
+                log::debug!("Ignoring late {} trade {} for the {} candles", symbol, trade.trade_id, self.interval);
+                return changed;
+            }
+            _ => {
+                changed.extend(self.close_current());
+                self.current = Some(Candle::open(symbol, self.interval, trade));
+            }
+        }
+
+        changed.extend(self.current.clone());
+        changed
+    }
+
+    // Close the current candle when its interval ended before `now`, returns it once closed
+    pub fn close_elapsed(&mut self, now: u64) -> Option<Candle> {
+        match &self.current {
+            Some(current) if current.close_time < now => self.close_current(),
+            _ => None,
+        }
+    }
+
+    fn close_current(&mut self) -> Option<Candle> {
+        let mut finished = self.current.take()?;
+        finished.closed = true;
+        self.closed.push_back(finished.clone());
+        while self.closed.len() > self.max_closed {
+            self.closed.pop_front();
+        }
+        Some(finished)
+    }
+
+    // The most recent `limit` candles, oldest first, the last one may still be open
+    pub fn latest(&self, limit: usize) -> Vec<Candle> {
+        let candles: Vec<Candle> = self.closed.iter().chain(self.current.iter()).cloned().collect();
+        candles[candles.len().saturating_sub(limit)..].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn trade(trade_time: u64, price: Decimal, quantity: Decimal, is_buyer_market_maker: bool) -> TradeData {
+        TradeData {
+            event_type: "trade".to_string(),
+            event_time: trade_time,
+            symbol: "CANDLETEST".to_string(),
+            trade_id: trade_time,
+            price,
+            quantity,
+            trade_time,
+            is_buyer_market_maker,
+            ignore: false,
+        }
+    }
+
+    #[test]
+    fn test_candle_aggregates_trades() {
+        let mut series = CandleSeries::new(CandleInterval::OneMinute, 10);
+        series.add_trade("CANDLETEST", &trade(60_500, dec!(100), dec!(1), false));
+        series.add_trade("CANDLETEST", &trade(61_000, dec!(105.5), dec!(0.5), true));
+        series.add_trade("CANDLETEST", &trade(119_999, dec!(99), dec!(2), false));
+
+        let candles = series.latest(10);
+        assert_eq!(candles.len(), 1);
+        let candle = &candles[0];
+        assert_eq!((candle.open_time, candle.close_time), (60_000, 119_999));
+        assert_eq!((candle.open, candle.high, candle.low, candle.close), (dec!(100), dec!(105.5), dec!(99), dec!(99)));
+        assert_eq!(candle.volume, dec!(3.5));
+        assert_eq!(candle.quote_volume, dec!(350.75));
+        assert_eq!(candle.trade_count, 3);
+        assert_eq!(candle.taker_buy_volume, dec!(3));
+        assert!(!candle.closed);
+    }
+
+    #[test]
+    fn test_next_interval_closes_the_candle_and_history_is_bounded() {
+        let mut series = CandleSeries::new(CandleInterval::OneSecond, 2);
+        for second in 0..4 {
+            series.add_trade("CANDLETEST", &trade(second * 1_000, dec!(1), dec!(1), true));
+        }
+
+        // Closing the third candle reports it together with the new one
+        let changed = series.add_trade("CANDLETEST", &trade(4_000, dec!(2), dec!(1), true));
+        assert_eq!(changed.iter().map(|candle| (candle.open_time, candle.closed)).collect::<Vec<_>>(),
+            vec![(3_000, true), (4_000, false)]);
+
+        let open_times: Vec<u64> = series.latest(10).iter().map(|candle| candle.open_time).collect();
+        assert_eq!(open_times, vec![2_000, 3_000, 4_000]);
+        assert_eq!(series.latest(1)[0].open_time, 4_000);
+
+        // A trade of an interval that is already closed is ignored
+        assert!(series.add_trade("CANDLETEST", &trade(3_500, dec!(9), dec!(1), true)).is_empty());
+    }
+
+    #[test]
+    fn test_elapsed_interval_closes_without_a_later_trade() {
+        let mut series = CandleSeries::new(CandleInterval::OneMinute, 10);
+        series.add_trade("CANDLETEST", &trade(60_500, dec!(100), dec!(1), false));
+        assert_eq!(series.close_elapsed(119_999), None);
+
+        let closed = series.close_elapsed(120_000).unwrap();
+        assert_eq!((closed.open_time, closed.closed, closed.close), (60_000, true, dec!(100)));
+        assert_eq!(series.close_elapsed(200_000), None);
+        assert_eq!(series.latest(10), vec![closed]);
+
+        // The closed interval takes no more trades, the next one opens a candle
+        assert!(series.add_trade("CANDLETEST", &trade(119_000, dec!(9), dec!(1), true)).is_empty());
+        let changed = series.add_trade("CANDLETEST", &trade(180_000, dec!(101), dec!(1), true));
+        assert_eq!(changed.iter().map(|candle| (candle.open_time, candle.closed)).collect::<Vec<_>>(), vec![(180_000, false)]);
+    }
+
+    #[test]
+    fn test_interval_parsing() {
+        assert_eq!("15m".parse::<CandleInterval>(), Ok(CandleInterval::FifteenMinutes));
+        assert!("2m".parse::<CandleInterval>().is_err());
+        assert_eq!(CandleInterval::OneHour.open_time(7_250_000), 7_200_000);
+    }
+}
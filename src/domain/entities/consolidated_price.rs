@@ -0,0 +1,148 @@
+use std::fmt;
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use crate::domain::entities::freshness::DataTimes;
+use crate::domain::entities::order_book::OrderBookEntry;
+
+// How the quotes of several venues are combined into a single price
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsolidationMethod {
+    // Median of the venue mids, a single venue far off the others does not move it
+    Median,
+    // Venue mids weighted by the quantity at their best bid and ask
+    VolumeWeighted,
+}
+
+impl FromStr for ConsolidationMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "median" => Ok(ConsolidationMethod::Median),
+            "volume_weighted" => Ok(ConsolidationMethod::VolumeWeighted),
+            _ => Err(format!("Unknown consolidation method {}, expected median or volume_weighted", value)),
+        }
+    }
+}
+
+impl fmt::Display for ConsolidationMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConsolidationMethod::Median => "median",
+            ConsolidationMethod::VolumeWeighted => "volume_weighted",
+        })
+    }
+}
+
+impl ConsolidationMethod {
+    // Price of the quotes, `None` without quotes (or without quantity to weight them by)
+    pub fn price(&self, quotes: &[VenueQuote]) -> Option<Decimal> {
+        match self {
+            ConsolidationMethod::Median => median_mid(quotes),
+            ConsolidationMethod::VolumeWeighted => volume_weighted_mid(quotes),
+        }
+    }
+}
+
+// Latest top of book of a trading pair on one venue
+#[derive(Debug, Clone)]
+pub struct VenueQuote {
+    pub venue: String,
+    pub best_bid: OrderBookEntry,
+    pub best_ask: OrderBookEntry,
+    pub times: DataTimes,
+    pub fresh: bool, // Received within the maximum age, only fresh quotes are consolidated
+}
+
+impl VenueQuote {
+    pub fn mid(&self) -> Decimal {
+        (self.best_bid.price + self.best_ask.price) / Decimal::TWO
+    }
+}
+
+// Price of a trading pair across venues with the quotes of every venue, the fresh ones made the price
+#[derive(Debug, Clone)]
+pub struct ConsolidatedPrice {
+    pub symbol: String,
+    pub method: ConsolidationMethod,
+    pub price: Decimal,
+    pub venues_used: usize,
+    pub quotes: Vec<VenueQuote>,
+}
+
+// Why no consolidated price can be served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsolidationError {
+    // No venue has quoted the trading pair yet
+    NoData,
+    // Fewer venues than required have a fresh quote
+    NotEnoughVenues { fresh: usize, required: usize },
+}
+
+impl fmt::Display for ConsolidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsolidationError::NoData => write!(f, "No venue has quoted the trading pair yet"),
+            ConsolidationError::NotEnoughVenues { fresh, required } =>
+                write!(f, "{} venues have fresh quotes, {} are required", fresh, required),
+        }
+    }
+}
+
+// Median of the venue mids, the mean of the two middle ones for an even number of venues
+pub fn median_mid(quotes: &[VenueQuote]) -> Option<Decimal> {
+    let mut mids: Vec<Decimal> = quotes.iter().map(VenueQuote::mid).collect();
+    mids.sort();
+    let middle = mids.len() / 2;
+    match mids.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(mids[middle]),
+        _ => Some((mids[middle - 1] + mids[middle]) / Decimal::TWO),
+    }
+}
+
+// Venue mids weighted by the quantity at the best bid and ask of the venue
+pub fn volume_weighted_mid(quotes: &[VenueQuote]) -> Option<Decimal> {
+    let weight = |quote: &VenueQuote| quote.best_bid.qty + quote.best_ask.qty;
+    let total: Decimal = quotes.iter().map(weight).sum();
+    if total.is_zero() {
+        return None;
+    }
+    Some(quotes.iter().map(|quote| quote.mid() * weight(quote)).sum::<Decimal>() / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn quote(venue: &str, bid: Decimal, ask: Decimal, qty: Decimal) -> VenueQuote {
+        VenueQuote {
+            venue: venue.to_string(),
+            best_bid: OrderBookEntry { price: bid, qty },
+            best_ask: OrderBookEntry { price: ask, qty },
+            times: DataTimes::default(),
+            fresh: true,
+        }
+    }
+
+    #[test]
+    fn test_median_ignores_a_venue_far_off_the_others() {
+        let quotes = vec![quote("a", dec!(99), dec!(101), dec!(1)), quote("b", dec!(101), dec!(103), dec!(1)),
+            quote("c", dec!(199), dec!(201), dec!(1))];
+        assert_eq!(median_mid(&quotes), Some(dec!(102)));
+        assert_eq!(median_mid(&quotes[..2]), Some(dec!(101)));
+        assert_eq!(median_mid(&[]), None);
+    }
+
+    #[test]
+    fn test_volume_weighted_mid_follows_the_deeper_venue() {
+        let quotes = vec![quote("a", dec!(99), dec!(101), dec!(3)), quote("b", dec!(103), dec!(105), dec!(1))];
+        assert_eq!(volume_weighted_mid(&quotes), Some(dec!(101)));
+        assert_eq!(volume_weighted_mid(&[quote("a", dec!(99), dec!(101), dec!(0))]), None);
+        assert_eq!("Volume_Weighted".parse(), Ok(ConsolidationMethod::VolumeWeighted));
+        assert!("mean".parse::<ConsolidationMethod>().is_err());
+    }
+}
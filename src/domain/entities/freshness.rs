@@ -0,0 +1,56 @@
+use std::fmt;
+use std::time::Duration;
+use serde::Serialize;
+
+// When the data behind a response was produced by the exchange and received locally, in milliseconds since the
+// UNIX epoch. Not every stream carries an event time (e.g. the spot book ticker and the partial depth).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct DataTimes {
+    pub event_time: Option<u64>,
+    pub received_at: u64,
+}
+
+// Why data cannot be served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Staleness {
+    // Nothing has been received for the trading pair yet
+    NoData,
+    // The last update is older than the maximum age
+    Stale { age_ms: u64, max_age_ms: u64 },
+}
+
+impl fmt::Display for Staleness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Staleness::NoData => write!(f, "No data has been received"),
+            Staleness::Stale { age_ms, max_age_ms } =>
+                write!(f, "The last update is {}ms old, more than the maximum age of {}ms", age_ms, max_age_ms),
+        }
+    }
+}
+
+impl DataTimes {
+    // The times when the data was received at most `max_age` before `now`
+    pub fn check(self, now: u64, max_age: Duration) -> Result<Self, Staleness> {
+        let age_ms = now.saturating_sub(self.received_at);
+        let max_age_ms = max_age.as_millis() as u64;
+        if age_ms > max_age_ms {
+            return Err(Staleness::Stale { age_ms, max_age_ms });
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_older_than_the_maximum_age_is_stale() {
+        let times = DataTimes { event_time: Some(990), received_at: 1_000 };
+        assert_eq!(times.check(11_000, Duration::from_secs(10)), Ok(times));
+        assert_eq!(times.check(11_001, Duration::from_secs(10)), Err(Staleness::Stale { age_ms: 10_001, max_age_ms: 10_000 }));
+        // A clock behind the receive time (e.g. a replay restarted) does not make data stale
+        assert_eq!(times.check(500, Duration::ZERO), Ok(times));
+    }
+}
@@ -0,0 +1,60 @@
+use rust_decimal::Decimal;
+use crate::domain::entities::order_book::OrderBookEntry;
+
+// Exchange independent market data: every source translates the messages of its venue into these events, the
+// services only ever see them. Symbols are the trading pairs of the venue (e.g. BTCFDUSD), times are milliseconds
+// since the UNIX epoch and `event_time` is `None` when the venue does not send one.
+
+// All price levels of a book, replacing whatever was known before
+#[derive(Debug, Clone)]
+pub struct BookSnapshot {
+    pub symbol: String,
+    pub bids: Vec<OrderBookEntry>,
+    pub asks: Vec<OrderBookEntry>,
+    pub update_id: u64, // Sequence number of the last change included in the snapshot
+    pub event_time: Option<u64>,
+}
+
+// Changed price levels of a book, a zero quantity removes the level. A delta only applies on top of a book that
+// includes every change before `first_update_id`.
+#[derive(Debug, Clone)]
+pub struct BookDelta {
+    pub symbol: String,
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub bids: Vec<OrderBookEntry>,
+    pub asks: Vec<OrderBookEntry>,
+    pub event_time: Option<u64>,
+}
+
+// Best bid and best ask of a trading pair
+#[derive(Debug, Clone)]
+pub struct TopOfBook {
+    pub symbol: String,
+    pub update_id: u64,
+    pub best_bid: OrderBookEntry,
+    pub best_ask: OrderBookEntry,
+    pub event_time: Option<u64>,
+}
+
+// A single trade
+#[derive(Debug, Clone)]
+pub struct TradeEvent {
+    pub symbol: String,
+    pub trade_id: u64,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub trade_time: u64,
+    pub event_time: Option<u64>,
+    pub is_buyer_market_maker: bool, // The seller was the taker
+}
+
+#[derive(Debug, Clone)]
+pub enum MarketEvent {
+    BookSnapshot(BookSnapshot),
+    BookDelta(BookDelta),
+    // The source lost the continuity of the book of a trading pair, it is not served until the next snapshot
+    BookCleared(String),
+    TopOfBook(TopOfBook),
+    Trade(TradeEvent),
+}
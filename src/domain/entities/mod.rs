@@ -1,3 +1,12 @@
 pub mod book_ticker;
+pub mod candle;
+pub mod consolidated_price;
+pub mod freshness;
+pub mod market_event;
 pub mod order_book;
-pub mod trade;
\ No newline at end of file
+pub mod order_book_analytics;
+pub mod publication;
+pub mod reference_price;
+pub mod symbol;
+pub mod trade;
+pub mod trade_window;
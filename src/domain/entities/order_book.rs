@@ -1,6 +1,9 @@
 use std::collections::BTreeMap;
-use ordered_float::OrderedFloat;
+use std::fmt;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::domain::entities::market_event::{BookDelta, BookSnapshot};
+use crate::domain::entities::symbol::symbol_from_stream;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DepthData {
@@ -10,17 +13,106 @@ pub struct DepthData {
     pub asks: Vec<[String; 2]>,
 }
 
+impl DepthData {
+    // Normalized snapshot of the book of `symbol`, neither the REST snapshot nor the partial depth carry an event time
+    pub fn to_snapshot(&self, symbol: &str) -> BookSnapshot {
+        BookSnapshot {
+            symbol: symbol.to_string(),
+            bids: parse_levels(&self.bids),
+            asks: parse_levels(&self.asks),
+            update_id: self.last_update_id,
+            event_time: None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct OrderBookSD {
-    #[allow(dead_code)]
     pub stream: String,
     pub data: DepthData,
 }
 
+impl OrderBookSD {
+    // The partial depth payload has no symbol, it is taken from the stream name
+    pub fn to_snapshot(&self) -> BookSnapshot {
+        self.data.to_snapshot(&symbol_from_stream(&self.stream))
+    }
+}
+
+// Diff depth event (<symbol>@depth@100ms) used to maintain a local order book
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiffDepthData {
+    #[serde(rename = "e")]
+    pub event_type: String,        // Event type (depthUpdate)
+    #[serde(rename = "E")]
+    pub event_time: u64,           // Event time
+    #[serde(rename = "s")]
+    pub symbol: String,            // Symbol (e.g., BTCFDUSD)
+    #[serde(rename = "U")]
+    pub first_update_id: u64,      // First update ID in event
+    #[serde(rename = "u")]
+    pub final_update_id: u64,      // Final update ID in event
+    #[serde(rename = "b")]
+    pub bids: Vec<[String; 2]>,    // Bids to be updated (price, qty)
+    #[serde(rename = "a")]
+    pub asks: Vec<[String; 2]>,    // Asks to be updated (price, qty)
+}
+
+impl DiffDepthData {
+    pub fn to_delta(&self) -> BookDelta {
+        BookDelta {
+            symbol: self.symbol.clone(),
+            first_update_id: self.first_update_id,
+            final_update_id: self.final_update_id,
+            bids: parse_levels(&self.bids),
+            asks: parse_levels(&self.asks),
+            event_time: Some(self.event_time),
+        }
+    }
+}
+
+// Parse (price, qty) rows of decimal strings, malformed rows are skipped
+fn parse_levels(levels: &[[String; 2]]) -> Vec<OrderBookEntry> {
+    levels.iter()
+        .filter_map(|row| match (row[0].parse::<Decimal>(), row[1].parse::<Decimal>()) {
+            (Ok(price), Ok(qty)) => Some(OrderBookEntry { price, qty }),
+            _ => {
+                log::error!("Skipping malformed depth level: {:?}", row);
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffDepthSD {
+    #[allow(dead_code)]
+    pub stream: String,
+    pub data: DiffDepthData,
+}
+
+// Reasons why a diff event could not be applied to the local order book
+#[derive(Debug, PartialEq)]
+pub enum DepthSyncError {
+    NoSnapshot,
+    Gap { expected: u64, first_update_id: u64 },
+}
+
+impl fmt::Display for DepthSyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DepthSyncError::NoSnapshot => write!(f, "No depth snapshot has been applied"),
+            DepthSyncError::Gap { expected, first_update_id } =>
+                write!(f, "Depth sequence gap: expected update {} but event starts at {}", expected, first_update_id),
+        }
+    }
+}
+
 // Struct representing the order book with bids and asks
 pub struct OrderBook {
-    pub bids: BTreeMap<OrderedFloat<f64>, f64>,
-    pub asks: BTreeMap<OrderedFloat<f64>, f64>,
+    pub bids: BTreeMap<Decimal, Decimal>,
+    pub asks: BTreeMap<Decimal, Decimal>,
+    pub last_update_id: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -32,15 +124,23 @@ pub struct FullOrderBook {
 // Struct representing a single entry in the order book (price and quantity)
 #[derive(Debug, Serialize, Deserialize,Clone)]
 pub struct OrderBookEntry {
-    pub price: f64,
-    pub qty: f64,
+    pub price: Decimal,
+    pub qty: Decimal,
 }
 
 // Struct representing the top of the order book (best bid and best ask)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OrderBookTop {
     pub best_bid: OrderBookEntry,
     pub best_ask: OrderBookEntry,
+    pub last_update_id: u64, // Update id of the book the top was read from
+}
+
+// Notification published every time an order book has been changed
+#[derive(Debug, Clone)]
+pub struct OrderBookUpdate {
+    pub symbol: String,
+    pub top: Option<OrderBookTop>,
 }
 
 impl OrderBook {
@@ -49,30 +149,50 @@ impl OrderBook {
         OrderBook {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
+            last_update_id: 0,
         }
     }
 
-    // Update the order book with new data from a StreamData instance
-    pub fn update(&mut self, update: OrderBookSD) {
-        let mut new_bids = BTreeMap::new();
-        for row in update.data.bids {
-            let price = row[0].parse::<f64>().unwrap();
-            let qty = row[1].parse::<f64>().unwrap();
-            if qty > 0.0 {
-                new_bids.insert(OrderedFloat(price), qty);
-            }
+    // Replace the whole book with a snapshot (e.g. a partial depth update or a REST snapshot)
+    pub fn apply_snapshot(&mut self, snapshot: &BookSnapshot) {
+        self.bids = BTreeMap::new();
+        Self::apply_levels(&mut self.bids, &snapshot.bids);
+        self.asks = BTreeMap::new();
+        Self::apply_levels(&mut self.asks, &snapshot.asks);
+        self.last_update_id = snapshot.update_id;
+    }
+
+    // Apply a delta following the local order book procedure (as documented by Binance).
+    // Deltas fully covered by the current book are ignored, deltas leaving a gap are rejected.
+    pub fn apply_diff(&mut self, delta: &BookDelta) -> Result<(), DepthSyncError> {
+        if self.last_update_id == 0 {
+            return Err(DepthSyncError::NoSnapshot);
         }
-        self.bids = new_bids;
-
-        let mut new_asks = BTreeMap::new();
-        for row in update.data.asks {
-            let price = row[0].parse::<f64>().unwrap();
-            let qty = row[1].parse::<f64>().unwrap();
-            if qty > 0.0 {
-                new_asks.insert(OrderedFloat(price), qty);
+        if delta.final_update_id <= self.last_update_id {
+            return Ok(());
+        }
+        if delta.first_update_id > self.last_update_id + 1 {
+            return Err(DepthSyncError::Gap {
+                expected: self.last_update_id + 1,
+                first_update_id: delta.first_update_id,
+            });
+        }
+
+        Self::apply_levels(&mut self.bids, &delta.bids);
+        Self::apply_levels(&mut self.asks, &delta.asks);
+        self.last_update_id = delta.final_update_id;
+        Ok(())
+    }
+
+    // Set absolute quantities for the given price levels, a zero quantity removes the level
+    fn apply_levels(side: &mut BTreeMap<Decimal, Decimal>, levels: &[OrderBookEntry]) {
+        for level in levels {
+            if level.qty > Decimal::ZERO {
+                side.insert(level.price, level.qty);
+            } else {
+                side.remove(&level.price);
             }
         }
-        self.asks = new_asks;
     }
 
     // Print the top bid and ask prices and quantities in the order book (for debugging purposes)
@@ -94,26 +214,32 @@ impl OrderBook {
             (Some((&best_bid_price, &best_bid_qty)), Some((&best_ask_price, &best_ask_qty))) => {
                 Some(OrderBookTop {
                     best_bid: OrderBookEntry {
-                        price: best_bid_price.into_inner(),
+                        price: best_bid_price,
                         qty: best_bid_qty,
                     },
                     best_ask: OrderBookEntry {
-                        price: best_ask_price.into_inner(),
+                        price: best_ask_price,
                         qty: best_ask_qty,
                     },
+                    last_update_id: self.last_update_id,
                 })
             }
             _ => None, // Empty order book
         }
     }
 
+    // Number of bid and ask price levels
+    pub fn levels(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
     // Get the full order book (all bids and asks) as two vectors
     pub fn get_full_book(&self) -> Option<FullOrderBook> {
         let bids: Option<Vec<OrderBookEntry>> = if self.bids.is_empty() {
             None
         } else {
             Some(self.bids.iter().map(|(&price, &qty)| OrderBookEntry {
-                price: price.into_inner(),
+                price,
                 qty,
             }).collect())
         };
@@ -122,7 +248,7 @@ impl OrderBook {
             None
         } else {
             Some(self.asks.iter().map(|(&price, &qty)| OrderBookEntry {
-                price: price.into_inner(),
+                price,
                 qty,
             }).collect())
         };
@@ -139,7 +265,7 @@ impl OrderBook {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::order_book_sd::{OrderBookSD, DepthData};
+    use rust_decimal_macros::dec;
 
     // Test updating the order book and getting the top (best bid and ask)
     #[test]
@@ -157,14 +283,14 @@ mod tests {
         };
 
         // Update the order book
-        order_book.update(stream_data);
+        order_book.apply_snapshot(&stream_data.to_snapshot());
 
         // Check if the top bid and ask are correctly set
         let top = order_book.get_top().unwrap();
-        assert_eq!(top.best_bid.price, 10000.0);
-        assert_eq!(top.best_bid.qty, 1.0);
-        assert_eq!(top.best_ask.price, 10100.0);
-        assert_eq!(top.best_ask.qty, 2.0);
+        assert_eq!(top.best_bid.price, dec!(10000.0));
+        assert_eq!(top.best_bid.qty, dec!(1.0));
+        assert_eq!(top.best_ask.price, dec!(10100.0));
+        assert_eq!(top.best_ask.qty, dec!(2.0));
     }
 
     // Test getting the full order book with multiple bids and asks
@@ -189,7 +315,7 @@ mod tests {
         };
 
         // Update the order book
-        order_book.update(stream_data);
+        order_book.apply_snapshot(&stream_data.to_snapshot());
 
         // Get the full order book
         let full_book = order_book.get_full_book().unwrap();
@@ -197,18 +323,18 @@ mod tests {
         // Verify bids
         let bids = full_book.bids.unwrap();
         assert_eq!(bids.len(), 2);
-        assert_eq!(bids[0].price, 9990.0);
-        assert_eq!(bids[0].qty, 0.5);
-        assert_eq!(bids[1].price, 10000.0);
-        assert_eq!(bids[1].qty, 1.0);
+        assert_eq!(bids[0].price, dec!(9990.0));
+        assert_eq!(bids[0].qty, dec!(0.5));
+        assert_eq!(bids[1].price, dec!(10000.0));
+        assert_eq!(bids[1].qty, dec!(1.0));
 
         // Verify asks
         let asks = full_book.asks.unwrap();
         assert_eq!(asks.len(), 2);
-        assert_eq!(asks[0].price, 10100.0);
-        assert_eq!(asks[0].qty, 2.0);
-        assert_eq!(asks[1].price, 10200.0);
-        assert_eq!(asks[1].qty, 1.5);
+        assert_eq!(asks[0].price, dec!(10100.0));
+        assert_eq!(asks[0].qty, dec!(2.0));
+        assert_eq!(asks[1].price, dec!(10200.0));
+        assert_eq!(asks[1].qty, dec!(1.5));
     }
 
     // Test the case when the order book is updated with empty data (no bids, no asks)
@@ -227,9 +353,96 @@ mod tests {
         };
 
         // Update the order book with empty data
-        order_book.update(stream_data);
+        order_book.apply_snapshot(&stream_data.to_snapshot());
 
         // Verify that get_top() returns None for an empty book
         assert!(order_book.get_top().is_none());
     }
-}
\ No newline at end of file
+
+    // Test that levels keep the exchange precision and are serialized as decimal strings
+    #[test]
+    fn test_order_book_entries_keep_precision() {
+        let mut order_book = OrderBook::new();
+        order_book.apply_snapshot(&DepthData {
+            last_update_id: 1,
+            bids: vec![["0.10000000".to_string(), "0.00100000".to_string()]],
+            asks: vec![["0.20000000".to_string(), "3.00000000".to_string()]],
+        }.to_snapshot("BTCFDUSD"));
+
+        let top = order_book.get_top().unwrap();
+        assert_eq!(top.best_bid.price + top.best_ask.price, dec!(0.3));
+        assert_eq!(
+            serde_json::to_value(&top.best_bid).unwrap(),
+            serde_json::json!({"price": "0.10000000", "qty": "0.00100000"})
+        );
+    }
+
+    fn diff(first_update_id: u64, final_update_id: u64, bids: Vec<[&str; 2]>, asks: Vec<[&str; 2]>) -> BookDelta {
+        DiffDepthData {
+            event_type: "depthUpdate".to_string(),
+            event_time: 0,
+            symbol: "BTCFDUSD".to_string(),
+            first_update_id,
+            final_update_id,
+            bids: bids.iter().map(|[p, q]| [p.to_string(), q.to_string()]).collect(),
+            asks: asks.iter().map(|[p, q]| [p.to_string(), q.to_string()]).collect(),
+        }.to_delta()
+    }
+
+    fn snapshot_book() -> OrderBook {
+        let mut order_book = OrderBook::new();
+        order_book.apply_snapshot(&DepthData {
+            last_update_id: 100,
+            bids: vec![
+                ["10000.0".to_string(), "1.0".to_string()],
+                ["9990.0".to_string(), "0.5".to_string()],
+            ],
+            asks: vec![["10100.0".to_string(), "2.0".to_string()]],
+        }.to_snapshot("BTCFDUSD"));
+        order_book
+    }
+
+    // Test that diffs are applied on top of the snapshot and zero quantities remove levels
+    #[test]
+    fn test_apply_diff_after_snapshot() {
+        let mut order_book = snapshot_book();
+
+        // Already covered by the snapshot - ignored
+        assert!(order_book.apply_diff(&diff(90, 100, vec![["10000.0", "0"]], vec![])).is_ok());
+        assert_eq!(order_book.bids.len(), 2);
+
+        // First event straddles lastUpdateId + 1
+        let first = diff(95, 105, vec![["10000.0", "0"], ["10050.0", "3.0"]], vec![["10100.0", "1.5"]]);
+        assert!(order_book.apply_diff(&first).is_ok());
+        assert_eq!(order_book.last_update_id, 105);
+
+        let top = order_book.get_top().unwrap();
+        assert_eq!(top.best_bid.price, dec!(10050.0));
+        assert_eq!(top.best_bid.qty, dec!(3.0));
+        assert_eq!(top.best_ask.qty, dec!(1.5));
+        assert!(!order_book.bids.contains_key(&dec!(10000.0)));
+
+        // Next event continues the sequence
+        assert!(order_book.apply_diff(&diff(106, 110, vec![], vec![["10090.0", "0.1"]])).is_ok());
+        assert_eq!(order_book.get_top().unwrap().best_ask.price, dec!(10090.0));
+    }
+
+    // Test that a missing range of update ids is reported as a gap
+    #[test]
+    fn test_apply_diff_detects_gap() {
+        let mut order_book = snapshot_book();
+
+        let result = order_book.apply_diff(&diff(102, 105, vec![], vec![]));
+        assert_eq!(result, Err(DepthSyncError::Gap { expected: 101, first_update_id: 102 }));
+        assert_eq!(order_book.last_update_id, 100);
+    }
+
+    // Test that diffs are rejected before any snapshot was applied
+    #[test]
+    fn test_apply_diff_without_snapshot() {
+        let mut order_book = OrderBook::new();
+
+        let result = order_book.apply_diff(&diff(1, 2, vec![["1.0", "1.0"]], vec![]));
+        assert_eq!(result, Err(DepthSyncError::NoSnapshot));
+    }
+}
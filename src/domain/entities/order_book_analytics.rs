@@ -0,0 +1,406 @@
+use std::fmt;
+use std::str::FromStr;
+use rust_decimal::Decimal;
+use crate::domain::entities::order_book::OrderBook;
+
+// Levels per side the analytics look at unless a request sets them
+pub const DEFAULT_ANALYTICS_LEVELS: usize = 10;
+// Half width of the liquidity band around the mid unless a request sets it, in basis points
+pub const DEFAULT_BAND_BPS: u32 = 10;
+const BPS_PER_UNIT: i64 = 10_000;
+
+// Parameters of an analytics query: the top `levels` per side and a band of ±`band_bps` around the mid
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookAnalyticsParams {
+    pub levels: usize,
+    pub band_bps: Decimal,
+}
+
+impl BookAnalyticsParams {
+    // The band is given either in basis points or in percent, missing values (or 0 levels) fall back to the defaults
+    pub fn new(levels: Option<usize>, band_bps: Option<Decimal>, band_pct: Option<Decimal>) -> Result<Self, String> {
+        let band_bps = match (band_bps, band_pct) {
+            (Some(_), Some(_)) => return Err("Set the band either in bps or in pct, not both".to_string()),
+            (Some(bps), None) => bps,
+            (None, Some(pct)) => pct.checked_mul(Decimal::ONE_HUNDRED).ok_or_else(|| band_out_of_range(format!("{}%", pct)))?,
+            (None, None) => Decimal::from(DEFAULT_BAND_BPS),
+        };
+        if band_bps <= Decimal::ZERO || band_bps >= Decimal::from(BPS_PER_UNIT) {
+            return Err(band_out_of_range(band_bps));
+        }
+
+        Ok(BookAnalyticsParams {
+            levels: levels.filter(|levels| *levels > 0).unwrap_or(DEFAULT_ANALYTICS_LEVELS),
+            band_bps,
+        })
+    }
+}
+
+fn band_out_of_range(band: impl fmt::Display) -> String {
+    format!("The band has to be above 0 and below 10000 bps, got {}", band)
+}
+
+// Best bid and ask and the distance between them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spread {
+    pub best_bid: Decimal,
+    pub best_ask: Decimal,
+    pub mid: Decimal,
+    pub absolute: Decimal,
+    pub bps: Decimal, // Relative to the mid
+}
+
+// Quantity resting on the top levels of each side
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthImbalance {
+    pub levels: usize,
+    pub bid_qty: Decimal,
+    pub ask_qty: Decimal,
+    pub imbalance: Decimal, // (bid - ask) / (bid + ask), from -1 (only asks) to 1 (only bids)
+}
+
+// Liquidity resting within ±band_bps of the mid
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandLiquidity {
+    pub band_bps: Decimal,
+    pub lower_price: Decimal,
+    pub upper_price: Decimal,
+    pub bid_qty: Decimal,
+    pub ask_qty: Decimal,
+    pub bid_notional: Decimal,
+    pub ask_notional: Decimal,
+}
+
+// Prices of the top levels weighted by their quantity
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedPrices {
+    pub levels: usize,
+    pub bid_price: Decimal,   // Quantity-weighted average price of the top bid levels
+    pub ask_price: Decimal,   // Quantity-weighted average price of the top ask levels
+    pub weighted_mid: Decimal, // Midpoint of the two averages
+    pub microprice: Decimal,  // The averages weighted by the quantity of the opposite side
+}
+
+// Analytics of one order book, computed from both sides of the book at once
+#[derive(Debug, Clone, PartialEq)]
+pub struct BookAnalytics {
+    pub spread: Spread,
+    pub imbalance: DepthImbalance,
+    pub liquidity: BandLiquidity,
+    pub weighted: WeightedPrices,
+}
+
+// Side of a hypothetical market order, a buy takes the asks and a sell the bids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImpactSide {
+    Buy,
+    Sell,
+}
+
+impl FromStr for ImpactSide {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "buy" => Ok(ImpactSide::Buy),
+            "sell" => Ok(ImpactSide::Sell),
+            _ => Err(format!("Unknown side {}, expected buy or sell", value)),
+        }
+    }
+}
+
+impl fmt::Display for ImpactSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ImpactSide::Buy => "buy",
+            ImpactSide::Sell => "sell",
+        })
+    }
+}
+
+// Size of a hypothetical market order in the base asset (e.g. 5 BTC) or in the quote asset (e.g. 10000 USDT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImpactAmount {
+    Base(Decimal),
+    Quote(Decimal),
+}
+
+// A hypothetical market order to estimate against the book
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpactOrder {
+    pub side: ImpactSide,
+    pub amount: ImpactAmount,
+}
+
+impl ImpactOrder {
+    // Exactly one positive amount has to be given, either in the base or in the quote asset
+    pub fn new(side: &str, base_qty: Option<Decimal>, quote_qty: Option<Decimal>) -> Result<Self, String> {
+        let amount = match (base_qty, quote_qty) {
+            (Some(qty), None) => ImpactAmount::Base(qty),
+            (None, Some(quote)) => ImpactAmount::Quote(quote),
+            _ => return Err("Set either the base quantity or the quote quantity".to_string()),
+        };
+        if matches!(amount, ImpactAmount::Base(qty) | ImpactAmount::Quote(qty) if qty <= Decimal::ZERO) {
+            return Err("The quantity has to be above 0".to_string());
+        }
+        Ok(ImpactOrder { side: side.parse()?, amount })
+    }
+}
+
+// Outcome of walking the book with a market order, the fill covers the visible depth only
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpactEstimate {
+    pub side: ImpactSide,
+    pub filled_qty: Decimal,        // Base asset
+    pub filled_quote: Decimal,      // Quote asset, sum of price * quantity
+    pub average_price: Decimal,     // Volume-weighted fill price
+    pub worst_price: Decimal,       // Price of the last level touched
+    pub mid: Decimal,
+    pub slippage_bps: Decimal,      // Cost of the average price against the mid, positive when worse
+    pub levels_consumed: usize,     // Levels touched, the last one possibly only in part
+    pub insufficient_depth: bool,   // The visible depth could not fill the whole amount
+}
+
+impl OrderBook {
+    // `None` unless both sides of the book have levels
+    pub fn analytics(&self, params: &BookAnalyticsParams) -> Option<BookAnalytics> {
+        Some(BookAnalytics {
+            spread: self.spread()?,
+            imbalance: self.depth_imbalance(params.levels)?,
+            liquidity: self.band_liquidity(params.band_bps)?,
+            weighted: self.weighted_prices(params.levels)?,
+        })
+    }
+
+    pub fn spread(&self) -> Option<Spread> {
+        let (&best_bid, _) = self.bids.iter().next_back()?;
+        let (&best_ask, _) = self.asks.iter().next()?;
+        let mid = (best_bid + best_ask) / Decimal::TWO;
+        let absolute = best_ask - best_bid;
+        Some(Spread {
+            best_bid,
+            best_ask,
+            mid,
+            absolute,
+            bps: absolute / mid * Decimal::from(BPS_PER_UNIT),
+        })
+    }
+
+    pub fn depth_imbalance(&self, levels: usize) -> Option<DepthImbalance> {
+        let bid_qty: Decimal = self.bids.values().rev().take(levels).sum();
+        let ask_qty: Decimal = self.asks.values().take(levels).sum();
+        if bid_qty.is_zero() || ask_qty.is_zero() {
+            return None;
+        }
+        Some(DepthImbalance {
+            levels,
+            bid_qty,
+            ask_qty,
+            imbalance: (bid_qty - ask_qty) / (bid_qty + ask_qty),
+        })
+    }
+
+    pub fn band_liquidity(&self, band_bps: Decimal) -> Option<BandLiquidity> {
+        let mid = self.spread()?.mid;
+        let offset = mid * band_bps / Decimal::from(BPS_PER_UNIT);
+        let (lower_price, upper_price) = (mid - offset, mid + offset);
+
+        let mut liquidity = BandLiquidity {
+            band_bps,
+            lower_price,
+            upper_price,
+            bid_qty: Decimal::ZERO,
+            ask_qty: Decimal::ZERO,
+            bid_notional: Decimal::ZERO,
+            ask_notional: Decimal::ZERO,
+        };
+        for (&price, &qty) in self.bids.range(lower_price..) {
+            liquidity.bid_qty += qty;
+            liquidity.bid_notional += price * qty;
+        }
+        for (&price, &qty) in self.asks.range(..=upper_price) {
+            liquidity.ask_qty += qty;
+            liquidity.ask_notional += price * qty;
+        }
+        Some(liquidity)
+    }
+
+    // Walk the levels of the side the order takes, `None` unless both sides of the book have levels
+    pub fn impact(&self, order: &ImpactOrder) -> Option<ImpactEstimate> {
+        let mid = self.spread()?.mid;
+        let levels: Box<dyn Iterator<Item = (&Decimal, &Decimal)>> = match order.side {
+            ImpactSide::Buy => Box::new(self.asks.iter()),
+            ImpactSide::Sell => Box::new(self.bids.iter().rev()),
+        };
+
+        let (mut filled_qty, mut filled_quote, mut worst_price, mut levels_consumed) = (Decimal::ZERO, Decimal::ZERO, mid, 0);
+        let mut insufficient_depth = true;
+        for (&price, &qty) in levels {
+            let take_qty = match order.amount {
+                ImpactAmount::Base(amount) => qty.min(amount - filled_qty),
+                ImpactAmount::Quote(amount) => qty.min((amount - filled_quote) / price),
+            };
+            filled_qty += take_qty;
+            filled_quote += price * take_qty;
+            worst_price = price;
+            levels_consumed += 1;
+
+            // A level taken only in part ends the walk, which also absorbs the rounding of quote amounts
+            let done = take_qty < qty || match order.amount {
+                ImpactAmount::Base(amount) => filled_qty >= amount,
+                ImpactAmount::Quote(amount) => filled_quote >= amount,
+            };
+            if done {
+                insufficient_depth = false;
+                break;
+            }
+        }
+
+        // A quote amount too small for any quantity at the best price (it rounds to 0) takes nothing from the book
+        if filled_qty.is_zero() {
+            return Some(ImpactEstimate {
+                side: order.side,
+                filled_qty,
+                filled_quote,
+                average_price: mid,
+                worst_price: mid,
+                mid,
+                slippage_bps: Decimal::ZERO,
+                levels_consumed: 0,
+                insufficient_depth: true,
+            });
+        }
+
+        let average_price = filled_quote / filled_qty;
+        let slippage = match order.side {
+            ImpactSide::Buy => average_price - mid,
+            ImpactSide::Sell => mid - average_price,
+        };
+        Some(ImpactEstimate {
+            side: order.side,
+            filled_qty,
+            filled_quote,
+            average_price,
+            worst_price,
+            mid,
+            slippage_bps: slippage / mid * Decimal::from(BPS_PER_UNIT),
+            levels_consumed,
+            insufficient_depth,
+        })
+    }
+
+    pub fn weighted_prices(&self, levels: usize) -> Option<WeightedPrices> {
+        let (bid_price, bid_qty) = weighted_average(self.bids.iter().rev().take(levels))?;
+        let (ask_price, ask_qty) = weighted_average(self.asks.iter().take(levels))?;
+        Some(WeightedPrices {
+            levels,
+            bid_price,
+            ask_price,
+            weighted_mid: (bid_price + ask_price) / Decimal::TWO,
+            microprice: (bid_price * ask_qty + ask_price * bid_qty) / (bid_qty + ask_qty),
+        })
+    }
+}
+
+// Quantity-weighted average price and total quantity of the levels, `None` without quantity
+fn weighted_average<'a>(levels: impl Iterator<Item = (&'a Decimal, &'a Decimal)>) -> Option<(Decimal, Decimal)> {
+    let (notional, qty) = levels.fold((Decimal::ZERO, Decimal::ZERO), |(notional, qty), (&price, &level_qty)| {
+        (notional + price * level_qty, qty + level_qty)
+    });
+    (!qty.is_zero()).then(|| (notional / qty, qty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::domain::entities::order_book::DepthData;
+
+    fn book() -> OrderBook {
+        let level = |price: &str, qty: &str| [price.to_string(), qty.to_string()];
+        let mut order_book = OrderBook::new();
+        order_book.apply_snapshot(&DepthData {
+            last_update_id: 1,
+            bids: vec![level("99", "1"), level("98", "2"), level("90", "10")],
+            asks: vec![level("101", "3"), level("103", "1"), level("120", "5")],
+        }.to_snapshot("BTCFDUSD"));
+        order_book
+    }
+
+    #[test]
+    fn test_spread_and_top_levels() {
+        let book = book();
+
+        let spread = book.spread().unwrap();
+        assert_eq!((spread.mid, spread.absolute, spread.bps), (dec!(100), dec!(2), dec!(200)));
+
+        let imbalance = book.depth_imbalance(2).unwrap();
+        assert_eq!((imbalance.bid_qty, imbalance.ask_qty, imbalance.imbalance), (dec!(3), dec!(4), dec!(-1) / dec!(7)));
+
+        // Bids (99 * 1 + 98 * 2) / 3, asks (101 * 3 + 103 * 1) / 4
+        let weighted = book.weighted_prices(2).unwrap();
+        assert_eq!(weighted.bid_price, dec!(295) / dec!(3));
+        assert_eq!(weighted.ask_price, dec!(101.5));
+        assert_eq!(weighted.weighted_mid, (dec!(295) / dec!(3) + dec!(101.5)) / dec!(2));
+        assert_eq!(weighted.microprice, (dec!(295) / dec!(3) * dec!(4) + dec!(101.5) * dec!(3)) / dec!(7));
+    }
+
+    #[test]
+    fn test_liquidity_within_the_band() {
+        // ±3% of the mid is 97 to 103, both bounds included
+        let liquidity = book().band_liquidity(dec!(300)).unwrap();
+        assert_eq!((liquidity.lower_price, liquidity.upper_price), (dec!(97), dec!(103)));
+        assert_eq!((liquidity.bid_qty, liquidity.bid_notional), (dec!(3), dec!(295)));
+        assert_eq!((liquidity.ask_qty, liquidity.ask_notional), (dec!(4), dec!(406)));
+    }
+
+    #[test]
+    fn test_impact_walks_the_levels() {
+        let book = book();
+
+        // 3 at 101 and 1 at 103, half of the second level is left
+        let buy = book.impact(&ImpactOrder::new("buy", Some(dec!(3.5)), None).unwrap()).unwrap();
+        assert_eq!((buy.filled_qty, buy.filled_quote, buy.worst_price), (dec!(3.5), dec!(354.5), dec!(103)));
+        assert_eq!(buy.average_price, dec!(354.5) / dec!(3.5));
+        assert_eq!(buy.slippage_bps, (dec!(354.5) / dec!(3.5) - dec!(100)) / dec!(100) * dec!(10000));
+        assert_eq!((buy.levels_consumed, buy.insufficient_depth), (2, false));
+
+        // 99 from the best bid and the rest of the 196 quote at 98
+        let sell = book.impact(&ImpactOrder::new("SELL", None, Some(dec!(148))).unwrap()).unwrap();
+        assert_eq!((sell.filled_qty, sell.filled_quote, sell.worst_price), (dec!(1.5), dec!(148), dec!(98)));
+        assert_eq!(sell.slippage_bps, (dec!(100) - dec!(148) / dec!(1.5)) / dec!(100) * dec!(10000));
+
+        // More than the book holds fills what is visible
+        let too_large = book.impact(&ImpactOrder::new("buy", Some(dec!(100)), None).unwrap()).unwrap();
+        assert_eq!((too_large.filled_qty, too_large.levels_consumed, too_large.insufficient_depth), (dec!(9), 3, true));
+
+        // A quote amount rounding to no quantity at all fills nothing
+        let tiny = book.impact(&ImpactOrder::new("buy", None, Some(Decimal::new(1, 28))).unwrap()).unwrap();
+        assert_eq!((tiny.filled_qty, tiny.average_price, tiny.slippage_bps), (dec!(0), dec!(100), dec!(0)));
+        assert_eq!((tiny.levels_consumed, tiny.insufficient_depth), (0, true));
+
+        assert!(ImpactOrder::new("hold", Some(dec!(1)), None).is_err());
+        assert!(ImpactOrder::new("buy", Some(dec!(1)), Some(dec!(1))).is_err());
+        assert!(ImpactOrder::new("buy", None, Some(dec!(-1))).is_err());
+    }
+
+    #[test]
+    fn test_params_and_one_sided_books() {
+        assert_eq!(BookAnalyticsParams::new(None, None, Some(dec!(0.5))).unwrap().band_bps, dec!(50));
+        assert_eq!(BookAnalyticsParams::new(Some(0), None, None).unwrap(),
+            BookAnalyticsParams { levels: DEFAULT_ANALYTICS_LEVELS, band_bps: dec!(10) });
+        assert!(BookAnalyticsParams::new(None, Some(dec!(5)), Some(dec!(1))).is_err());
+        assert!(BookAnalyticsParams::new(None, Some(dec!(0)), None).is_err());
+        // A percentage too large to convert to bps is out of range rather than an overflow
+        assert_eq!(BookAnalyticsParams::new(None, None, Some(Decimal::MAX)).unwrap_err(),
+            format!("The band has to be above 0 and below 10000 bps, got {}%", Decimal::MAX));
+
+        let mut one_sided = OrderBook::new();
+        one_sided.apply_snapshot(&DepthData {
+            last_update_id: 1,
+            bids: vec![["99".to_string(), "1".to_string()]],
+            asks: vec![],
+        }.to_snapshot("BTCFDUSD"));
+        assert!(one_sided.analytics(&BookAnalyticsParams::new(None, None, None).unwrap()).is_none());
+    }
+}
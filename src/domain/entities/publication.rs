@@ -0,0 +1,270 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use crate::domain::entities::symbol::normalize_symbol;
+
+// Price a publisher follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    // Book ticker mid price
+    Mid,
+    // Book ticker microprice
+    Microprice,
+    // VWAP of the trades in the first reference price window
+    Vwap,
+}
+
+impl FromStr for PriceSource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "mid" => Ok(PriceSource::Mid),
+            "microprice" => Ok(PriceSource::Microprice),
+            "vwap" => Ok(PriceSource::Vwap),
+            _ => Err(format!("Unknown price source {}, expected mid, microprice or vwap", value)),
+        }
+    }
+}
+
+impl fmt::Display for PriceSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PriceSource::Mid => "mid",
+            PriceSource::Microprice => "microprice",
+            PriceSource::Vwap => "vwap",
+        })
+    }
+}
+
+// When the price of a trading pair is published: once it moved more than `deviation_bps` away from the last
+// published price, or when `heartbeat_secs` have passed since the last publication.
+// Written as `SYMBOL:source:deviation_bps:heartbeat_secs`, e.g. `BTCFDUSD:mid:50:3600`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PublisherPolicy {
+    pub symbol: String,
+    pub source: PriceSource,
+    pub deviation_bps: Decimal,
+    pub heartbeat_secs: u64,
+}
+
+impl FromStr for PublisherPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = value.split(':').map(str::trim).collect();
+        let [symbol, source, deviation_bps, heartbeat_secs] = parts[..] else {
+            return Err(format!("Publisher {} has to be written as SYMBOL:source:deviation_bps:heartbeat_secs", value));
+        };
+        let deviation_bps: Decimal = deviation_bps.parse()
+            .map_err(|_| format!("The deviation of publisher {} has to be a decimal number of bps", value))?;
+        let heartbeat_secs: u64 = heartbeat_secs.parse()
+            .map_err(|_| format!("The heartbeat of publisher {} has to be a number of seconds", value))?;
+        if symbol.is_empty() || deviation_bps <= Decimal::ZERO || heartbeat_secs == 0 {
+            return Err(format!("Publisher {} needs a symbol, a deviation above 0 bps and a heartbeat above 0s", value));
+        }
+
+        Ok(PublisherPolicy {
+            symbol: normalize_symbol(symbol),
+            source: source.parse()?,
+            deviation_bps,
+            heartbeat_secs,
+        })
+    }
+}
+
+impl TryFrom<String> for PublisherPolicy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for PublisherPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}:{}", self.symbol, self.source, self.deviation_bps, self.heartbeat_secs)
+    }
+}
+
+// Why a price was published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublicationReason {
+    // First price seen since startup
+    Initial,
+    // The price moved more than the deviation threshold
+    Deviation,
+    // The heartbeat interval passed without a large enough move
+    Heartbeat,
+}
+
+impl fmt::Display for PublicationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PublicationReason::Initial => "initial",
+            PublicationReason::Deviation => "deviation",
+            PublicationReason::Heartbeat => "heartbeat",
+        })
+    }
+}
+
+// A published price, times are clock times in milliseconds since the UNIX epoch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Publication {
+    pub symbol: String,
+    pub sequence: u64,          // Publications of the trading pair so far, starting at 1
+    pub source: PriceSource,
+    pub price: Decimal,
+    pub reason: PublicationReason,
+    pub deviation_bps: Decimal, // Move against the previously published price, 0 for the first one
+    pub published_at: u64,
+}
+
+// Decides which observed prices of one trading pair are published and keeps the last publication
+#[derive(Debug)]
+pub struct Publisher {
+    policy: PublisherPolicy,
+    latest_price: Option<Decimal>, // Latest observed price, republished on a heartbeat
+    latest_at: u64,                // When the latest price was observed
+    last: Option<Publication>,
+}
+
+impl Publisher {
+    pub fn new(policy: PublisherPolicy) -> Self {
+        Publisher { policy, latest_price: None, latest_at: 0, last: None }
+    }
+
+    pub fn policy(&self) -> &PublisherPolicy {
+        &self.policy
+    }
+
+    pub fn last(&self) -> Option<&Publication> {
+        self.last.as_ref()
+    }
+
+    // Observe a new price, returns the publication when it has to be published
+    pub fn observe(&mut self, price: Decimal, now: u64) -> Option<Publication> {
+        self.latest_price = Some(price);
+        self.latest_at = now;
+        let Some(last) = &self.last else {
+            return Some(self.publish(price, PublicationReason::Initial, now));
+        };
+        if deviation_bps(last.price, price).abs() > self.policy.deviation_bps {
+            return Some(self.publish(price, PublicationReason::Deviation, now));
+        }
+        self.due_heartbeat(now)
+    }
+
+    // Republish the latest observed price once the heartbeat interval has passed since the last publication.
+    // A price observed longer than `max_age` ago is not republished, a heartbeat must not pass stale data off as fresh.
+    pub fn heartbeat(&mut self, now: u64, max_age: Duration) -> Option<Publication> {
+        if now.saturating_sub(self.latest_at) > max_age.as_millis() as u64 {
+            return None;
+        }
+        self.due_heartbeat(now)
+    }
+
+    fn due_heartbeat(&mut self, now: u64) -> Option<Publication> {
+        let last = self.last.as_ref()?;
+        let price = self.latest_price?;
+        if now.saturating_sub(last.published_at) < self.policy.heartbeat_secs.saturating_mul(1_000) {
+            return None;
+        }
+        Some(self.publish(price, PublicationReason::Heartbeat, now))
+    }
+
+    fn publish(&mut self, price: Decimal, reason: PublicationReason, now: u64) -> Publication {
+        let (sequence, deviation) = match &self.last {
+            Some(last) => (last.sequence + 1, deviation_bps(last.price, price)),
+            None => (1, Decimal::ZERO),
+        };
+        let publication = Publication {
+            symbol: self.policy.symbol.clone(),
+            sequence,
+            source: self.policy.source,
+            price,
+            reason,
+            deviation_bps: deviation,
+            published_at: now,
+        };
+        self.last = Some(publication.clone());
+        publication
+    }
+}
+
+// Signed move from `from` to `to` in basis points of `from`
+fn deviation_bps(from: Decimal, to: Decimal) -> Decimal {
+    if from.is_zero() {
+        return Decimal::ZERO;
+    }
+    (to - from) / from * Decimal::from(10_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn publisher() -> Publisher {
+        Publisher::new("btcfdusd:mid:50:60".parse().unwrap())
+    }
+
+    #[test]
+    fn test_policy_parsing() {
+        let policy: PublisherPolicy = "btcfdusd:VWAP:12.5:3600".parse().unwrap();
+        assert_eq!(policy.symbol, "BTCFDUSD");
+        assert_eq!(policy.source, PriceSource::Vwap);
+        assert_eq!(policy.deviation_bps, dec!(12.5));
+        assert_eq!(policy.heartbeat_secs, 3600);
+        assert_eq!(policy.to_string(), "BTCFDUSD:vwap:12.5:3600");
+
+        assert!("BTCFDUSD:mid:50".parse::<PublisherPolicy>().is_err());
+        assert!("BTCFDUSD:last:50:60".parse::<PublisherPolicy>().is_err());
+        assert!("BTCFDUSD:mid:0:60".parse::<PublisherPolicy>().is_err());
+        assert!("BTCFDUSD:mid:50:0".parse::<PublisherPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_publishes_on_deviation_only() {
+        let mut publisher = publisher();
+        let first = publisher.observe(dec!(100), 1_000).unwrap();
+        assert_eq!((first.sequence, first.reason), (1, PublicationReason::Initial));
+
+        // 50 bps is not more than the threshold
+        assert_eq!(publisher.observe(dec!(100.5), 2_000), None);
+        assert_eq!(publisher.observe(dec!(99.5), 3_000), None);
+
+        let moved = publisher.observe(dec!(99.49), 4_000).unwrap();
+        assert_eq!((moved.sequence, moved.reason), (2, PublicationReason::Deviation));
+        assert_eq!(moved.deviation_bps, dec!(-51));
+        assert_eq!(publisher.last(), Some(&moved));
+    }
+
+    #[test]
+    fn test_heartbeat_republishes_the_latest_price() {
+        let mut publisher = publisher();
+        let max_age = Duration::from_secs(60);
+        assert_eq!(publisher.heartbeat(100_000, max_age), None);
+
+        publisher.observe(dec!(100), 1_000).unwrap();
+        publisher.observe(dec!(100.2), 30_000);
+        assert_eq!(publisher.heartbeat(60_999, max_age), None);
+
+        let heartbeat = publisher.heartbeat(61_000, max_age).unwrap();
+        assert_eq!(heartbeat.reason, PublicationReason::Heartbeat);
+        assert_eq!(heartbeat.price, dec!(100.2));
+        assert_eq!(heartbeat.deviation_bps, dec!(20));
+        // The next heartbeat is due a full interval after this one
+        assert_eq!(publisher.heartbeat(100_000, max_age), None);
+        assert_eq!(publisher.observe(dec!(100.2), 121_000).map(|p| p.reason), Some(PublicationReason::Heartbeat));
+
+        // No price was observed for longer than the maximum age, the heartbeat is skipped until a new one arrives
+        assert_eq!(publisher.heartbeat(181_001, max_age), None);
+        assert_eq!(publisher.observe(dec!(100.2), 190_000).map(|p| p.reason), Some(PublicationReason::Heartbeat));
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use rust_decimal::Decimal;
+use crate::domain::entities::trade::AggressorFlow;
+
+// Book ticker prices sampled on every ticker update
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSample {
+    pub time: u64, // Milliseconds since the UNIX epoch
+    pub mid: Decimal,
+    pub microprice: Decimal,
+}
+
+// Volume-weighted average trade price over a window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vwap {
+    pub vwap: Decimal,
+    pub volume: Decimal,
+    pub window_secs: u64,
+    pub sample_count: u64, // Trades in the window
+    pub coverage: f64,     // Share of the window the service has observed, 0 to 1
+    pub trade_ids: (u64, u64), // First and last trade id in the window
+}
+
+// Time-weighted averages of the book ticker mid and microprice over a window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Twap {
+    pub mid: Decimal,
+    pub microprice: Decimal,
+    pub window_secs: u64,
+    pub sample_count: u64, // Samples in force during the window
+    pub coverage: f64,     // Share of the window covered by samples, 0 to 1
+}
+
+fn coverage(covered_ms: u64, window_ms: u64) -> f64 {
+    (covered_ms as f64 / window_ms as f64).min(1.0)
+}
+
+// VWAP from the flow of the trades (with ids in `trade_ids`) in the window ending at `now`, `None` without volume.
+// The window was observed from `observed_since` on, trades before that were not seen.
+pub fn volume_weighted(flow: &AggressorFlow, trade_ids: (u64, u64), now: u64, window_secs: u64, observed_since: u64) -> Option<Vwap> {
+    let window_ms = window_secs * 1_000;
+    let start = now.saturating_sub(window_ms);
+    let volume = flow.volume();
+    if volume.is_zero() {
+        return None;
+    }
+
+    Some(Vwap {
+        vwap: flow.notional() / volume,
+        volume,
+        window_secs,
+        sample_count: flow.count(),
+        coverage: coverage(now.saturating_sub(start.max(observed_since)), window_ms),
+        trade_ids,
+    })
+}
+
+// TWAP of the samples (oldest first) in the window ending at `now`, every sample holds until the next one.
+// A sample taken before the window still counts for the part of the window it was in force.
+pub fn time_weighted(samples: &VecDeque<PriceSample>, now: u64, window_secs: u64) -> Option<Twap> {
+    let window_ms = window_secs * 1_000;
+    let start = now.saturating_sub(window_ms);
+
+    let (mut mid, mut microprice, mut covered, mut sample_count) = (Decimal::ZERO, Decimal::ZERO, 0, 0);
+    for (index, sample) in samples.iter().enumerate() {
+        let from = sample.time.max(start);
+        let until = samples.get(index + 1).map_or(now, |next| next.time).min(now);
+        if until <= from {
+            continue;
+        }
+        let weight = Decimal::from(until - from);
+        mid += sample.mid * weight;
+        microprice += sample.microprice * weight;
+        covered += until - from;
+        sample_count += 1;
+    }
+
+    if covered == 0 {
+        // No time passed since the latest sample, it is the best estimate there is
+        let latest = samples.iter().rev().find(|sample| sample.time <= now)?;
+        return Some(Twap {
+            mid: latest.mid,
+            microprice: latest.microprice,
+            window_secs,
+            sample_count: 1,
+            coverage: 0.0,
+        });
+    }
+
+    let covered_weight = Decimal::from(covered);
+    Some(Twap {
+        mid: mid / covered_weight,
+        microprice: microprice / covered_weight,
+        window_secs,
+        sample_count,
+        coverage: coverage(covered, window_ms),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn sample(time: u64, mid: Decimal) -> PriceSample {
+        PriceSample { time, mid, microprice: mid + dec!(1) }
+    }
+
+    #[test]
+    fn test_twap_weights_samples_by_time_in_force() {
+        // 100 is carried into the window and in force for its first 10s, 130 for the last 10s
+        let samples = VecDeque::from(vec![sample(0, dec!(100)), sample(20_000, dec!(130))]);
+        let twap = time_weighted(&samples, 30_000, 20).unwrap();
+
+        assert_eq!(twap.mid, dec!(115));
+        assert_eq!(twap.microprice, dec!(116));
+        assert_eq!(twap.sample_count, 2);
+        assert_eq!(twap.coverage, 1.0);
+
+        // Samples only cover 30s of a 60s window
+        let twap = time_weighted(&samples, 30_000, 60).unwrap();
+        assert_eq!(twap.mid, dec!(110));
+        assert_eq!(twap.coverage, 0.5);
+    }
+
+    #[test]
+    fn test_twap_of_a_fresh_sample() {
+        let samples = VecDeque::from(vec![sample(5_000, dec!(100))]);
+
+        let twap = time_weighted(&samples, 5_000, 60).unwrap();
+        assert_eq!((twap.mid, twap.sample_count, twap.coverage), (dec!(100), 1, 0.0));
+        assert!(time_weighted(&VecDeque::new(), 5_000, 60).is_none());
+    }
+
+    #[test]
+    fn test_vwap_of_the_window_flow() {
+        let flow = AggressorFlow {
+            buy_volume: dec!(1),
+            sell_volume: dec!(3),
+            buy_count: 1,
+            sell_count: 1,
+            buy_notional: dec!(100),
+            sell_notional: dec!(330),
+        };
+
+        let vwap = volume_weighted(&flow, (7, 8), 61_000, 30, 0).unwrap();
+        assert_eq!(vwap.vwap, dec!(107.5));
+        assert_eq!(vwap.volume, dec!(4));
+        assert_eq!(vwap.sample_count, 2);
+        assert_eq!(vwap.coverage, 1.0);
+        assert_eq!(vwap.trade_ids, (7, 8));
+
+        // Observed for 15.25s of the 61s window
+        assert_eq!(volume_weighted(&flow, (7, 8), 61_000, 61, 45_750).unwrap().coverage, 0.25);
+        assert!(volume_weighted(&AggressorFlow::default(), (0, 0), 61_000, 30, 0).is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::Deserialize;
+
+// Normalize a trading pair to the upper-case form used by Binance payloads (e.g. btcfdusd -> BTCFDUSD)
+pub fn normalize_symbol(symbol: &str) -> String {
+    symbol.trim().to_uppercase()
+}
+
+// Extract the trading pair from a combined stream name (e.g. btcfdusd@depth10@100ms -> BTCFDUSD)
+pub fn symbol_from_stream(stream: &str) -> String {
+    normalize_symbol(stream.split('@').next().unwrap_or_default())
+}
+
+// A trading pair and the product it is listed as on another venue, whose quotes are consolidated with it.
+// Written as `SYMBOL:PRODUCT_ID`, e.g. `BTCFDUSD:BTC-USD`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct VenueProduct {
+    pub symbol: String,
+    pub product_id: String,
+}
+
+impl FromStr for VenueProduct {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split(':').map(str::trim).collect::<Vec<&str>>()[..] {
+            [symbol, product_id] if !symbol.is_empty() && !product_id.is_empty() => Ok(VenueProduct {
+                symbol: normalize_symbol(symbol),
+                product_id: product_id.to_uppercase(),
+            }),
+            _ => Err(format!("Venue product {} has to be written as SYMBOL:PRODUCT_ID", value)),
+        }
+    }
+}
+
+impl TryFrom<String> for VenueProduct {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for VenueProduct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.symbol, self.product_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_symbol() {
+        assert_eq!(normalize_symbol(" btcfdusd "), "BTCFDUSD");
+        assert_eq!(normalize_symbol("ETHUSDT"), "ETHUSDT");
+    }
+
+    #[test]
+    fn test_symbol_from_stream() {
+        assert_eq!(symbol_from_stream("btcfdusd@depth10@100ms"), "BTCFDUSD");
+        assert_eq!(symbol_from_stream("ethusdt@bookTicker"), "ETHUSDT");
+        assert_eq!(symbol_from_stream(""), "");
+    }
+
+    #[test]
+    fn test_venue_product() {
+        let product: VenueProduct = " btcfdusd : btc-usd ".parse().unwrap();
+        assert_eq!(product, VenueProduct { symbol: "BTCFDUSD".to_string(), product_id: "BTC-USD".to_string() });
+        assert_eq!(product.to_string(), "BTCFDUSD:BTC-USD");
+        assert!("BTCFDUSD".parse::<VenueProduct>().is_err());
+        assert!("BTCFDUSD:".parse::<VenueProduct>().is_err());
+    }
+}
@@ -1,4 +1,6 @@
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use crate::domain::entities::market_event::TradeEvent;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TradeSD {
@@ -17,9 +19,9 @@ pub struct TradeData {
     #[serde(rename = "t")]
     pub trade_id: u64,             // Trade ID
     #[serde(rename = "p")]
-    pub price: String,             // Price
+    pub price: Decimal,            // Price
     #[serde(rename = "q")]
-    pub quantity: String,          // Quantity
+    pub quantity: Decimal,         // Quantity
     #[serde(rename = "T")]
     pub trade_time: u64,           // Trade time
     #[serde(rename = "m")]
@@ -28,3 +30,152 @@ pub struct TradeData {
     pub ignore: bool               // Ignore
 }
 
+impl TradeData {
+    pub fn to_event(&self) -> TradeEvent {
+        TradeEvent {
+            symbol: self.symbol.clone(),
+            trade_id: self.trade_id,
+            price: self.price,
+            quantity: self.quantity,
+            trade_time: self.trade_time,
+            event_time: Some(self.event_time),
+            is_buyer_market_maker: self.is_buyer_market_maker,
+        }
+    }
+
+    // The seller was the maker, so the buyer crossed the spread
+    pub fn is_buy_initiated(&self) -> bool {
+        !self.is_buyer_market_maker
+    }
+}
+
+// Trades are kept and published in the stream format, trades without an event time take their trade time
+impl From<&TradeEvent> for TradeData {
+    fn from(trade: &TradeEvent) -> Self {
+        TradeData {
+            event_type: "trade".to_string(),
+            event_time: trade.event_time.unwrap_or(trade.trade_time),
+            symbol: trade.symbol.clone(),
+            trade_id: trade.trade_id,
+            price: trade.price,
+            quantity: trade.quantity,
+            trade_time: trade.trade_time,
+            is_buyer_market_maker: trade.is_buyer_market_maker,
+            ignore: false,
+        }
+    }
+}
+
+// Traded volume split by the side of the aggressor (taker)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AggressorFlow {
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    pub buy_count: u64,
+    pub sell_count: u64,
+    pub buy_notional: Decimal,  // Sum of price * quantity
+    pub sell_notional: Decimal,
+}
+
+impl AggressorFlow {
+    pub fn add(&mut self, trade: &TradeData) {
+        let notional = trade.price * trade.quantity;
+        if trade.is_buy_initiated() {
+            self.buy_volume += trade.quantity;
+            self.buy_count += 1;
+            self.buy_notional += notional;
+        } else {
+            self.sell_volume += trade.quantity;
+            self.sell_count += 1;
+            self.sell_notional += notional;
+        }
+    }
+
+    pub fn merge(&mut self, other: &AggressorFlow) {
+        self.buy_volume += other.buy_volume;
+        self.sell_volume += other.sell_volume;
+        self.buy_count += other.buy_count;
+        self.sell_count += other.sell_count;
+        self.buy_notional += other.buy_notional;
+        self.sell_notional += other.sell_notional;
+    }
+
+    // Remove flow merged before, decimals keep the running totals exact
+    pub fn subtract(&mut self, other: &AggressorFlow) {
+        self.buy_volume -= other.buy_volume;
+        self.sell_volume -= other.sell_volume;
+        self.buy_count -= other.buy_count;
+        self.sell_count -= other.sell_count;
+        self.buy_notional -= other.buy_notional;
+        self.sell_notional -= other.sell_notional;
+    }
+
+    pub fn volume(&self) -> Decimal {
+        self.buy_volume + self.sell_volume
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buy_count + self.sell_count
+    }
+
+    pub fn notional(&self) -> Decimal {
+        self.buy_notional + self.sell_notional
+    }
+
+    // Buy minus sell volume
+    pub fn volume_delta(&self) -> Decimal {
+        self.buy_volume - self.sell_volume
+    }
+
+    // Order-flow imbalance from -1 (only sells) to 1 (only buys), `None` without volume
+    pub fn imbalance(&self) -> Option<Decimal> {
+        let total = self.volume();
+        if total.is_zero() {
+            return None;
+        }
+        Some(self.volume_delta() / total)
+    }
+}
+
+// Cumulative volume delta (buy minus sell volume) of every trade since `since`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CumulativeVolumeDelta {
+    pub delta: Decimal,
+    pub since: u64, // Startup or the last reset, milliseconds since the UNIX epoch
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn trade(price: Decimal, quantity: Decimal, is_buyer_market_maker: bool) -> TradeData {
+        TradeData {
+            event_type: "trade".to_string(),
+            event_time: 0,
+            symbol: "FLOWTEST".to_string(),
+            trade_id: 0,
+            price,
+            quantity,
+            trade_time: 0,
+            is_buyer_market_maker,
+            ignore: false,
+        }
+    }
+
+    #[test]
+    fn test_aggressor_flow_splits_by_taker_side() {
+        let mut flow = AggressorFlow::default();
+        assert_eq!(flow.imbalance(), None);
+
+        flow.add(&trade(dec!(100), dec!(3), false));
+        flow.add(&trade(dec!(101), dec!(1), true));
+        flow.add(&trade(dec!(102), dec!(1), false));
+
+        assert_eq!((flow.buy_volume, flow.buy_count, flow.buy_notional), (dec!(4), 2, dec!(402)));
+        assert_eq!((flow.sell_volume, flow.sell_count, flow.sell_notional), (dec!(1), 1, dec!(101)));
+        assert_eq!(flow.volume_delta(), dec!(3));
+        assert_eq!(flow.imbalance(), Some(dec!(0.6)));
+    }
+}
+
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use crate::domain::entities::trade::{AggressorFlow, TradeData};
+
+// Width of a time bucket in milliseconds
+const BUCKET_MS: u64 = 1_000;
+
+// Trades of one second, aggregated and raw (the raw trades resolve the partially covered bucket at a window edge)
+#[derive(Debug)]
+struct Bucket {
+    start: u64,
+    flow: AggressorFlow,
+    trades: Vec<TradeData>,
+}
+
+// Running totals of one tracked window, they hold every bucket starting at `from` or later
+#[derive(Debug)]
+struct WindowTotals {
+    window_ms: u64,
+    from: u64,
+    flow: AggressorFlow,
+}
+
+// Start of the first bucket lying completely at or after `time`
+fn bucket_at_or_after(time: u64) -> u64 {
+    time.div_ceil(BUCKET_MS) * BUCKET_MS
+}
+
+// Trades of one trading pair in one second buckets (by trade time) with running totals per tracked window.
+// A trade counts for a window ending at `now` when it is at most the window length old.
+// Tracked windows are answered from their totals, other windows by adding up their buckets.
+#[derive(Debug)]
+pub struct TradeWindows {
+    buckets: VecDeque<Bucket>, // Oldest first
+    totals: Vec<WindowTotals>,
+    retention_ms: u64,
+}
+
+impl TradeWindows {
+    // `retention_ms` is raised to the longest tracked window
+    pub fn new(windows_ms: &[u64], retention_ms: u64) -> Self {
+        TradeWindows {
+            buckets: VecDeque::new(),
+            totals: windows_ms.iter()
+                .map(|&window_ms| WindowTotals { window_ms, from: 0, flow: AggressorFlow::default() })
+                .collect(),
+            retention_ms: windows_ms.iter().copied().fold(retention_ms, u64::max),
+        }
+    }
+
+    pub fn add(&mut self, trade: &TradeData, now: u64) {
+        let start = trade.trade_time - trade.trade_time % BUCKET_MS;
+        // Trades arrive in time order, a late one is sorted into its bucket
+        let position = self.buckets.iter().rposition(|bucket| bucket.start <= start);
+        let index = match position {
+            Some(index) if self.buckets[index].start == start => index,
+            _ => {
+                let index = position.map_or(0, |index| index + 1);
+                self.buckets.insert(index, Bucket { start, flow: AggressorFlow::default(), trades: Vec::new() });
+                index
+            }
+        };
+        let bucket = &mut self.buckets[index];
+        bucket.flow.add(trade);
+        bucket.trades.push(trade.clone());
+
+        for totals in self.totals.iter_mut().filter(|totals| start >= totals.from) {
+            totals.flow.add(trade);
+        }
+        self.advance(now);
+    }
+
+    // Move the tracked windows to `now` and drop the buckets older than the retention,
+    // so statistics do not depend on new trades arriving
+    pub fn advance(&mut self, now: u64) {
+        for totals in self.totals.iter_mut() {
+            let from = bucket_at_or_after(now.saturating_sub(totals.window_ms));
+            if from <= totals.from {
+                continue;
+            }
+            for bucket in self.buckets.iter().filter(|bucket| bucket.start >= totals.from && bucket.start < from) {
+                totals.flow.subtract(&bucket.flow);
+            }
+            totals.from = from;
+        }
+
+        let horizon = now.saturating_sub(self.retention_ms);
+        while self.buckets.front().is_some_and(|bucket| bucket.start + BUCKET_MS <= horizon) {
+            self.buckets.pop_front();
+        }
+    }
+
+    // Flow of the trades at most `window_ms` old at `now`, call `advance(now)` first
+    pub fn flow(&self, now: u64, window_ms: u64) -> AggressorFlow {
+        let window_start = now.saturating_sub(window_ms);
+        let from = bucket_at_or_after(window_start);
+
+        let mut flow = match self.totals.iter().find(|totals| totals.window_ms == window_ms && totals.from == from) {
+            Some(totals) => totals.flow.clone(),
+            None => {
+                let mut flow = AggressorFlow::default();
+                for bucket in self.buckets.iter().filter(|bucket| bucket.start >= from) {
+                    flow.merge(&bucket.flow);
+                }
+                flow
+            }
+        };
+
+        // The bucket the window starts in only counts with its trades inside the window
+        if let Some(edge) = self.buckets.iter().find(|bucket| bucket.start < window_start && bucket.start + BUCKET_MS > window_start) {
+            for trade in edge.trades.iter().filter(|trade| trade.trade_time >= window_start) {
+                flow.add(trade);
+            }
+        }
+        flow
+    }
+
+    // Lowest and highest id of the trades at most `window_ms` old at `now`, `None` without trades
+    pub fn trade_id_range(&self, now: u64, window_ms: u64) -> Option<(u64, u64)> {
+        let window_start = now.saturating_sub(window_ms);
+        self.buckets.iter()
+            .filter(|bucket| bucket.start + BUCKET_MS > window_start)
+            .flat_map(|bucket| bucket.trades.iter())
+            .filter(|trade| trade.trade_time >= window_start)
+            .fold(None, |range, trade| match range {
+                None => Some((trade.trade_id, trade.trade_id)),
+                Some((first, last)) => Some((trade.trade_id.min(first), trade.trade_id.max(last))),
+            })
+    }
+
+    // Number of retained trades
+    pub fn trade_count(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.trades.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn trade(trade_time: u64, quantity: Decimal) -> TradeData {
+        TradeData {
+            event_type: "trade".to_string(),
+            event_time: trade_time,
+            symbol: "WINDOWTEST".to_string(),
+            trade_id: trade_time,
+            price: dec!(10),
+            quantity,
+            trade_time,
+            is_buyer_market_maker: false,
+            ignore: false,
+        }
+    }
+
+    #[test]
+    fn test_windows_are_exact_at_the_edge() {
+        let mut windows = TradeWindows::new(&[10_000, 60_000], 60_000);
+        windows.add(&trade(100_250, dec!(1)), 100_250);
+        windows.add(&trade(100_750, dec!(2)), 100_750);
+        windows.add(&trade(105_000, dec!(4)), 105_000);
+
+        // The window of 10s at 110_500 starts in the middle of the first bucket
+        windows.advance(110_500);
+        assert_eq!(windows.flow(110_500, 10_000).volume(), dec!(6));
+        windows.advance(110_750);
+        assert_eq!(windows.flow(110_750, 10_000).volume(), dec!(6));
+        windows.advance(110_751);
+        assert_eq!(windows.flow(110_751, 10_000).volume(), dec!(4));
+        assert_eq!(windows.flow(110_751, 60_000).volume(), dec!(7));
+        // An untracked window adds up the buckets
+        assert_eq!(windows.flow(110_751, 30_000).volume(), dec!(7));
+        assert_eq!(windows.trade_id_range(110_751, 10_000), Some((105_000, 105_000)));
+        assert_eq!(windows.trade_id_range(110_751, 60_000), Some((100_250, 105_000)));
+    }
+
+    #[test]
+    fn test_time_alone_expires_trades() {
+        let mut windows = TradeWindows::new(&[10_000], 20_000);
+        windows.add(&trade(1_000, dec!(1)), 1_000);
+        windows.add(&trade(2_000, dec!(1)), 2_000);
+        assert_eq!(windows.trade_count(), 2);
+
+        // No new trades, only the clock moves
+        windows.advance(11_500);
+        assert_eq!(windows.flow(11_500, 10_000).volume(), dec!(1));
+        windows.advance(30_000);
+        assert_eq!(windows.flow(30_000, 10_000).volume(), Decimal::ZERO);
+        assert_eq!(windows.trade_count(), 0);
+    }
+
+    #[test]
+    fn test_late_trades_are_sorted_into_their_bucket() {
+        let mut windows = TradeWindows::new(&[5_000], 5_000);
+        windows.add(&trade(9_000, dec!(1)), 9_000);
+        windows.add(&trade(7_500, dec!(2)), 9_100);
+        windows.add(&trade(1_000, dec!(4)), 9_200);
+
+        // The trade from 1_000 is too old for the window, the one from 7_500 is not
+        windows.advance(10_000);
+        assert_eq!(windows.flow(10_000, 5_000).volume(), dec!(3));
+        assert_eq!(windows.trade_count(), 2);
+    }
+}
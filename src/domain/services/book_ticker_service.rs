@@ -1,109 +1,264 @@
 #![allow(dead_code)]
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
-use once_cell::sync::Lazy;
-use tokio::sync::Mutex;
-use crate::domain::entities::book_ticker::{BookTickerData, BookTickerSD};
-
-// Singleton for BookTickerData shared across the application
-pub static BOOK_TICKER: Lazy<Arc<Mutex<BookTickerData>>> = Lazy::new(|| {
-    Arc::new(Mutex::new(BookTickerData::default()))
-});
+use rust_decimal::Decimal;
+use tokio::sync::{broadcast, Mutex};
+use crate::domain::entities::book_ticker::BookTickerData;
+use crate::domain::entities::freshness::{DataTimes, Staleness};
+use crate::domain::entities::market_event::TopOfBook;
+use crate::domain::entities::reference_price::{time_weighted, PriceSample, Twap};
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::clock::{Clock, SystemClock};
+use crate::domain::services::freshness::FreshnessTracker;
+use crate::domain::services::{DEFAULT_RETENTION, UPDATE_CHANNEL_CAPACITY};
 
 // Trait for the BookTickerService that defines the methods
 #[async_trait]
 pub trait BookTickerServiceTrait: Send + Sync {
-    async fn update_ticker(&self, update: BookTickerSD);
-    async fn print_ticker(&self);
-    async fn mid_price(&self) -> f64;
-    async fn mid_weighted_price(&self) -> f64;
-    async fn get_ticker_data(&self) -> BookTickerData;
+    async fn update_ticker(&self, top: &TopOfBook);
+    async fn print_ticker(&self, symbol: &str);
+    async fn mid_price(&self, symbol: &str) -> Option<Decimal>;
+    async fn mid_weighted_price(&self, symbol: &str) -> Option<Decimal>;
+    async fn get_ticker_data(&self, symbol: &str) -> Option<BookTickerData>;
+    // Time-weighted mid and microprice over the last `window_secs`
+    async fn twap(&self, symbol: &str, window_secs: u64) -> Option<Twap>;
+    // Receive time of the last ticker, an error when there is none or it is too old to be served
+    fn data_times(&self, symbol: &str) -> Result<DataTimes, Staleness>;
+    // Receive time of the last ticker even when it is too old to be served
+    fn last_times(&self, symbol: &str) -> Option<DataTimes>;
+    fn subscribe(&self) -> broadcast::Receiver<BookTickerData>;
 }
 
-// Implementation of BookTickerService
+// Implementation of BookTickerService, owns the latest ticker and the price samples per trading pair
 #[derive(Debug)]
-pub struct BookTickerService;
+pub struct BookTickerService {
+    tickers: Mutex<HashMap<String, BookTickerData>>,
+    samples: Mutex<HashMap<String, VecDeque<PriceSample>>>,
+    updates: broadcast::Sender<BookTickerData>,
+    freshness: FreshnessTracker,
+    clock: Arc<dyn Clock>,
+    retention: Duration,
+}
+
+impl BookTickerService {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    // Service timestamping its price samples with the given clock (e.g. the replay clock)
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        BookTickerService {
+            tickers: Mutex::new(HashMap::new()),
+            samples: Mutex::new(HashMap::new()),
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            freshness: FreshnessTracker::new(clock.clone()),
+            clock,
+            retention: DEFAULT_RETENTION,
+        }
+    }
+
+    // Keep the price samples for at least `retention`, the longest TWAP window served
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = self.retention.max(retention);
+        self
+    }
+
+    // Tickers received longer than `max_age` ago are not served
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.freshness = self.freshness.with_max_age(max_age);
+        self
+    }
+
+    async fn record_sample(&self, ticker: &BookTickerData) {
+        let now = self.clock.now_millis();
+        let mut samples = self.samples.lock().await;
+        let symbol_samples = samples.entry(normalize_symbol(&ticker.symbol)).or_default();
+
+        // Keep the sample in force at the start of the retention, it still covers part of the oldest window
+        let horizon = now.saturating_sub(self.retention.as_millis() as u64);
+        while symbol_samples.get(1).is_some_and(|next| next.time <= horizon) {
+            symbol_samples.pop_front();
+        }
+        symbol_samples.push_back(PriceSample {
+            time: now,
+            mid: ticker.mid_price(),
+            microprice: ticker.microprice(),
+        });
+    }
+}
+
+impl Default for BookTickerService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[async_trait]
 impl BookTickerServiceTrait for BookTickerService {
-    async fn update_ticker(&self, update: BookTickerSD) {
-        let mut ticker = BOOK_TICKER.lock().await;
-        ticker.update_id = update.data.update_id;
-        ticker.symbol = update.data.symbol;
-        ticker.best_bid_price = update.data.best_bid_price;
-        ticker.best_bid_qty = update.data.best_bid_qty;
-        ticker.best_ask_price = update.data.best_ask_price;
-        ticker.best_ask_qty = update.data.best_ask_qty;
+    async fn update_ticker(&self, top: &TopOfBook) {
+        let mut tickers = self.tickers.lock().await;
+        let ticker = tickers.entry(normalize_symbol(&top.symbol)).or_default();
+        ticker.update_id = top.update_id;
+        ticker.symbol = top.symbol.clone();
+        ticker.best_bid_price = top.best_bid.price;
+        ticker.best_bid_qty = top.best_bid.qty;
+        ticker.best_ask_price = top.best_ask.price;
+        ticker.best_ask_qty = top.best_ask.qty;
+        self.record_sample(ticker).await;
+        self.freshness.record(&ticker.symbol, top.event_time);
+
+        // No subscribers is not an error, sending never blocks the ingestion
+        let _ = self.updates.send(ticker.clone());
+    }
+
+    async fn print_ticker(&self, symbol: &str) {
+        let tickers = self.tickers.lock().await;
+        if let Some(ticker) = tickers.get(&normalize_symbol(symbol)) {
+            ticker.print();
+        }
+    }
+
+    async fn mid_price(&self, symbol: &str) -> Option<Decimal> {
+        let tickers = self.tickers.lock().await;
+        tickers.get(&normalize_symbol(symbol)).map(|ticker| ticker.mid_price())
+    }
+
+    async fn mid_weighted_price(&self, symbol: &str) -> Option<Decimal> {
+        let tickers = self.tickers.lock().await;
+        tickers.get(&normalize_symbol(symbol)).map(|ticker| ticker.mid_weighted_price())
     }
 
-    async fn print_ticker(&self) {
-        let ticker = BOOK_TICKER.lock().await;
-        ticker.print();
+    async fn get_ticker_data(&self, symbol: &str) -> Option<BookTickerData> {
+        let tickers = self.tickers.lock().await;
+        tickers.get(&normalize_symbol(symbol)).cloned()
+    }
+    async fn twap(&self, symbol: &str, window_secs: u64) -> Option<Twap> {
+        let samples = self.samples.lock().await;
+        time_weighted(samples.get(&normalize_symbol(symbol))?, self.clock.now_millis(), window_secs)
     }
 
-    async fn mid_price(&self) -> f64 {
-        let ticker = BOOK_TICKER.lock().await;
-        ticker.mid_price()
+    fn data_times(&self, symbol: &str) -> Result<DataTimes, Staleness> {
+        self.freshness.check(symbol)
     }
 
-    async fn mid_weighted_price(&self) -> f64 {
-        let ticker = BOOK_TICKER.lock().await;
-        ticker.mid_weighted_price()
+    fn last_times(&self, symbol: &str) -> Option<DataTimes> {
+        self.freshness.times(symbol)
     }
 
-    async fn get_ticker_data(&self) -> BookTickerData {
-        let ticker = BOOK_TICKER.lock().await;
-        ticker.clone()
+    fn subscribe(&self) -> broadcast::Receiver<BookTickerData> {
+        self.updates.subscribe()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::domain::book_ticker::{BookTickerData, BookTickerSD};
+    use crate::domain::entities::order_book::OrderBookEntry;
     use super::*;
+    use rust_decimal_macros::dec;
+    use crate::domain::services::clock::ReplayClock;
+
+    fn ticker(symbol: &str, bid: &str, ask: &str) -> TopOfBook {
+        TopOfBook {
+            symbol: symbol.to_string(),
+            update_id: 123,
+            best_bid: OrderBookEntry { price: bid.parse().unwrap(), qty: dec!(2.0) },
+            best_ask: OrderBookEntry { price: ask.parse().unwrap(), qty: dec!(3.0) },
+            event_time: None,
+        }
+    }
 
     #[tokio::test]
     async fn test_mid_price() {
-        let service = BookTickerService;
-
-        let data = BookTickerSD {
-            stream: "btcfdusd@bookTicker".to_string(),
-            data: BookTickerData {
-                update_id: 123,
-                symbol: "BTCUSD".to_string(),
-                best_bid_price: "50000.0".to_string(),
-                best_bid_qty: "2.0".to_string(),
-                best_ask_price: "51000.0".to_string(),
-                best_ask_qty: "3.0".to_string(),
-            }
-        };
+        let service = BookTickerService::new();
 
-        service.update_ticker(data).await;
+        service.update_ticker(&ticker("MIDTEST1", "50000.0", "51000.0")).await;
 
-        let mid_price = service.mid_price().await;
-        assert_eq!(mid_price, 50500.0); // (50000 + 51000) / 2
+        let mid_price = service.mid_price("MIDTEST1").await;
+        assert_eq!(mid_price, Some(dec!(50500.0))); // (50000 + 51000) / 2
     }
 
     #[tokio::test]
     async fn test_mid_weighted_price() {
-        let service = BookTickerService;
-
-        let data = BookTickerSD {
-            stream: "btcfdusd@bookTicker".to_string(),
-            data: BookTickerData {
-                update_id: 123,
-                symbol: "BTCUSD".to_string(),
-                best_bid_price: "50000.0".to_string(),
-                best_bid_qty: "2.0".to_string(),
-                best_ask_price: "51000.0".to_string(),
-                best_ask_qty: "3.0".to_string(),
-            }
-        };
-
-        service.update_ticker(data).await;
-
-        let mid_weighted_price = service.mid_weighted_price().await;
+        let service = BookTickerService::new();
+
+        service.update_ticker(&ticker("MIDTEST2", "50000.0", "51000.0")).await;
+
+        let mid_weighted_price = service.mid_weighted_price("midtest2").await;
         // Weighted average: (50000 * 2 + 51000 * 3) / (2 + 3) = 50600
-        assert_eq!(mid_weighted_price, 50600.0);
+        assert_eq!(mid_weighted_price, Some(dec!(50600.0)));
+    }
+
+    #[tokio::test]
+    async fn test_symbols_are_tracked_independently() {
+        let service = BookTickerService::new();
+
+        service.update_ticker(&ticker("MULTITEST1", "100.0", "102.0")).await;
+        service.update_ticker(&ticker("MULTITEST2", "10.0", "12.0")).await;
+
+        assert_eq!(service.mid_price("MULTITEST1").await, Some(dec!(101.0)));
+        assert_eq!(service.mid_price("MULTITEST2").await, Some(dec!(11.0)));
+        assert!(service.get_ticker_data("UNKNOWN").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_updates_are_published_to_subscribers() {
+        let service = BookTickerService::new();
+        let mut updates = service.subscribe();
+
+        service.update_ticker(&ticker("PUBTEST", "1.0", "3.0")).await;
+
+        let update = updates.recv().await.unwrap();
+        assert_eq!(update.symbol, "PUBTEST");
+        assert_eq!(update.mid_price(), dec!(2.0));
+    }
+
+    #[tokio::test]
+    async fn test_twap_follows_the_samples_of_every_update() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = BookTickerService::with_clock(clock.clone()).with_retention(Duration::from_secs(60));
+
+        clock.set(1_000_000);
+        service.update_ticker(&ticker("TWAPTEST", "99.0", "101.0")).await;
+        clock.set(1_030_000);
+        service.update_ticker(&ticker("TWAPTEST", "109.0", "111.0")).await;
+        clock.set(1_060_000);
+
+        // Mid 100 for the first 30s of the minute, 110 for the last 30s
+        let twap = service.twap("twaptest", 60).await.unwrap();
+        assert_eq!(twap.mid, dec!(105));
+        assert_eq!(twap.sample_count, 2);
+        assert_eq!(twap.coverage, 1.0);
+        // Bid quantity 2, ask quantity 3: the microprice is 0.2 below the mid
+        assert_eq!(twap.microprice, dec!(104.8));
+        assert!(service.twap("UNKNOWN", 60).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tickers_are_stale_after_the_maximum_age() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = BookTickerService::with_clock(clock.clone()).with_max_age(Duration::from_secs(10));
+        assert_eq!(service.data_times("STALETEST"), Err(Staleness::NoData));
+
+        clock.set(1_000_000);
+        service.update_ticker(&ticker("STALETEST", "99.0", "101.0")).await;
+        assert_eq!(service.data_times("staletest"), Ok(DataTimes { event_time: None, received_at: 1_000_000 }));
+
+        clock.set(1_010_001);
+        assert!(matches!(service.data_times("STALETEST"), Err(Staleness::Stale { age_ms: 10_001, .. })));
+        // The ticker itself is kept, only serving it is refused
+        assert_eq!(service.mid_price("STALETEST").await, Some(dec!(100.0)));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_service_instances_do_not_share_state() {
+        let first = BookTickerService::new();
+        let second = BookTickerService::new();
+
+        first.update_ticker(&ticker("BTCFDUSD", "100.0", "102.0")).await;
+
+        assert_eq!(first.mid_price("BTCFDUSD").await, Some(dec!(101.0)));
+        assert!(second.get_ticker_data("BTCFDUSD").await.is_none());
+    }
+}
@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Source of the current time for the time windows kept by the services
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    // Milliseconds since the UNIX epoch
+    fn now_millis(&self) -> u64;
+}
+
+// Wall clock used for live ingestion
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+    }
+}
+
+// Clock driven by the replay: it shows the receive time of the frame replayed last,
+// so time windows look exactly like they did when the frames were recorded
+#[derive(Debug, Default)]
+pub struct ReplayClock {
+    now: AtomicU64,
+}
+
+impl ReplayClock {
+    pub fn set(&self, now_millis: u64) {
+        self.now.store(now_millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ReplayClock {
+    fn now_millis(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
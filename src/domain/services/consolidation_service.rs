@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::domain::entities::consolidated_price::{ConsolidatedPrice, ConsolidationError, ConsolidationMethod, VenueQuote};
+use crate::domain::entities::freshness::DataTimes;
+use crate::domain::entities::market_event::TopOfBook;
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::clock::{Clock, SystemClock};
+use crate::domain::services::DEFAULT_MAX_DATA_AGE;
+
+// Keeps the latest top of book of every venue per trading pair and combines the fresh ones into a single price,
+// so the price does not depend on one exchange
+#[derive(Debug)]
+pub struct ConsolidationService {
+    quotes: Mutex<HashMap<String, BTreeMap<String, VenueQuote>>>,
+    clock: Arc<dyn Clock>,
+    method: ConsolidationMethod,
+    min_venues: usize,
+    max_age: Duration,
+}
+
+impl ConsolidationService {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    // Service timestamping the quotes with the given clock (e.g. the replay clock)
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        ConsolidationService {
+            quotes: Mutex::new(HashMap::new()),
+            clock,
+            method: ConsolidationMethod::Median,
+            min_venues: 1,
+            max_age: DEFAULT_MAX_DATA_AGE,
+        }
+    }
+
+    pub fn with_method(mut self, method: ConsolidationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    // Venues with a fresh quote needed for a consolidated price
+    pub fn with_min_venues(mut self, min_venues: usize) -> Self {
+        self.min_venues = min_venues.max(1);
+        self
+    }
+
+    // Quotes received longer than `max_age` ago are not consolidated
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    // Replace the quote of `venue` for the trading pair of `top`
+    pub fn update(&self, venue: &str, top: &TopOfBook) {
+        let quote = VenueQuote {
+            venue: venue.to_string(),
+            best_bid: top.best_bid.clone(),
+            best_ask: top.best_ask.clone(),
+            times: DataTimes { event_time: top.event_time, received_at: self.clock.now_millis() },
+            fresh: true,
+        };
+        self.quotes.lock().unwrap()
+            .entry(normalize_symbol(&top.symbol))
+            .or_default()
+            .insert(venue.to_string(), quote);
+    }
+
+    // Latest quote of every venue ordered by venue, whether fresh or not
+    pub fn quotes(&self, symbol: &str) -> Vec<VenueQuote> {
+        let now = self.clock.now_millis();
+        let quotes = self.quotes.lock().unwrap();
+        quotes.get(&normalize_symbol(symbol)).into_iter()
+            .flat_map(BTreeMap::values)
+            .map(|quote| VenueQuote { fresh: quote.times.check(now, self.max_age).is_ok(), ..quote.clone() })
+            .collect()
+    }
+
+    // Price of the trading pair across the venues with a fresh quote
+    pub fn consolidated(&self, symbol: &str) -> Result<ConsolidatedPrice, ConsolidationError> {
+        let quotes = self.quotes(symbol);
+        if quotes.is_empty() {
+            return Err(ConsolidationError::NoData);
+        }
+        let fresh: Vec<VenueQuote> = quotes.iter().filter(|quote| quote.fresh).cloned().collect();
+        let not_enough = ConsolidationError::NotEnoughVenues { fresh: fresh.len(), required: self.min_venues };
+        if fresh.len() < self.min_venues {
+            return Err(not_enough);
+        }
+
+        Ok(ConsolidatedPrice {
+            symbol: normalize_symbol(symbol),
+            method: self.method,
+            price: self.method.price(&fresh).ok_or(not_enough)?,
+            venues_used: fresh.len(),
+            quotes,
+        })
+    }
+}
+
+impl Default for ConsolidationService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+    use crate::domain::entities::order_book::OrderBookEntry;
+    use crate::domain::services::clock::ReplayClock;
+
+    fn top(bid: Decimal, ask: Decimal, qty: Decimal) -> TopOfBook {
+        TopOfBook {
+            symbol: "btcfdusd".to_string(),
+            update_id: 1,
+            best_bid: OrderBookEntry { price: bid, qty },
+            best_ask: OrderBookEntry { price: ask, qty },
+            event_time: None,
+        }
+    }
+
+    #[test]
+    fn test_stale_venues_are_left_out_of_the_price() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = ConsolidationService::with_clock(clock.clone()).with_max_age(Duration::from_secs(5));
+        assert_eq!(service.consolidated("BTCFDUSD").unwrap_err(), ConsolidationError::NoData);
+
+        clock.set(1_000);
+        service.update("coinbase", &top(dec!(101), dec!(103), dec!(1)));
+        clock.set(4_000);
+        service.update("binance", &top(dec!(99), dec!(101), dec!(1)));
+        let price = service.consolidated("BTCFDUSD").unwrap();
+        assert_eq!((price.price, price.venues_used), (dec!(101), 2));
+        assert_eq!(price.quotes.iter().map(|quote| quote.venue.as_str()).collect::<Vec<_>>(), vec!["binance", "coinbase"]);
+
+        // Coinbase goes quiet: its quote is still shown but only Binance makes the price
+        clock.set(7_000);
+        let price = service.consolidated("BTCFDUSD").unwrap();
+        assert_eq!((price.price, price.venues_used), (dec!(100), 1));
+        assert!(!price.quotes[1].fresh);
+    }
+
+    #[test]
+    fn test_price_needs_the_minimum_fresh_venues() {
+        let service = ConsolidationService::new().with_method(ConsolidationMethod::VolumeWeighted).with_min_venues(2);
+        service.update("binance", &top(dec!(99), dec!(101), dec!(3)));
+        assert_eq!(service.consolidated("BTCFDUSD").unwrap_err(), ConsolidationError::NotEnoughVenues { fresh: 1, required: 2 });
+
+        service.update("coinbase", &top(dec!(103), dec!(105), dec!(1)));
+        assert_eq!(service.consolidated("BTCFDUSD").unwrap().price, dec!(101));
+    }
+}
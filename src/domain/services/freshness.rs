@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::domain::entities::freshness::{DataTimes, Staleness};
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::clock::Clock;
+use crate::domain::services::DEFAULT_MAX_DATA_AGE;
+
+// Event and receive time of the last update per trading pair, kept by every service for the data it serves
+#[derive(Debug)]
+pub struct FreshnessTracker {
+    times: Mutex<HashMap<String, DataTimes>>,
+    clock: Arc<dyn Clock>,
+    max_age: Duration,
+}
+
+impl FreshnessTracker {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        FreshnessTracker {
+            times: Mutex::new(HashMap::new()),
+            clock,
+            max_age: DEFAULT_MAX_DATA_AGE,
+        }
+    }
+
+    // Data received longer than `max_age` ago is stale
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    // Remember that an update of `symbol` with the given exchange event time has just been received
+    pub fn record(&self, symbol: &str, event_time: Option<u64>) {
+        let received_at = self.clock.now_millis();
+        self.times.lock().unwrap().insert(normalize_symbol(symbol), DataTimes { event_time, received_at });
+    }
+
+    // Forget the last update, the symbol has no data until the next one
+    pub fn forget(&self, symbol: &str) {
+        self.times.lock().unwrap().remove(&normalize_symbol(symbol));
+    }
+
+    // Times of the last update, whether fresh or not
+    pub fn times(&self, symbol: &str) -> Option<DataTimes> {
+        self.times.lock().unwrap().get(&normalize_symbol(symbol)).copied()
+    }
+
+    // Times of the last update when it is recent enough to be served
+    pub fn check(&self, symbol: &str) -> Result<DataTimes, Staleness> {
+        self.times(symbol).ok_or(Staleness::NoData)?.check(self.clock.now_millis(), self.max_age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::services::clock::ReplayClock;
+
+    #[test]
+    fn test_updates_go_stale_after_the_maximum_age() {
+        let clock = Arc::new(ReplayClock::default());
+        let tracker = FreshnessTracker::new(clock.clone()).with_max_age(Duration::from_secs(5));
+        assert_eq!(tracker.check("btcfdusd"), Err(Staleness::NoData));
+
+        clock.set(10_000);
+        tracker.record("btcfdusd", Some(9_990));
+        clock.set(15_000);
+        assert_eq!(tracker.check("BTCFDUSD"), Ok(DataTimes { event_time: Some(9_990), received_at: 10_000 }));
+        clock.set(15_001);
+        assert!(matches!(tracker.check("BTCFDUSD"), Err(Staleness::Stale { .. })));
+        assert_eq!(tracker.times("BTCFDUSD").map(|times| times.received_at), Some(10_000));
+    }
+}
@@ -1,3 +1,16 @@
+use std::time::Duration;
+
+// Capacity of the update channels, subscribers lagging further behind lose the oldest updates
+pub const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+// How long trades and ticker samples are kept unless a longer retention is requested
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(70);
+// Data received longer ago is not served unless another maximum age is configured
+pub const DEFAULT_MAX_DATA_AGE: Duration = Duration::from_secs(10);
+
 pub mod book_ticker_service;
+pub mod clock;
+pub mod consolidation_service;
+pub mod freshness;
 pub mod order_book_service;
+pub mod publisher_service;
 pub mod trade_history_service;
\ No newline at end of file
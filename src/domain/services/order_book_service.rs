@@ -1,44 +1,239 @@
 #![allow(dead_code)]
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use async_trait::async_trait;
-use once_cell::sync::Lazy;
-use tokio::sync::Mutex;
-use crate::domain::entities::order_book::{FullOrderBook, OrderBook, OrderBookTop, OrderBookSD};
-
-pub static ORDER_BOOK: Lazy<Arc<Mutex<OrderBook>>> = Lazy::new(|| {
-    Arc::new(Mutex::new(OrderBook::new()))
-});
+use tokio::sync::{broadcast, Mutex};
+use crate::domain::entities::freshness::{DataTimes, Staleness};
+use crate::domain::entities::market_event::{BookDelta, BookSnapshot};
+use crate::domain::entities::order_book::{DepthSyncError, FullOrderBook, OrderBook, OrderBookTop, OrderBookUpdate};
+use crate::domain::entities::order_book_analytics::{BookAnalytics, BookAnalyticsParams, ImpactEstimate, ImpactOrder};
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::clock::{Clock, SystemClock};
+use crate::domain::services::freshness::FreshnessTracker;
+use crate::domain::services::UPDATE_CHANNEL_CAPACITY;
 
 #[async_trait]
 pub trait OrderBookServiceTrait: Send + Sync {
-    async fn update_order_book(&self, update: OrderBookSD);
-    async fn print_top_of_book(&self);
-    async fn get_top_of_book(&self) -> Option<OrderBookTop>;
-    async fn get_full_book(&self) -> Option<FullOrderBook>;
+    // Replace the book of the snapshot's trading pair
+    async fn apply_snapshot(&self, snapshot: &BookSnapshot);
+    // Apply a delta on top of the book, an error when it does not continue the book
+    async fn apply_delta(&self, delta: &BookDelta) -> Result<(), DepthSyncError>;
+    // Drop the book until the next snapshot, e.g. while it is resynced after a gap
+    async fn clear_book(&self, symbol: &str);
+    async fn print_top_of_book(&self, symbol: &str);
+    async fn get_top_of_book(&self, symbol: &str) -> Option<OrderBookTop>;
+    async fn get_full_book(&self, symbol: &str) -> Option<FullOrderBook>;
+    async fn book_levels(&self, symbol: &str) -> Option<(usize, usize)>;
+    async fn analytics(&self, symbol: &str, params: &BookAnalyticsParams) -> Option<BookAnalytics>;
+    async fn impact(&self, symbol: &str, order: &ImpactOrder) -> Option<ImpactEstimate>;
+    // Event and receive time of the last book update, an error when there is none or it is too old to be served
+    fn data_times(&self, symbol: &str) -> Result<DataTimes, Staleness>;
+    // Event and receive time of the last book update even when it is too old to be served
+    fn last_times(&self, symbol: &str) -> Option<DataTimes>;
+    fn subscribe(&self) -> broadcast::Receiver<OrderBookUpdate>;
+}
+
+// Owns the order books per trading pair and announces every change to its subscribers
+pub struct OrderBookService {
+    books: Mutex<HashMap<String, OrderBook>>,
+    updates: broadcast::Sender<OrderBookUpdate>,
+    freshness: FreshnessTracker,
 }
 
-pub struct OrderBookService;
+impl OrderBookService {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    // Service stamping the receive time of the book updates with the given clock (e.g. the replay clock)
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        OrderBookService {
+            books: Mutex::new(HashMap::new()),
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            freshness: FreshnessTracker::new(clock),
+        }
+    }
+
+    // Books updated longer than `max_age` ago are not served
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.freshness = self.freshness.with_max_age(max_age);
+        self
+    }
+
+    // Publish the new top of book of a changed order book, sending never blocks the ingestion
+    fn publish_update(&self, symbol: String, book: &OrderBook) {
+        // No subscribers is not an error
+        let _ = self.updates.send(OrderBookUpdate {
+            symbol,
+            top: book.get_top(),
+        });
+    }
+}
+
+impl Default for OrderBookService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[async_trait]
 impl OrderBookServiceTrait for OrderBookService {
 
-    async fn update_order_book(&self, update: OrderBookSD) {
-        let mut book = ORDER_BOOK.lock().await;
-        book.update(update);
+    async fn apply_snapshot(&self, snapshot: &BookSnapshot) {
+        let mut books = self.books.lock().await;
+        let symbol = normalize_symbol(&snapshot.symbol);
+        let book = books.entry(symbol.clone()).or_insert_with(OrderBook::new);
+        book.apply_snapshot(snapshot);
+        self.freshness.record(&symbol, snapshot.event_time);
+        self.publish_update(symbol, book);
+    }
+
+    async fn apply_delta(&self, delta: &BookDelta) -> Result<(), DepthSyncError> {
+        let mut books = self.books.lock().await;
+        let symbol = normalize_symbol(&delta.symbol);
+        match books.get_mut(&symbol) {
+            Some(book) => {
+                book.apply_diff(delta)?;
+                self.freshness.record(&symbol, delta.event_time);
+                self.publish_update(symbol, book);
+                Ok(())
+            }
+            None => Err(DepthSyncError::NoSnapshot),
+        }
+    }
+
+    async fn clear_book(&self, symbol: &str) {
+        let symbol = normalize_symbol(symbol);
+        self.books.lock().await.remove(&symbol);
+        self.freshness.forget(&symbol);
+    }
+
+    async fn print_top_of_book(&self, symbol: &str) {
+        let books = self.books.lock().await;
+        if let Some(book) = books.get(&normalize_symbol(symbol)) {
+            book.print_top_of_book();
+        }
+    }
+
+    async fn get_top_of_book(&self, symbol: &str) -> Option<OrderBookTop> {
+        let books = self.books.lock().await;
+        books.get(&normalize_symbol(symbol)).and_then(|book| book.get_top())
+    }
+
+    async fn get_full_book(&self, symbol: &str) -> Option<FullOrderBook> {
+        let books = self.books.lock().await;
+        books.get(&normalize_symbol(symbol)).and_then(|book| book.get_full_book())
+    }
+
+    async fn book_levels(&self, symbol: &str) -> Option<(usize, usize)> {
+        let books = self.books.lock().await;
+        books.get(&normalize_symbol(symbol)).map(|book| book.levels())
     }
 
-    async fn print_top_of_book(&self) {
-        let book = ORDER_BOOK.lock().await;
-        book.print_top_of_book();
+    async fn analytics(&self, symbol: &str, params: &BookAnalyticsParams) -> Option<BookAnalytics> {
+        let books = self.books.lock().await;
+        books.get(&normalize_symbol(symbol)).and_then(|book| book.analytics(params))
     }
 
-    async fn get_top_of_book(&self) -> Option<OrderBookTop> {
-        let book = ORDER_BOOK.lock().await;
-        book.get_top()
+    async fn impact(&self, symbol: &str, order: &ImpactOrder) -> Option<ImpactEstimate> {
+        let books = self.books.lock().await;
+        books.get(&normalize_symbol(symbol)).and_then(|book| book.impact(order))
     }
 
-    async fn get_full_book(&self) -> Option<FullOrderBook> {
-        let book = ORDER_BOOK.lock().await;
-        book.get_full_book()
+    fn data_times(&self, symbol: &str) -> Result<DataTimes, Staleness> {
+        self.freshness.check(symbol)
     }
-}
\ No newline at end of file
+
+    fn last_times(&self, symbol: &str) -> Option<DataTimes> {
+        self.freshness.times(symbol)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<OrderBookUpdate> {
+        self.updates.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use rust_decimal::Decimal;
+    use crate::domain::entities::order_book::OrderBookEntry;
+    use crate::domain::services::clock::ReplayClock;
+
+    fn depth(symbol: &str, bid: Decimal, ask: Decimal) -> BookSnapshot {
+        BookSnapshot {
+            symbol: symbol.to_string(),
+            bids: vec![OrderBookEntry { price: bid, qty: dec!(1.0) }],
+            asks: vec![OrderBookEntry { price: ask, qty: dec!(2.0) }],
+            update_id: 1,
+            event_time: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_order_books_are_kept_per_symbol() {
+        let service = OrderBookService::new();
+
+        service.apply_snapshot(&depth("booktest1", dec!(100.0), dec!(101.0))).await;
+        service.apply_snapshot(&depth("BOOKTEST2", dec!(200.0), dec!(201.0))).await;
+
+        let top1 = service.get_top_of_book("BOOKTEST1").await.unwrap();
+        let top2 = service.get_top_of_book("booktest2").await.unwrap();
+        assert_eq!(top1.best_bid.price, dec!(100.0));
+        assert_eq!(top2.best_ask.price, dec!(201.0));
+        assert!(service.get_full_book("UNKNOWN").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_updates_are_published_to_subscribers() {
+        let service = OrderBookService::new();
+        let mut updates = service.subscribe();
+
+        service.apply_snapshot(&depth("pubbooktest", dec!(50.0), dec!(51.0))).await;
+
+        let update = updates.recv().await.unwrap();
+        assert_eq!(update.symbol, "PUBBOOKTEST");
+        assert_eq!(update.top.unwrap().best_ask.price, dec!(51.0));
+    }
+
+    #[tokio::test]
+    async fn test_diff_events_carry_their_event_time() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = OrderBookService::with_clock(clock.clone()).with_max_age(Duration::from_secs(1));
+        assert_eq!(service.data_times("DIFFTIMETEST"), Err(Staleness::NoData));
+
+        clock.set(5_000);
+        service.apply_snapshot(&BookSnapshot { symbol: "DIFFTIMETEST".to_string(), bids: vec![], asks: vec![], update_id: 10, event_time: None }).await;
+        assert_eq!(service.data_times("DIFFTIMETEST"), Ok(DataTimes { event_time: None, received_at: 5_000 }));
+
+        clock.set(5_500);
+        let delta = BookDelta {
+            symbol: "DIFFTIMETEST".to_string(),
+            first_update_id: 11,
+            final_update_id: 11,
+            bids: vec![OrderBookEntry { price: dec!(100), qty: dec!(1) }],
+            asks: vec![],
+            event_time: Some(5_490),
+        };
+        service.apply_delta(&delta).await.unwrap();
+        assert_eq!(service.data_times("DIFFTIMETEST"), Ok(DataTimes { event_time: Some(5_490), received_at: 5_500 }));
+
+        clock.set(6_501);
+        assert!(matches!(service.data_times("DIFFTIMETEST"), Err(Staleness::Stale { .. })));
+        assert_eq!(service.last_times("DIFFTIMETEST").map(|times| times.received_at), Some(5_500));
+    }
+
+    #[tokio::test]
+    async fn test_cleared_book_is_not_served_until_the_next_snapshot() {
+        let service = OrderBookService::new();
+        service.apply_snapshot(&depth("CLEARTEST", dec!(100.0), dec!(101.0))).await;
+
+        service.clear_book("cleartest").await;
+        assert!(service.get_top_of_book("CLEARTEST").await.is_none());
+        assert_eq!(service.data_times("CLEARTEST"), Err(Staleness::NoData));
+        let delta = BookDelta { symbol: "CLEARTEST".to_string(), first_update_id: 2, final_update_id: 2, bids: vec![], asks: vec![], event_time: None };
+        assert_eq!(service.apply_delta(&delta).await, Err(DepthSyncError::NoSnapshot));
+    }
+}
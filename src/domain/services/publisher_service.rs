@@ -0,0 +1,215 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use rust_decimal::Decimal;
+use tokio::sync::{broadcast, Mutex};
+use crate::domain::entities::book_ticker::BookTickerData;
+use crate::domain::entities::publication::{PriceSource, Publication, Publisher, PublisherPolicy};
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::book_ticker_service::BookTickerServiceTrait;
+use crate::domain::services::clock::Clock;
+use crate::domain::services::trade_history_service::{TradeHistoryService, DEFAULT_WINDOW_SECS};
+use crate::domain::services::{DEFAULT_MAX_DATA_AGE, UPDATE_CHANNEL_CAPACITY};
+
+// Publications kept in the audit log per trading pair, older ones are dropped
+pub const PUBLICATION_LOG_CAPACITY: usize = 1_000;
+// Publications returned when a request does not set a limit
+pub const DEFAULT_PUBLICATION_LIMIT: usize = 100;
+
+// Publisher of one trading pair with the audit log of its publications, oldest first
+#[derive(Debug)]
+struct PublisherState {
+    publisher: Publisher,
+    log: VecDeque<Publication>,
+}
+
+// Publishes the price of the configured trading pairs only when it moved more than the deviation threshold
+// or when the heartbeat interval passed, instead of on every update
+#[derive(Debug)]
+pub struct PublisherService {
+    publishers: Mutex<HashMap<String, PublisherState>>,
+    updates: broadcast::Sender<Publication>,
+    clock: Arc<dyn Clock>,
+    vwap_window_secs: u64,
+    max_age: Duration,
+}
+
+impl PublisherService {
+    // Service timestamping its publications with the given clock (e.g. the replay clock)
+    pub fn with_clock(policies: &[PublisherPolicy], clock: Arc<dyn Clock>) -> Self {
+        let publishers = policies.iter()
+            .map(|policy| (policy.symbol.clone(), PublisherState {
+                publisher: Publisher::new(policy.clone()),
+                log: VecDeque::new(),
+            }))
+            .collect();
+        PublisherService {
+            publishers: Mutex::new(publishers),
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            clock,
+            vwap_window_secs: DEFAULT_WINDOW_SECS,
+            max_age: DEFAULT_MAX_DATA_AGE,
+        }
+    }
+
+    // Window of the VWAP followed by publishers with the vwap source
+    pub fn with_vwap_window(mut self, window_secs: u64) -> Self {
+        self.vwap_window_secs = window_secs;
+        self
+    }
+
+    // Prices observed longer than `max_age` ago are not republished on a heartbeat
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub async fn policy(&self, symbol: &str) -> Option<PublisherPolicy> {
+        let publishers = self.publishers.lock().await;
+        publishers.get(&normalize_symbol(symbol)).map(|state| state.publisher.policy().clone())
+    }
+
+    // Last published price, `None` when the pair has no publisher or nothing was published yet
+    pub async fn latest(&self, symbol: &str) -> Option<Publication> {
+        let publishers = self.publishers.lock().await;
+        publishers.get(&normalize_symbol(symbol))?.publisher.last().cloned()
+    }
+
+    // The most recent `limit` publications, oldest first, `None` when the pair has no publisher
+    pub async fn publication_log(&self, symbol: &str, limit: usize) -> Option<Vec<Publication>> {
+        let publishers = self.publishers.lock().await;
+        let log = &publishers.get(&normalize_symbol(symbol))?.log;
+        Some(log.iter().skip(log.len().saturating_sub(limit)).cloned().collect())
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Publication> {
+        self.updates.subscribe()
+    }
+
+    // Observe a new price of a trading pair, ignored unless its publisher follows `source`
+    pub async fn observe(&self, symbol: &str, source: PriceSource, price: Decimal) {
+        let now = self.clock.now_millis();
+        let mut publishers = self.publishers.lock().await;
+        let Some(state) = publishers.get_mut(&normalize_symbol(symbol)) else { return };
+        if state.publisher.policy().source != source {
+            return;
+        }
+        if let Some(publication) = state.publisher.observe(price, now) {
+            self.record(state, publication);
+        }
+    }
+
+    // Observe the mid and the microprice of a ticker update
+    pub async fn observe_ticker(&self, ticker: &BookTickerData) {
+        self.observe(&ticker.symbol, PriceSource::Mid, ticker.mid_price()).await;
+        self.observe(&ticker.symbol, PriceSource::Microprice, ticker.microprice()).await;
+    }
+
+    // Republish the prices whose heartbeat interval passed, called periodically so quiet pairs are published too.
+    // Pairs without a fresh price (stale data or a dead feed) are not republished.
+    pub async fn heartbeat(&self) {
+        let now = self.clock.now_millis();
+        let mut publishers = self.publishers.lock().await;
+        for state in publishers.values_mut() {
+            if let Some(publication) = state.publisher.heartbeat(now, self.max_age) {
+                self.record(state, publication);
+            }
+        }
+    }
+
+    // Follow the ticker and trade updates and check the heartbeats every second, runs until the services are dropped
+    pub async fn run(&self, book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>, trade_history_service: Arc<TradeHistoryService>) {
+        let mut tickers = book_ticker_service.subscribe();
+        let mut trades = trade_history_service.subscribe();
+        let mut ticks = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            // A lagging receiver skips to the newest updates, only the latest price matters
+            tokio::select! {
+                ticker = tickers.recv() => match ticker {
+                    Ok(ticker) => self.observe_ticker(&ticker).await,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                trade = trades.recv() => match trade {
+                    Ok(trade) => {
+                        if let Some(vwap) = trade_history_service.vwap(&trade.symbol, self.vwap_window_secs).await {
+                            self.observe(&trade.symbol, PriceSource::Vwap, vwap.vwap).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = ticks.tick() => self.heartbeat().await,
+            }
+        }
+    }
+
+    // Append the publication to the audit log and hand it to the subscribers
+    fn record(&self, state: &mut PublisherState, publication: Publication) {
+        log::info!("Published {} {} {} ({}, {} bps, #{})", publication.symbol, publication.source, publication.price,
+            publication.reason, publication.deviation_bps.round_dp(2), publication.sequence);
+        if state.log.len() == PUBLICATION_LOG_CAPACITY {
+            state.log.pop_front();
+        }
+        state.log.push_back(publication.clone());
+
+        // No subscribers is not an error
+        let _ = self.updates.send(publication);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use crate::domain::entities::publication::PublicationReason;
+    use crate::domain::services::clock::ReplayClock;
+
+    fn ticker(symbol: &str, bid: Decimal, ask: Decimal) -> BookTickerData {
+        BookTickerData {
+            update_id: 1,
+            symbol: symbol.to_string(),
+            best_bid_price: bid,
+            best_bid_qty: dec!(1),
+            best_ask_price: ask,
+            best_ask_qty: dec!(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publications_are_logged_and_published() {
+        let clock = Arc::new(ReplayClock::default());
+        let policies = ["PUBTEST:mid:10:60".parse().unwrap(), "OTHERTEST:vwap:10:60".parse().unwrap()];
+        let service = PublisherService::with_clock(&policies, clock.clone()).with_max_age(Duration::from_secs(90));
+        let mut updates = service.subscribe();
+
+        clock.set(1_000);
+        service.observe_ticker(&ticker("pubtest", dec!(99), dec!(101))).await;
+        clock.set(2_000);
+        service.observe_ticker(&ticker("PUBTEST", dec!(99.01), dec!(101.01))).await; // 1 bps
+        clock.set(3_000);
+        service.observe_ticker(&ticker("PUBTEST", dec!(100), dec!(102))).await; // 100 bps
+        // Ticker prices of a pair publishing the VWAP are ignored
+        service.observe_ticker(&ticker("OTHERTEST", dec!(1), dec!(2))).await;
+
+        let latest = service.latest("pubtest").await.unwrap();
+        assert_eq!((latest.sequence, latest.price, latest.reason), (2, dec!(101), PublicationReason::Deviation));
+        assert_eq!(updates.recv().await.unwrap().reason, PublicationReason::Initial);
+        assert_eq!(updates.recv().await.unwrap(), latest);
+        assert!(service.latest("OTHERTEST").await.is_none());
+        assert!(service.latest("UNKNOWN").await.is_none());
+
+        clock.set(63_000);
+        service.heartbeat().await;
+        let log = service.publication_log("PUBTEST", 2).await.unwrap();
+        assert_eq!(log.iter().map(|p| p.sequence).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(log[1].reason, PublicationReason::Heartbeat);
+        assert_eq!(service.publication_log("OTHERTEST", 10).await, Some(vec![]));
+        assert!(service.publication_log("UNKNOWN", 10).await.is_none());
+
+        // The last price is older than the maximum age by the next heartbeat, nothing is republished
+        clock.set(123_001);
+        service.heartbeat().await;
+        assert_eq!(service.latest("PUBTEST").await.unwrap().sequence, 3);
+    }
+}
@@ -1,157 +1,509 @@
-use std::collections::VecDeque;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use once_cell::sync::Lazy;
-use tokio::sync::Mutex;
-use crate::domain::entities::trade::{TradeData, TradeSD};
+use std::time::Duration;
+use rust_decimal::Decimal;
+use tokio::sync::{broadcast, Mutex};
+use crate::domain::entities::candle::{Candle, CandleInterval, CandleSeries};
+use crate::domain::entities::freshness::{DataTimes, Staleness};
+use crate::domain::entities::reference_price::{volume_weighted, Vwap};
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::domain::services::clock::{Clock, SystemClock};
+use crate::domain::services::freshness::FreshnessTracker;
+use crate::domain::entities::market_event::TradeEvent;
+use crate::domain::entities::trade::{AggressorFlow, CumulativeVolumeDelta, TradeData};
+use crate::domain::entities::trade_window::TradeWindows;
+use crate::domain::services::{DEFAULT_RETENTION, UPDATE_CHANNEL_CAPACITY};
 
-// Static singleton for global trade history storage
-pub static TRADE_HISTORY: Lazy<Arc<Mutex<VecDeque<TradeData>>>> = Lazy::new(|| {
-    Arc::new(Mutex::new(VecDeque::new()))
-});
+// Closed candles kept per trading pair and interval
+pub const MAX_CLOSED_CANDLES: usize = 1_000;
+// Candles returned when a request does not set a limit
+pub const DEFAULT_CANDLE_LIMIT: usize = 100;
+// Window of the trade statistics unless other windows are configured
+pub const DEFAULT_WINDOW_SECS: u64 = 60;
+// Time after the end of its interval a candle waits for trades before the timer closes it, trades arrive after
+// their trade time
+pub const CANDLE_CLOSE_DELAY_MILLIS: u64 = 1_000;
 
-#[derive(Debug, Clone, Default)]
-pub struct TradeHistoryService;
+// Rolling windows of the trades of one trading pair
+#[derive(Debug)]
+struct TradeWindow {
+    trades: TradeWindows,
+    observed_since: u64, // Clock time of the first trade, nothing was seen before
+    cvd: CumulativeVolumeDelta,
+}
+
+// Rolling windows of trades keyed by trading pair
+type TradeHistories = HashMap<String, TradeWindow>;
+// Candles of every interval keyed by trading pair
+type CandleHistories = HashMap<String, HashMap<CandleInterval, CandleSeries>>;
+
+// Owns the rolling trade windows and the candles built from them per trading pair
+#[derive(Debug)]
+pub struct TradeHistoryService {
+    histories: Mutex<TradeHistories>,
+    candles: Mutex<CandleHistories>,
+    updates: broadcast::Sender<TradeData>,
+    candle_updates: broadcast::Sender<Candle>,
+    freshness: FreshnessTracker,
+    clock: Arc<dyn Clock>,
+    windows_secs: Vec<u64>, // Windows with running totals
+    retention: Duration,
+}
 
 impl TradeHistoryService {
-    // Add a trade to the rolling window
-    pub async fn add_trade(&self, trade_sd: TradeSD) {
-        let mut trades = TRADE_HISTORY.lock().await;
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    // Service measuring its time windows with the given clock (e.g. the replay clock)
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        TradeHistoryService {
+            histories: Mutex::new(HashMap::new()),
+            candles: Mutex::new(HashMap::new()),
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            candle_updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
+            freshness: FreshnessTracker::new(clock.clone()),
+            clock,
+            windows_secs: vec![DEFAULT_WINDOW_SECS],
+            retention: DEFAULT_RETENTION,
+        }
+    }
+
+    // Keep running totals for these windows (in seconds), queries of other windows add up the time buckets
+    pub fn with_windows(mut self, windows_secs: &[u64]) -> Self {
+        self.windows_secs = windows_secs.to_vec();
+        self
+    }
+
+    // Keep the trades for at least `retention`, the longest VWAP window served
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = self.retention.max(retention);
+        self
+    }
+
+    // Trade statistics of pairs without a trade for longer than `max_age` are not served
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.freshness = self.freshness.with_max_age(max_age);
+        self
+    }
+
+    // Add a trade to the rolling window and the candles of its trading pair
+    pub async fn add_trade(&self, trade: &TradeEvent) {
+        let trade = TradeData::from(trade);
+        let symbol = normalize_symbol(&trade.symbol);
+        self.update_candles(&symbol, &trade).await;
 
         // Get the current time in milliseconds since the UNIX epoch
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
-
-        // Keep trades within a larger window (e.g., 70 seconds)
-        while let Some(oldest_trade) = trades.front() {
-            if current_time - oldest_trade.trade_time > 70_000 {
-                trades.pop_front();
-            } else {
-                break;
+        let current_time = self.clock.now_millis();
+
+        let mut histories = self.histories.lock().await;
+        let window = histories.entry(symbol).or_insert_with(|| TradeWindow {
+            trades: TradeWindows::new(
+                &self.windows_secs.iter().map(|secs| secs * 1_000).collect::<Vec<_>>(),
+                self.retention.as_millis() as u64,
+            ),
+            observed_since: current_time,
+            cvd: CumulativeVolumeDelta { delta: Decimal::ZERO, since: current_time },
+        });
+        window.cvd.delta += if trade.is_buy_initiated() { trade.quantity } else { -trade.quantity };
+
+        // Add the new trade to the rolling windows, trades older than the retention are dropped
+        window.trades.add(&trade, current_time);
+        self.freshness.record(&trade.symbol, Some(trade.event_time));
+
+        // No subscribers is not an error, sending never blocks the ingestion
+        let _ = self.updates.send(trade);
+    }
+
+    // Candles are built from trade times, only `close_candles` closes them by the clock
+    async fn update_candles(&self, symbol: &str, trade: &TradeData) {
+        let mut candles = self.candles.lock().await;
+        let series = candles.entry(symbol.to_string()).or_insert_with(|| {
+            CandleInterval::ALL.into_iter()
+                .map(|interval| (interval, CandleSeries::new(interval, MAX_CLOSED_CANDLES)))
+                .collect()
+        });
+
+        for candle_series in series.values_mut() {
+            for candle in candle_series.add_trade(symbol, trade) {
+                // No subscribers is not an error
+                let _ = self.candle_updates.send(candle);
+            }
+        }
+    }
+
+    // The most recent `limit` candles of the interval, oldest first, the last one may still be open
+    pub async fn candles(&self, symbol: &str, interval: CandleInterval, limit: usize) -> Option<Vec<Candle>> {
+        let candles = self.candles.lock().await;
+        candles.get(&normalize_symbol(symbol))
+            .and_then(|series| series.get(&interval))
+            .map(|series| series.latest(limit))
+    }
+
+    // Close the candles whose interval ended `CANDLE_CLOSE_DELAY_MILLIS` ago, called periodically so quiet pairs do
+    // not keep an open candle until their next trade
+    pub async fn close_candles(&self) {
+        let now = self.clock.now_millis().saturating_sub(CANDLE_CLOSE_DELAY_MILLIS);
+        let mut candles = self.candles.lock().await;
+        for candle_series in candles.values_mut().flat_map(HashMap::values_mut) {
+            if let Some(candle) = candle_series.close_elapsed(now) {
+                // No subscribers is not an error
+                let _ = self.candle_updates.send(candle);
             }
         }
+    }
+
+    // Subscribe to every candle change from now on: the current candle after each trade and every candle that closed
+    pub fn subscribe_candles(&self) -> broadcast::Receiver<Candle> {
+        self.candle_updates.subscribe()
+    }
+
+    // Number of trades currently kept in the rolling window
+    pub async fn history_len(&self, symbol: &str) -> Option<usize> {
+        let histories = self.histories.lock().await;
+        histories.get(&normalize_symbol(symbol)).map(|window| window.trades.trade_count())
+    }
 
-        // Add the new trade to the rolling window
-        trades.push_back(trade_sd.data);
+    // Event and receive time of the last trade, an error when there is none or it is too old to be served
+    pub fn data_times(&self, symbol: &str) -> Result<DataTimes, Staleness> {
+        self.freshness.check(symbol)
     }
 
-    // Calculate the average volume per trade within the last 60 seconds
-    pub async fn average_volume_per_trade(&self) -> f64 {
-        let trades = TRADE_HISTORY.lock().await;
+    // Event and receive time of the last trade even when it is too old to be served
+    pub fn last_times(&self, symbol: &str) -> Option<DataTimes> {
+        self.freshness.times(symbol)
+    }
+
+    // Subscribe to every trade added from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<TradeData> {
+        self.updates.subscribe()
+    }
 
-        let relevant_trades: Vec<&TradeData> = trades.iter()
-            .filter(|trade| self.is_within_last_60_seconds(trade))
-            .collect();
+    // Calculate the average volume per trade within the last `window_secs`
+    pub async fn average_volume_per_trade(&self, symbol: &str, window_secs: u64) -> Option<Decimal> {
+        let flow = self.window_flow(symbol, window_secs).await?;
 
         // Return 0 if there are no relevant trades
-        if relevant_trades.is_empty() {
-            return 0.0;
+        if flow.count() == 0 {
+            return Some(Decimal::ZERO);
         }
+        Some(flow.volume() / Decimal::from(flow.count()))
+    }
 
-        // Sum the volumes of all relevant trades and calculate the average
-        let total_volume: f64 = relevant_trades.iter()
-            .map(|trade| trade.quantity.parse::<f64>().unwrap_or(0.0))
-            .sum();
-        let trade_count = relevant_trades.len() as f64;
+    // Calculate the total volume of all trades within the last `window_secs`
+    pub async fn total_volume(&self, symbol: &str, window_secs: u64) -> Option<Decimal> {
+        Some(self.window_flow(symbol, window_secs).await?.volume())
+    }
 
-        total_volume / trade_count
+    // Volume, count and notional of the trades within the last `window_secs` split by aggressor side
+    pub async fn aggressor_flow(&self, symbol: &str, window_secs: u64) -> Option<AggressorFlow> {
+        self.window_flow(symbol, window_secs).await
     }
 
-    // Calculate the total volume of all trades within the last 60 seconds
-    pub async fn total_volume(&self) -> f64 {
-        let trades = TRADE_HISTORY.lock().await;
+    // Buy minus sell volume of every trade since startup or the last reset
+    pub async fn cumulative_volume_delta(&self, symbol: &str) -> Option<CumulativeVolumeDelta> {
+        let histories = self.histories.lock().await;
+        histories.get(&normalize_symbol(symbol)).map(|window| window.cvd.clone())
+    }
 
-        let relevant_trades: Vec<&TradeData> = trades.iter()
-            .filter(|trade| self.is_within_last_60_seconds(trade))
-            .collect();
+    // Start the cumulative volume delta over from zero, returns the delta before the reset
+    pub async fn reset_cumulative_volume_delta(&self, symbol: &str) -> Option<CumulativeVolumeDelta> {
+        let mut histories = self.histories.lock().await;
+        let window = histories.get_mut(&normalize_symbol(symbol))?;
+        let reset = CumulativeVolumeDelta { delta: Decimal::ZERO, since: self.clock.now_millis() };
+        Some(std::mem::replace(&mut window.cvd, reset))
+    }
 
-        // Sum the volumes of all relevant trades
-        relevant_trades.iter()
-            .map(|trade| trade.quantity.parse::<f64>().unwrap_or(0.0))
-            .sum()
+    // Volume-weighted average price of the trades within the last `window_secs`
+    pub async fn vwap(&self, symbol: &str, window_secs: u64) -> Option<Vwap> {
+        let now = self.clock.now_millis();
+        let mut histories = self.histories.lock().await;
+        let window = histories.get_mut(&normalize_symbol(symbol))?;
+        window.trades.advance(now);
+        let trade_ids = window.trades.trade_id_range(now, window_secs * 1_000)?;
+        volume_weighted(&window.trades.flow(now, window_secs * 1_000), trade_ids, now, window_secs, window.observed_since)
     }
 
-    // Helper function to check if a trade is within the last 60 seconds
-    fn is_within_last_60_seconds(&self, trade: &TradeData) -> bool {
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
-        current_time - trade.trade_time <= 60_000
+    // Flow of the trades within the last `window_secs`, the windows are moved to the current time first
+    async fn window_flow(&self, symbol: &str, window_secs: u64) -> Option<AggressorFlow> {
+        let now = self.clock.now_millis();
+        let mut histories = self.histories.lock().await;
+        let window = histories.get_mut(&normalize_symbol(symbol))?;
+        window.trades.advance(now);
+        Some(window.trades.flow(now, window_secs * 1_000))
+    }
+
+    // Drop the trades older than the retention, called periodically so quiet pairs do not hold on to them
+    pub async fn evict_expired(&self) {
+        let now = self.clock.now_millis();
+        let mut histories = self.histories.lock().await;
+        for window in histories.values_mut() {
+            window.trades.advance(now);
+        }
+    }
+}
+
+impl Default for TradeHistoryService {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::trade::{TradeData, TradeSD};
     use std::time::{SystemTime, UNIX_EPOCH};
     use rand::Rng;
+    use rust_decimal_macros::dec;
+    use crate::domain::services::clock::ReplayClock;
 
     // Helper function to generate a random trade
-    fn generate_trade(event_time_offset: u64, quantity: &str, trade_id: u64) -> TradeSD {
-        TradeSD {
-            stream: "btcusdt@tradeStream".to_string(),
-            data: TradeData {
-                event_type: "trade".to_string(),
-                event_time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64 - event_time_offset,
-                symbol: "BTCUSDT".to_string(),
-                trade_id,
-                price: "50000".to_string(),
-                quantity: quantity.to_string(),
-                trade_time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64 - event_time_offset,
-                is_buyer_market_maker: true,
-                ignore: false,
-            },
+    fn generate_trade(symbol: &str, event_time_offset: u64, quantity: &str, trade_id: u64) -> TradeEvent {
+        TradeEvent {
+            symbol: symbol.to_string(),
+            trade_id,
+            price: dec!(50000),
+            quantity: quantity.parse().unwrap(),
+            trade_time: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64 - event_time_offset,
+            event_time: Some(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64 - event_time_offset),
+            is_buyer_market_maker: true,
         }
     }
 
     #[tokio::test]
     async fn test_large_trade_set_with_corner_case_near_60_seconds() {
-        let service = TradeHistoryService;
+        let service = TradeHistoryService::new();
         let mut rng = rand::thread_rng();
 
         // Generate a set of trades within 60 seconds window and a few outside
         for i in 0..1000 {
             // Event time offsets from 0 ms to 70 seconds
             let event_time_offset = rng.gen_range(0..70_000);
-            let trade = generate_trade(event_time_offset, "100", i as u64);
-            service.add_trade(trade).await;
+            let trade = generate_trade("LARGETEST", event_time_offset, "100", i as u64);
+            service.add_trade(&trade).await;
         }
 
         // Add a corner case trade exactly at 60 seconds
-        let corner_trade = generate_trade(60_000, "200", 1001);
-        service.add_trade(corner_trade).await;
+        let corner_trade = generate_trade("LARGETEST", 60_000, "200", 1001);
+        service.add_trade(&corner_trade).await;
 
         // Calculate total volume (only trades within the last 60 seconds should count)
-        let total_volume = service.total_volume().await;
+        let total_volume = service.total_volume("LARGETEST", 60).await.unwrap();
         log::info!("Total Volume: {}", total_volume);
 
         // Calculate average volume per trade
-        let average_volume = service.average_volume_per_trade().await;
+        let average_volume = service.average_volume_per_trade("LARGETEST", 60).await.unwrap();
         log::info!("Average Volume per Trade: {}", average_volume);
 
         // Assert that total volume and average volume are correctly calculated
-        assert!(total_volume > 0.0);
-        assert!(average_volume > 0.0);
+        assert!(total_volume > Decimal::ZERO);
+        assert!(average_volume > Decimal::ZERO);
 
         // Test that the last trade exactly at 60 seconds is included
-        let last_trade_volume = 200.0;
+        let last_trade_volume = dec!(200);
         assert!(total_volume >= last_trade_volume);
     }
 
     #[tokio::test]
     async fn test_no_trades_after_60_seconds() {
-        let service = TradeHistoryService;
+        let service = TradeHistoryService::new();
 
         // Generate trades that are all older than 60 seconds
         for i in 0..10 {
             let event_time_offset = 70_000; // All trades 70 seconds old
-            let trade = generate_trade(event_time_offset, "100", i as u64);
-            service.add_trade(trade).await;
+            let trade = generate_trade("OLDTEST", event_time_offset, "100", i as u64);
+            service.add_trade(&trade).await;
         }
 
         // Ensure that no trades are counted (all should be ignored)
-        let total_volume = service.total_volume().await;
-        assert_eq!(total_volume, 0.0);
+        let total_volume = service.total_volume("OLDTEST", 60).await;
+        assert_eq!(total_volume, Some(Decimal::ZERO));
+
+        let average_volume = service.average_volume_per_trade("OLDTEST", 60).await;
+        assert_eq!(average_volume, Some(Decimal::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_volumes_are_exact() {
+        let service = TradeHistoryService::new();
+
+        // 0.1 + 0.2 is not 0.3 in binary floating point
+        service.add_trade(&generate_trade("EXACTTEST", 0, "0.1", 1)).await;
+        service.add_trade(&generate_trade("EXACTTEST", 0, "0.2", 2)).await;
+
+        assert_eq!(service.total_volume("EXACTTEST", 60).await, Some(dec!(0.3)));
+        assert_eq!(service.average_volume_per_trade("EXACTTEST", 60).await, Some(dec!(0.15)));
+    }
+
+    #[tokio::test]
+    async fn test_windows_follow_the_injected_clock() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = TradeHistoryService::with_clock(clock.clone()).with_max_age(Duration::from_secs(30));
+        let mut trade = generate_trade("CLOCKTEST", 0, "1.5", 1);
+        trade.trade_time = 1_000_000;
+        trade.event_time = Some(999_990);
+
+        clock.set(1_000_000);
+        service.add_trade(&trade).await;
+        assert_eq!(service.total_volume("CLOCKTEST", 60).await, Some(dec!(1.5)));
+        assert_eq!(service.data_times("CLOCKTEST"), Ok(DataTimes { event_time: Some(999_990), received_at: 1_000_000 }));
+
+        // Sixty seconds later by the clock the trade is still in the window, a moment later it is not
+        clock.set(1_060_000);
+        assert_eq!(service.total_volume("CLOCKTEST", 60).await, Some(dec!(1.5)));
+        clock.set(1_060_001);
+        assert_eq!(service.total_volume("CLOCKTEST", 60).await, Some(Decimal::ZERO));
+        assert!(matches!(service.data_times("CLOCKTEST"), Err(Staleness::Stale { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_several_windows_and_eviction_without_trades() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = TradeHistoryService::with_clock(clock.clone())
+            .with_windows(&[10, 60, 3600])
+            .with_retention(Duration::from_secs(3600));
+
+        for (trade_id, (trade_time, quantity)) in [(400_000, "4"), (1_000_000, "1"), (1_055_000, "2")].into_iter().enumerate() {
+            let mut trade = generate_trade("MULTIWINDOW", 0, quantity, trade_id as u64);
+            trade.trade_time = trade_time;
+            clock.set(trade_time);
+            service.add_trade(&trade).await;
+        }
+
+        assert_eq!(service.total_volume("MULTIWINDOW", 10).await, Some(dec!(2)));
+        assert_eq!(service.total_volume("MULTIWINDOW", 60).await, Some(dec!(3)));
+        assert_eq!(service.total_volume("MULTIWINDOW", 3600).await, Some(dec!(7)));
+        // A window without running totals adds up the buckets
+        assert_eq!(service.average_volume_per_trade("MULTIWINDOW", 300).await, Some(dec!(1.5)));
+
+        // Only the clock moves, the retention of an hour drops every trade
+        clock.set(4_700_000);
+        service.evict_expired().await;
+        assert_eq!(service.history_len("MULTIWINDOW").await, Some(0));
+        assert_eq!(service.total_volume("MULTIWINDOW", 3600).await, Some(Decimal::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_vwap_covers_the_retained_window() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = TradeHistoryService::with_clock(clock.clone()).with_retention(Duration::from_secs(300));
+
+        clock.set(1_000_000);
+        let mut first = generate_trade("VWAPTEST", 0, "1", 1);
+        first.trade_time = 1_000_000;
+        service.add_trade(&first).await;
+
+        // Four minutes later the first trade is still retained for the five minute window
+        clock.set(1_240_000);
+        let mut second = generate_trade("VWAPTEST", 0, "3", 2);
+        second.trade_time = 1_240_000;
+        second.price = dec!(50100);
+        service.add_trade(&second).await;
+
+        let vwap = service.vwap("VWAPTEST", 300).await.unwrap();
+        assert_eq!(vwap.vwap, dec!(50075));
+        assert_eq!(vwap.volume, dec!(4));
+        assert_eq!(vwap.sample_count, 2);
+        assert_eq!(vwap.coverage, 0.8);
+        assert_eq!(vwap.trade_ids, (1, 2));
 
-        let average_volume = service.average_volume_per_trade().await;
-        assert_eq!(average_volume, 0.0);
+        let last_minute = service.vwap("VWAPTEST", 60).await.unwrap();
+        assert_eq!((last_minute.vwap, last_minute.sample_count, last_minute.coverage), (dec!(50100), 1, 1.0));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_aggressor_flow_and_cumulative_volume_delta() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = TradeHistoryService::with_clock(clock.clone());
+        clock.set(1_000_000);
+
+        let mut old_buy = generate_trade("FLOWTEST", 0, "5", 1);
+        old_buy.trade_time = 900_000;
+        old_buy.is_buyer_market_maker = false;
+        let mut buy = generate_trade("FLOWTEST", 0, "2", 2);
+        buy.trade_time = 990_000;
+        buy.is_buyer_market_maker = false;
+        let mut sell = generate_trade("FLOWTEST", 0, "0.5", 3);
+        sell.trade_time = 995_000;
+        for trade in [old_buy, buy, sell] {
+            service.add_trade(&trade).await;
+        }
+
+        // The window only holds the last minute, the delta counts every trade
+        let flow = service.aggressor_flow("FLOWTEST", 60).await.unwrap();
+        assert_eq!((flow.buy_volume, flow.buy_count), (dec!(2), 1));
+        assert_eq!((flow.sell_volume, flow.sell_count, flow.sell_notional), (dec!(0.5), 1, dec!(25000)));
+        assert_eq!(flow.imbalance(), Some(dec!(0.6)));
+        let cvd = service.cumulative_volume_delta("FLOWTEST").await.unwrap();
+        assert_eq!((cvd.delta, cvd.since), (dec!(6.5), 1_000_000));
+
+        clock.set(1_001_000);
+        assert_eq!(service.reset_cumulative_volume_delta("FLOWTEST").await.unwrap().delta, dec!(6.5));
+        let mut after_reset = generate_trade("FLOWTEST", 0, "1", 4);
+        after_reset.trade_time = 1_001_000;
+        service.add_trade(&after_reset).await;
+        let cvd = service.cumulative_volume_delta("FLOWTEST").await.unwrap();
+        assert_eq!((cvd.delta, cvd.since), (dec!(-1), 1_001_000));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_symbol_has_no_history() {
+        let service = TradeHistoryService::new();
+
+        assert!(service.total_volume("NEVERTRADED", 60).await.is_none());
+        assert!(service.average_volume_per_trade("NEVERTRADED", 60).await.is_none());
+        assert!(service.vwap("NEVERTRADED", 60).await.is_none());
+        assert!(service.aggressor_flow("NEVERTRADED", 60).await.is_none());
+        assert!(service.reset_cumulative_volume_delta("NEVERTRADED").await.is_none());
+        assert!(service.candles("NEVERTRADED", CandleInterval::OneMinute, 10).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_trades_build_candles_of_every_interval() {
+        let service = TradeHistoryService::new();
+        let mut updates = service.subscribe_candles();
+        let mut first = generate_trade("candletest", 0, "1", 1);
+        first.trade_time = 3_600_000;
+        let mut second = generate_trade("candletest", 0, "2", 2);
+        second.trade_time = 3_601_000;
+        second.price = dec!(50100);
+
+        service.add_trade(&first).await;
+        service.add_trade(&second).await;
+
+        let seconds = service.candles("CANDLETEST", CandleInterval::OneSecond, 10).await.unwrap();
+        assert_eq!(seconds.len(), 2);
+        assert!(seconds[0].closed);
+        let hour = service.candles("CANDLETEST", CandleInterval::OneHour, 10).await.unwrap();
+        assert_eq!(hour.len(), 1);
+        assert_eq!((hour[0].open, hour[0].close, hour[0].volume), (dec!(50000), dec!(50100), dec!(3)));
+
+        // One update per interval for the first trade
+        let first_updates: Vec<Candle> = (0..CandleInterval::ALL.len()).map(|_| updates.try_recv().unwrap()).collect();
+        assert!(first_updates.iter().all(|candle| candle.symbol == "CANDLETEST" && candle.trade_count == 1));
+    }
+
+    #[tokio::test]
+    async fn test_candles_of_a_quiet_pair_close_by_the_clock() {
+        let clock = Arc::new(ReplayClock::default());
+        let service = TradeHistoryService::with_clock(clock.clone());
+        let mut trade = generate_trade("quietcandles", 0, "1", 1);
+        trade.trade_time = 60_500;
+        service.add_trade(&trade).await;
+        let mut updates = service.subscribe_candles();
+
+        // The one second candle waits for late trades until the delay after its interval is over
+        clock.set(61_000 + CANDLE_CLOSE_DELAY_MILLIS - 1);
+        service.close_candles().await;
+        assert!(updates.try_recv().is_err());
+        clock.set(61_000 + CANDLE_CLOSE_DELAY_MILLIS);
+        service.close_candles().await;
+        let closed = updates.try_recv().unwrap();
+        assert_eq!((closed.interval, closed.open_time, closed.closed), (CandleInterval::OneSecond, 60_000, true));
+        assert!(updates.try_recv().is_err());
+
+        let minutes = service.candles("QUIETCANDLES", CandleInterval::OneMinute, 10).await.unwrap();
+        assert!(!minutes[0].closed);
+        assert!(service.candles("QUIETCANDLES", CandleInterval::OneSecond, 10).await.unwrap()[0].closed);
+    }
+}
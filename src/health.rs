@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use crate::domain::entities::symbol::normalize_symbol;
+
+// Lifecycle of a feed as driven by the feed supervisor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FeedTaskState {
+    // Opening the connection and subscribing
+    Connecting,
+    // Connected, updates are applied (also while replaying recordings)
+    Live,
+    // Waiting before the next connection attempt after a failure
+    BackingOff,
+    // Given up after too many failed attempts, its data will not be updated anymore
+    Dead,
+}
+
+#[derive(Debug)]
+struct FeedState {
+    task: FeedTaskState,
+    since: Instant,
+    retry_at: Option<Instant>,
+    failures: u32,
+    last_error: Option<String>,
+    last_updates: HashMap<String, Instant>,
+    symbols: Vec<String>, // Trading pairs the feed has to keep fresh to be ready
+    secondary: bool,
+}
+
+impl FeedState {
+    fn new(task: FeedTaskState) -> Self {
+        FeedState {
+            task,
+            since: Instant::now(),
+            retry_at: None,
+            failures: 0,
+            last_error: None,
+            last_updates: HashMap::new(),
+            symbols: Vec::new(),
+            secondary: false,
+        }
+    }
+
+    fn enter(&mut self, task: FeedTaskState) {
+        if self.task != task {
+            self.task = task;
+            self.since = Instant::now();
+        }
+        self.retry_at = None;
+    }
+}
+
+// Health of a single feed reported by /readyz, /feeds and grpc.health.v1
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedStatus {
+    pub feed: String,
+    pub state: FeedTaskState,
+    pub seconds_in_state: f64,
+    pub next_attempt_in_secs: Option<f64>,
+    pub failures: u32,
+    pub last_error: Option<String>,
+    pub seconds_since_last_update: Option<f64>,
+    pub stale_symbols: Vec<String>,
+    pub ready: bool,
+    pub secondary: bool, // Reported but not part of the overall readiness
+}
+
+// Feed readiness threshold unless another one is configured
+pub const DEFAULT_MAX_STALENESS: Duration = Duration::from_secs(30);
+
+// Lifecycle and last updates of the feeds of the running application keyed by stream name, shared by the sources,
+// the feed supervisor and the health adapters
+#[derive(Debug)]
+pub struct HealthRegistry {
+    feeds: Mutex<HashMap<String, FeedState>>,
+    max_staleness: Duration,
+    feed_max_staleness: HashMap<String, Duration>, // Thresholds of feeds whose pairs update less often
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        HealthRegistry {
+            feeds: Mutex::new(HashMap::new()),
+            max_staleness: DEFAULT_MAX_STALENESS,
+            feed_max_staleness: HashMap::new(),
+        }
+    }
+
+    // Feeds are only ready while their trading pairs were updated within `max_staleness`
+    pub fn with_max_staleness(mut self, max_staleness: Duration) -> Self {
+        self.max_staleness = max_staleness;
+        self
+    }
+
+    // Threshold of a single feed, e.g. the trades of a quiet pair are much rarer than its book updates
+    pub fn with_feed_max_staleness(mut self, feed: &str, max_staleness: Duration) -> Self {
+        self.feed_max_staleness.insert(feed.to_string(), max_staleness);
+        self
+    }
+
+    // Move a feed to the given lifecycle state, registering it when needed. A live feed has no failures anymore.
+    pub fn set_state(&self, feed: &str, task: FeedTaskState) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let state = feeds.entry(feed.to_string()).or_insert_with(|| FeedState::new(task));
+        state.enter(task);
+        if task == FeedTaskState::Live {
+            state.failures = 0;
+        }
+    }
+
+    // Remember that a connection attempt of a feed failed, `failures` is the number of consecutive failed attempts
+    pub fn record_failure(&self, feed: &str, failures: u32, error: &str) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let state = feeds.entry(feed.to_string()).or_insert_with(|| FeedState::new(FeedTaskState::Connecting));
+        state.failures = failures;
+        state.last_error = Some(error.to_string());
+    }
+
+    // A feed waits `delay` before its next connection attempt
+    pub fn back_off(&self, feed: &str, delay: Duration) {
+        let mut feeds = self.feeds.lock().unwrap();
+        let state = feeds.entry(feed.to_string()).or_insert_with(|| FeedState::new(FeedTaskState::BackingOff));
+        state.enter(FeedTaskState::BackingOff);
+        state.retry_at = Some(Instant::now() + delay);
+    }
+
+    // The feed is only ready while every one of the trading pairs has a fresh update
+    pub fn expect_updates(&self, feed: &str, symbols: &[String]) {
+        let mut feeds = self.feeds.lock().unwrap();
+        feeds.entry(feed.to_string())
+            .or_insert_with(|| FeedState::new(FeedTaskState::Connecting))
+            .symbols = symbols.iter().map(|symbol| normalize_symbol(symbol)).collect();
+    }
+
+    // A feed of a secondary venue, e.g. consolidated with the Binance quotes: its outage or quiet periods do not make
+    // the application unready, `consolidation_min_venues` decides how many venues a consolidated price needs instead
+    pub fn set_secondary(&self, feed: &str) {
+        let mut feeds = self.feeds.lock().unwrap();
+        feeds.entry(feed.to_string()).or_insert_with(|| FeedState::new(FeedTaskState::Connecting)).secondary = true;
+    }
+
+    // Remember that an update for `symbol` has just been applied from `feed`
+    pub fn record_update(&self, feed: &str, symbol: &str) {
+        let mut feeds = self.feeds.lock().unwrap();
+        feeds.entry(feed.to_string())
+            .or_insert_with(|| FeedState::new(FeedTaskState::Live))
+            .last_updates
+            .insert(symbol.to_string(), Instant::now());
+    }
+
+    // Time since the last update per (feed, symbol)
+    pub fn update_ages(&self) -> Vec<(String, String, Duration)> {
+        let feeds = self.feeds.lock().unwrap();
+        feeds.iter()
+            .flat_map(|(feed, state)| state.last_updates.iter()
+                .map(move |(symbol, updated_at)| (feed.clone(), symbol.clone(), updated_at.elapsed())))
+            .collect()
+    }
+
+    // Status of every registered feed. A feed is ready while it is live and every one of its trading pairs
+    // (the updated ones when it expects none) has been updated within the maximum staleness of the feed.
+    // `seconds_since_last_update` is the age of the oldest of them, `None` while one has never been updated.
+    pub fn feed_statuses(&self) -> Vec<FeedStatus> {
+        let feeds = self.feeds.lock().unwrap();
+        let mut statuses: Vec<FeedStatus> = feeds.iter()
+            .map(|(feed, state)| {
+                let max_staleness = self.feed_max_staleness.get(feed).copied().unwrap_or(self.max_staleness);
+                let ages: Vec<(&String, Option<Duration>)> = if state.symbols.is_empty() {
+                    state.last_updates.iter().map(|(symbol, updated_at)| (symbol, Some(updated_at.elapsed()))).collect()
+                } else {
+                    state.symbols.iter().map(|symbol| (symbol, state.last_updates.get(symbol).map(Instant::elapsed))).collect()
+                };
+                let mut stale_symbols: Vec<String> = ages.iter()
+                    .filter(|(_, age)| !age.is_some_and(|age| age <= max_staleness))
+                    .map(|(symbol, _)| symbol.to_string())
+                    .collect();
+                stale_symbols.sort();
+                let since_last_update = ages.iter().map(|(_, age)| *age).collect::<Option<Vec<_>>>()
+                    .and_then(|ages| ages.into_iter().max());
+                FeedStatus {
+                    feed: feed.clone(),
+                    state: state.task,
+                    seconds_in_state: state.since.elapsed().as_secs_f64(),
+                    next_attempt_in_secs: state.retry_at
+                        .map(|retry_at| retry_at.saturating_duration_since(Instant::now()).as_secs_f64()),
+                    failures: state.failures,
+                    last_error: state.last_error.clone(),
+                    seconds_since_last_update: since_last_update.map(|age| age.as_secs_f64()),
+                    ready: state.task == FeedTaskState::Live && !ages.is_empty() && stale_symbols.is_empty(),
+                    stale_symbols,
+                    secondary: state.secondary,
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.feed.cmp(&b.feed));
+        statuses
+    }
+
+    // Status of a single feed, `None` when it is not registered
+    pub fn feed_status(&self, feed: &str) -> Option<FeedStatus> {
+        self.feed_statuses().into_iter().find(|status| status.feed == feed)
+    }
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Ready when there is at least one feed that is not secondary and all of those are ready
+pub fn is_ready(statuses: &[FeedStatus]) -> bool {
+    let mut primary = statuses.iter().filter(|status| !status.secondary).peekable();
+    primary.peek().is_some() && primary.all(|status| status.ready)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> HealthRegistry {
+        HealthRegistry::new().with_max_staleness(Duration::from_secs(10))
+    }
+
+    // Pretend the last update of `symbol` was applied `age` ago
+    fn age_update(health: &HealthRegistry, feed: &str, symbol: &str, age: Duration) {
+        health.feeds.lock().unwrap().get_mut(feed).unwrap().last_updates.insert(symbol.to_string(), Instant::now() - age);
+    }
+
+    #[test]
+    fn test_feed_is_ready_only_after_fresh_update() {
+        let health = registry();
+        health.set_state("book_ticker", FeedTaskState::Live);
+        assert!(!health.feed_status("book_ticker").unwrap().ready);
+
+        health.record_update("book_ticker", "BTCFDUSD");
+        assert!(health.feed_status("book_ticker").unwrap().ready);
+        age_update(&health, "book_ticker", "BTCFDUSD", Duration::from_secs(11));
+        assert!(!health.feed_status("book_ticker").unwrap().ready);
+    }
+
+    #[test]
+    fn test_feed_is_ready_only_while_every_pair_is_fresh() {
+        let health = registry();
+        health.set_state("order_book", FeedTaskState::Live);
+        health.expect_updates("order_book", &["btcfdusd".to_string(), "ETHFDUSD".to_string()]);
+
+        // A pair that was never updated keeps the feed unready, however fresh the other one is
+        health.record_update("order_book", "BTCFDUSD");
+        let status = health.feed_status("order_book").unwrap();
+        assert_eq!((status.ready, status.seconds_since_last_update), (false, None));
+        assert_eq!(status.stale_symbols, vec!["ETHFDUSD".to_string()]);
+
+        health.record_update("order_book", "ETHFDUSD");
+        assert!(health.feed_status("order_book").unwrap().ready);
+
+        // ETHFDUSD stays fresh while BTCFDUSD goes stale
+        age_update(&health, "order_book", "BTCFDUSD", Duration::from_secs(30));
+        let status = health.feed_status("order_book").unwrap();
+        assert_eq!((status.ready, status.stale_symbols), (false, vec!["BTCFDUSD".to_string()]));
+        assert!(status.seconds_since_last_update.is_some_and(|secs| secs >= 30.0));
+    }
+
+    #[test]
+    fn test_feed_threshold_overrides_the_default() {
+        let health = registry().with_feed_max_staleness("trade", Duration::from_secs(300));
+        for feed in ["book_ticker", "trade"] {
+            health.set_state(feed, FeedTaskState::Live);
+            health.record_update(feed, "BTCFDUSD");
+            age_update(&health, feed, "BTCFDUSD", Duration::from_secs(60));
+        }
+
+        // A pair without a trade for a minute is still fresh, its book ticker is not
+        let statuses = health.feed_statuses();
+        assert_eq!((statuses[0].feed.as_str(), statuses[0].ready), ("book_ticker", false));
+        assert_eq!((statuses[1].feed.as_str(), statuses[1].ready), ("trade", true));
+        age_update(&health, "trade", "BTCFDUSD", Duration::from_secs(301));
+        assert_eq!(health.feed_status("trade").unwrap().stale_symbols, vec!["BTCFDUSD".to_string()]);
+    }
+
+    #[test]
+    fn test_secondary_feed_is_left_out_of_the_readiness() {
+        let health = registry();
+        health.set_state("book_ticker", FeedTaskState::Live);
+        health.record_update("book_ticker", "BTCFDUSD");
+        health.set_secondary("coinbase_ticker");
+        health.set_state("coinbase_ticker", FeedTaskState::Dead);
+
+        let statuses = health.feed_statuses();
+        assert_eq!(statuses.iter().map(|status| status.feed.as_str()).collect::<Vec<_>>(), vec!["book_ticker", "coinbase_ticker"]);
+        assert_eq!((statuses[1].ready, statuses[1].secondary), (false, true));
+        assert!(is_ready(&statuses));
+        assert!(!is_ready(&statuses[1..]));
+    }
+
+    #[test]
+    fn test_feed_is_not_ready_while_reconnecting_or_dead() {
+        let health = registry();
+        health.set_state("trade", FeedTaskState::Live);
+        health.record_update("trade", "BTCFDUSD");
+
+        health.record_failure("trade", 1, "connection refused");
+        health.back_off("trade", Duration::from_secs(60));
+        let status = health.feed_status("trade").unwrap();
+        assert_eq!((status.state, status.failures, status.ready), (FeedTaskState::BackingOff, 1, false));
+        assert!(status.next_attempt_in_secs.is_some_and(|secs| secs > 50.0));
+        assert_eq!(status.last_error.as_deref(), Some("connection refused"));
+
+        health.set_state("trade", FeedTaskState::Dead);
+        let status = health.feed_status("trade").unwrap();
+        assert_eq!((status.state, status.next_attempt_in_secs), (FeedTaskState::Dead, None));
+        assert!(!is_ready(&[status]));
+
+        // A new connection clears the failures, the last error is kept for diagnosis
+        health.set_state("trade", FeedTaskState::Live);
+        let status = health.feed_status("trade").unwrap();
+        assert_eq!((status.failures, status.ready), (0, true));
+        assert!(status.last_error.is_some());
+        assert!(health.feed_status("order_book").is_none());
+    }
+}
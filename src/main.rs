@@ -2,21 +2,51 @@ mod domain;
 mod adapters;
 mod ports;
 mod config;
+mod metrics;
+mod health;
+mod attestation;
+mod shutdown;
 
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 use tonic::transport::Server;
 use warp::Filter;
+use crate::adapters::proto::attestation_proto_service;
 use crate::adapters::proto::book_ticker_proto_service;
+use crate::adapters::proto::consolidated_proto_service;
 use crate::adapters::proto::order_book_proto_service;
+use crate::adapters::proto::publisher_proto_service;
 use crate::adapters::proto::trade_history_proto_service;
-use crate::ports::{ws_client_order_book, ws_client_trade};
-use crate::ports::ws_client_book_ticker;
+use crate::ports::combined_stream::CombinedStream;
+use crate::ports::market_data::{MarketDataServices, MarketDataSource, VenueSink};
+use crate::ports::ws_client_book_ticker::BookTickerFeed;
+use crate::ports::ws_client_coinbase::CoinbaseSource;
+use crate::ports::ws_client_order_book::OrderBookFeed;
+use crate::ports::ws_client_trade::TradeFeed;
+use crate::ports::replay::{self, ReplayTargets};
+use crate::ports::supervisor::FeedSupervisor;
 use crate::adapters::rest::order_book_api::create_order_book_api;
 use crate::adapters::rest::book_ticker_api::create_book_ticker_rest_api;
 use crate::adapters::rest::trade_history_rest::create_trade_history_rest_api;
-use crate::config::CONFIG;
+use crate::adapters::rest::ws_push_api::create_ws_push_api;
+use crate::adapters::rest::metrics_api::{create_metrics_api, http_metrics};
+use crate::adapters::rest::health_api::create_health_api;
+use crate::adapters::rest::feed_api::create_feed_api;
+use crate::adapters::rest::attestation_api::create_attestation_api;
+use crate::adapters::rest::publisher_api::create_publisher_api;
+use crate::adapters::rest::consolidated_api::create_consolidated_api;
+use crate::adapters::rest::service_error::handle_rejection;
+use crate::adapters::proto::grpc_health::report_feed_health;
+use crate::metrics::{STREAM_BOOK_TICKER, STREAM_ORDER_BOOK, STREAM_TRADE};
+use crate::adapters::proto::grpc_metrics::GrpcMetricsLayer;
+use crate::config::{IngestionMode, CONFIG};
+use crate::health::{FeedTaskState, HealthRegistry};
 use crate::domain::services::book_ticker_service::BookTickerService;
+use crate::domain::services::clock::{Clock, ReplayClock, SystemClock};
+use crate::domain::services::consolidation_service::ConsolidationService;
 use crate::domain::services::order_book_service::OrderBookService;
+use crate::domain::services::publisher_service::PublisherService;
 use crate::domain::services::trade_history_service::TradeHistoryService;
 
 #[tokio::main]
@@ -27,61 +57,191 @@ async fn main() {
     // Log the start of the application
     log::info!("Starting application...");
 
-    let book_ticker_service = Arc::new(BookTickerService);
-    let order_book_service = Arc::new(OrderBookService);
-    let trade_history_service = Arc::new(TradeHistoryService);
-
-    // Start both WebSocket and REST API in the same Tokio runtime using join!
-    let websocket_order_book_handle = tokio::spawn(async {
-        log::info!("Starting OrderBook WebSocket client...");
-        ws_client_order_book::start_websocket().await;
-    });
-
-    // Start both WebSocket and REST API in the same Tokio runtime using join!
-    let websocket_book_ticker_handle = tokio::spawn(async {
-        log::info!("Starting BookTicker WebSocket client...");
-        ws_client_book_ticker::start_websocket().await;
+    // A signing key that cannot be loaded stops the start instead of the first signed response
+    once_cell::sync::Lazy::force(&attestation::SIGNER);
+
+    // Ctrl+C and SIGTERM cancel the shutdown token, the tasks below stop on their own when it is cancelled
+    let shutdown = CancellationToken::new();
+    tokio::spawn(shutdown::cancel_on_signal(shutdown.clone()));
+
+    // The services own the market state, every adapter gets a handle to the same instances
+    // In replay mode the time windows follow the recorded receive times instead of the wall clock
+    let replay_clock = Arc::new(ReplayClock::default());
+    let clock: Arc<dyn Clock> = match CONFIG.default.ingestion_mode {
+        IngestionMode::Live => Arc::new(SystemClock),
+        IngestionMode::Replay => replay_clock.clone(),
+    };
+    // Ticker samples are kept for the longest VWAP/TWAP window, trades also for the longest trade statistics window
+    // Data older than the maximum age is kept but not served
+    let book_ticker_service = Arc::new(BookTickerService::with_clock(clock.clone())
+        .with_retention(CONFIG.default.max_reference_price_window())
+        .with_max_age(Duration::from_secs(CONFIG.default.max_data_age_secs)));
+    let order_book_service = Arc::new(OrderBookService::with_clock(clock.clone())
+        .with_max_age(Duration::from_secs(CONFIG.default.max_data_age_secs)));
+    let trade_history_service = Arc::new(TradeHistoryService::with_clock(clock.clone())
+        .with_windows(&CONFIG.default.trade_stats_windows_secs)
+        .with_retention(CONFIG.default.trade_retention())
+        .with_max_age(Duration::from_secs(CONFIG.default.max_trade_age_secs)));
+    // Quotes of every venue are combined into one price, a venue quoting no fresh data is left out
+    let consolidation_service = Arc::new(ConsolidationService::with_clock(clock.clone())
+        .with_method(CONFIG.default.consolidation_method)
+        .with_min_venues(CONFIG.default.consolidation_min_venues)
+        .with_max_age(Duration::from_secs(CONFIG.default.max_data_age_secs)));
+    // VWAP publishers follow the default reference price window
+    let publisher_service = Arc::new(PublisherService::with_clock(&CONFIG.default.publishers, clock)
+        .with_vwap_window(CONFIG.default.reference_price_window(None).unwrap())
+        .with_max_age(Duration::from_secs(CONFIG.default.max_data_age_secs)));
+
+    // Lifecycle and freshness of the feeds, reported by /readyz, /feeds, /metrics and grpc.health.v1
+    // Pairs trade less often than their book changes, the trade feed is stale after the maximum trade age
+    let health = Arc::new(HealthRegistry::new()
+        .with_max_staleness(Duration::from_secs(CONFIG.default.readiness_max_staleness_secs))
+        .with_feed_max_staleness(STREAM_TRADE, Duration::from_secs(CONFIG.default.max_trade_age_secs)));
+
+    // Expire old trades and close the candles of ended intervals even when a trading pair goes quiet
+    let eviction_trade_history_service = trade_history_service.clone();
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            ticks.tick().await;
+            eviction_trade_history_service.evict_expired().await;
+            eviction_trade_history_service.close_candles().await;
+        }
     });
 
-    let websocket_trade_handle = tokio::spawn( async{
-        log::info!("Starting Trade Stream WebSocket client...");
-        ws_client_trade::start_websocket().await;
+    // Publish the configured prices when they deviate enough or their heartbeat is due
+    let run_publisher_service = publisher_service.clone();
+    let publisher_book_ticker_service = book_ticker_service.clone();
+    let publisher_trade_history_service = trade_history_service.clone();
+    tokio::spawn(async move {
+        run_publisher_service.run(publisher_book_ticker_service, publisher_trade_history_service).await;
     });
 
-
-    let rest_api_handle = tokio::spawn(async {
+    let mut handles = Vec::new();
+    match CONFIG.default.ingestion_mode {
+        IngestionMode::Live => {
+            // One combined stream connection carries all Binance feeds, the supervisor reconnects it with backoff and
+            // decides what happens when it is dead. The source only publishes normalized events to the services.
+            let source = CombinedStream::from_config()
+                .with_feed(Arc::new(OrderBookFeed::new(CONFIG.default.order_book_mode.clone())))
+                .with_feed(Arc::new(BookTickerFeed))
+                .with_feed(Arc::new(TradeFeed));
+            let services = MarketDataServices::new(order_book_service.clone(), book_ticker_service.clone(), trade_history_service.clone());
+            let sink = VenueSink::new(source.name(), consolidation_service.clone()).with_services(Arc::new(services));
+            let supervisor = FeedSupervisor::from_config(shutdown.clone(), health.clone());
+            log::info!("Starting the {} market data source...", source.name());
+            handles.push(tokio::spawn(async move { source.run(&sink, &supervisor).await }));
+
+            // Coinbase only quotes the consolidated price, on its own supervised connection
+            if !CONFIG.default.coinbase_products.is_empty() {
+                let source = CoinbaseSource::from_config();
+                let sink = VenueSink::new(source.name(), consolidation_service.clone());
+                let supervisor = FeedSupervisor::from_config(shutdown.clone(), health.clone());
+                log::info!("Starting the {} market data source...", source.name());
+                handles.push(tokio::spawn(async move { source.run(&sink, &supervisor).await }));
+            }
+        }
+        IngestionMode::Replay => {
+            let targets = ReplayTargets {
+                book_ticker_service: book_ticker_service.clone(),
+                order_book_service: order_book_service.clone(),
+                trade_history_service: trade_history_service.clone(),
+                consolidation_service: consolidation_service.clone(),
+                clock: replay_clock,
+                health: health.clone(),
+            };
+            let replay_shutdown = shutdown.clone();
+            handles.push(tokio::spawn(async move {
+                for feed in [STREAM_ORDER_BOOK, STREAM_BOOK_TICKER, STREAM_TRADE] {
+                    targets.health.set_state(feed, FeedTaskState::Live);
+                    targets.health.expect_updates(feed, &CONFIG.default.trading_pairs);
+                }
+                replay::start_replay(targets, replay_shutdown).await;
+            }));
+        }
+    }
+
+    let rest_book_ticker_service = book_ticker_service.clone();
+    let rest_order_book_service = order_book_service.clone();
+    let rest_trade_history_service = trade_history_service.clone();
+    let rest_publisher_service = publisher_service.clone();
+    let rest_consolidation_service = consolidation_service.clone();
+    let rest_health = health.clone();
+    let rest_shutdown = shutdown.clone();
+    let ws_push_shutdown = shutdown.clone();
+    handles.push(tokio::spawn(async move {
         log::info!("Starting REST API server on port {} ...", CONFIG.default.server_port_http);
-        let api = create_order_book_api()
-            .or(create_book_ticker_rest_api())
-            .or(create_trade_history_rest_api());
-        warp::serve(api).run(([0, 0, 0, 0], CONFIG.default.server_port_http)).await;
-    });
-
-    let grpc_service_handle = tokio::spawn(async move {
+        let api = create_order_book_api(rest_order_book_service.clone())
+            .or(create_book_ticker_rest_api(rest_book_ticker_service.clone()))
+            .or(create_trade_history_rest_api(rest_trade_history_service.clone()))
+            .or(create_ws_push_api(rest_book_ticker_service, rest_order_book_service.clone(), rest_trade_history_service.clone(), ws_push_shutdown))
+            .or(create_metrics_api(rest_order_book_service, rest_trade_history_service, rest_health.clone()))
+            .or(create_health_api(rest_health.clone()))
+            .or(create_feed_api(rest_health))
+            .or(create_attestation_api(attestation::SIGNER.as_ref()))
+            .or(create_publisher_api(rest_publisher_service))
+            .or(create_consolidated_api(rest_consolidation_service))
+            .recover(handle_rejection)
+            .with(http_metrics());
+        // Stops accepting connections on shutdown and waits for the open ones to finish
+        let (_, server) = warp::serve(api)
+            .bind_with_graceful_shutdown(([0, 0, 0, 0], CONFIG.default.server_port_http), rest_shutdown.cancelled_owned());
+        server.await;
+        log::info!("REST API server stopped");
+    }));
+
+    let grpc_shutdown = shutdown.clone();
+    handles.push(tokio::spawn(async move {
         let addr = format!("0.0.0.0:{}", CONFIG.default.server_port_grpc).parse().unwrap();
-        let grpc_book_ticker_service = book_ticker_proto_service::create_book_ticker_service(book_ticker_service.clone());
-        let grpc_order_book_service = order_book_proto_service::create_order_book_service(order_book_service.clone());
-        let grpc_trade_history_service = trade_history_proto_service::create_trade_history_service(trade_history_service.clone());
-
-
+        let grpc_book_ticker_service = book_ticker_proto_service::create_book_ticker_service(book_ticker_service.clone(), grpc_shutdown.clone());
+        let grpc_order_book_service = order_book_proto_service::create_order_book_service(order_book_service.clone(), grpc_shutdown.clone());
+        let grpc_trade_history_service = trade_history_proto_service::create_trade_history_service(trade_history_service.clone(), grpc_shutdown.clone());
+        let grpc_attestation_service = attestation_proto_service::create_attestation_service();
+        let grpc_publisher_service = publisher_proto_service::create_publisher_service(publisher_service, grpc_shutdown.clone());
+        let grpc_consolidated_service = consolidated_proto_service::create_consolidated_service(consolidation_service);
+        let (health_reporter, grpc_health_service) = tonic_health::server::health_reporter();
+        tokio::spawn(report_feed_health(health_reporter, health));
 
         log::info!("Starting gRPC service on {}", addr);
         Server::builder()
+            .layer(GrpcMetricsLayer)
             .add_service(grpc_book_ticker_service)
             .add_service(grpc_order_book_service)
             .add_service(grpc_trade_history_service)
-            .serve(addr)
+            .add_service(grpc_attestation_service)
+            .add_service(grpc_publisher_service)
+            .add_service(grpc_consolidated_service)
+            .add_service(grpc_health_service)
+            .serve_with_shutdown(addr, grpc_shutdown.cancelled_owned())
             .await
             .unwrap();
-    });
-
-    // Wait for both tasks to finish (if they ever finish)
-    tokio::try_join!(
-        websocket_order_book_handle,
-        websocket_book_ticker_handle,
-        websocket_trade_handle,
-        rest_api_handle,
-        grpc_service_handle).unwrap();
-
-
-}
\ No newline at end of file
+        log::info!("gRPC service stopped");
+    }));
+
+    // The servers keep running after a replay, so the tasks only all finish after a shutdown
+    let mut tasks = futures_util::future::try_join_all(handles);
+    tokio::select! {
+        result = &mut tasks => {
+            if let Err(e) = result {
+                log::error!("Task failed: {}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        _ = shutdown.cancelled() => {}
+    }
+
+    // Open connections and streams get the drain timeout to finish, the recorders flush their files meanwhile
+    let drain_timeout = Duration::from_secs(CONFIG.default.shutdown_drain_secs);
+    match tokio::time::timeout(drain_timeout, tasks).await {
+        Ok(Ok(_)) => log::info!("Shutdown complete"),
+        Ok(Err(e)) => {
+            log::error!("Task failed during the shutdown: {}", e);
+            std::process::exit(1);
+        }
+        Err(_) => log::warn!("Tasks still running after the drain timeout of {}s, exiting", drain_timeout.as_secs()),
+    }
+
+    // A dead feed with the exit policy stops the application with a failure
+    std::process::exit(shutdown::exit_code());
+}
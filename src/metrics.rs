@@ -0,0 +1,113 @@
+use once_cell::sync::Lazy;
+use crate::health::HealthRegistry;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, GaugeVec, Opts, Registry, TextEncoder};
+
+// Stream labels used by the WebSocket clients
+pub const STREAM_ORDER_BOOK: &str = "order_book";
+pub const STREAM_BOOK_TICKER: &str = "book_ticker";
+pub const STREAM_TRADE: &str = "trade";
+// Ticker channel of Coinbase Advanced Trade, on its own connection
+pub const STREAM_COINBASE_TICKER: &str = "coinbase_ticker";
+// Label of combined stream frames that belong to no feed (subscription replies, unparsable frames)
+pub const STREAM_COMBINED: &str = "combined";
+
+// Registry exposed on /metrics
+pub static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(collector: T) -> T {
+    REGISTRY.register(Box::new(collector.clone())).expect("Metric registered twice");
+    collector
+}
+
+pub static WS_MESSAGES_RECEIVED: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("ws_messages_received_total", "WebSocket messages received per stream"), &["stream"]).unwrap()));
+
+pub static WS_PARSE_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("ws_parse_failures_total", "WebSocket messages that could not be parsed per stream"), &["stream"]).unwrap()));
+
+pub static WS_EMPTY_ROWS: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("ws_empty_rows_total", "WebSocket \"Empty row\" messages (null results) per stream"), &["stream"]).unwrap()));
+
+pub static WS_RECONNECT_ATTEMPTS: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("ws_reconnect_attempts_total", "WebSocket connection attempts after the first one per stream"), &["stream"]).unwrap()));
+
+pub static WS_RETRY_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| register(IntGaugeVec::new(
+    Opts::new("ws_retry_count", "Current consecutive failed connection attempts per stream"), &["stream"]).unwrap()));
+
+pub static RECORDER_DROPPED_FRAMES: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("recorder_dropped_frames_total", "Raw frames not recorded because the recorder fell behind per stream"), &["stream"]).unwrap()));
+
+pub static ORDER_BOOK_LEVELS: Lazy<IntGaugeVec> = Lazy::new(|| register(IntGaugeVec::new(
+    Opts::new("order_book_levels", "Price levels in the local order book"), &["symbol", "side"]).unwrap()));
+
+pub static TRADE_HISTORY_LENGTH: Lazy<IntGaugeVec> = Lazy::new(|| register(IntGaugeVec::new(
+    Opts::new("trade_history_length", "Trades kept in the rolling trade history"), &["symbol"]).unwrap()));
+
+pub static SECONDS_SINCE_LAST_UPDATE: Lazy<GaugeVec> = Lazy::new(|| register(GaugeVec::new(
+    Opts::new("seconds_since_last_update", "Seconds since the last applied update per stream and symbol"), &["stream", "symbol"]).unwrap()));
+
+pub static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("http_requests_total", "REST requests"), &["method", "route", "status"]).unwrap()));
+
+pub static HTTP_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| register(HistogramVec::new(
+    HistogramOpts::new("http_request_duration_seconds", "REST request latency"), &["method", "route"]).unwrap()));
+
+pub static GRPC_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| register(IntCounterVec::new(
+    Opts::new("grpc_requests_total", "gRPC requests"), &["method"]).unwrap()));
+
+pub static GRPC_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| register(HistogramVec::new(
+    HistogramOpts::new("grpc_request_duration_seconds", "gRPC latency until the response headers"), &["method"]).unwrap()));
+
+// Refresh the gauges derived from the last update times tracked by the health checks
+pub fn refresh_update_ages(health: &HealthRegistry) {
+    for (stream, symbol, age) in health.update_ages() {
+        SECONDS_SINCE_LAST_UPDATE
+            .with_label_values(&[&stream, &symbol])
+            .set(age.as_secs_f64());
+    }
+}
+
+// Render all registered metrics in the Prometheus text format
+pub fn encode() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        log::error!("Failed to encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+// Route label without the symbol segment (e.g. /orderbook/{symbol}/top) to keep the cardinality bounded
+pub fn route_label(path: &str, status: u16) -> String {
+    if status == 404 {
+        return "unmatched".to_string();
+    }
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        [resource, _symbol, rest @ ..] if !rest.is_empty() => format!("/{}/{{symbol}}/{}", resource, rest.join("/")),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_label_hides_symbol() {
+        assert_eq!(route_label("/orderbook/BTCFDUSD/top", 200), "/orderbook/{symbol}/top");
+        assert_eq!(route_label("/metrics", 200), "/metrics");
+        assert_eq!(route_label("/whatever/else/here", 404), "unmatched");
+    }
+
+    #[test]
+    fn test_encode_contains_stream_counters() {
+        WS_MESSAGES_RECEIVED.with_label_values(&[STREAM_TRADE]).inc();
+        let health = HealthRegistry::new();
+        health.record_update(STREAM_TRADE, "METRICSTEST");
+        refresh_update_ages(&health);
+
+        let text = encode();
+        assert!(text.contains("ws_messages_received_total{stream=\"trade\"}"));
+        assert!(text.contains("seconds_since_last_update{stream=\"trade\",symbol=\"METRICSTEST\"}"));
+    }
+}
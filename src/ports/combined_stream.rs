@@ -0,0 +1,396 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use async_trait::async_trait;
+use binance_spot_connector_rust::tokio_tungstenite::BinanceWebSocketClient;
+use binance_spot_connector_rust::websocket::Stream;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use crate::config::CONFIG;
+use crate::domain::entities::symbol::symbol_from_stream;
+use crate::health::{FeedTaskState, HealthRegistry};
+use crate::metrics::{STREAM_COMBINED, WS_MESSAGES_RECEIVED, WS_PARSE_FAILURES};
+use crate::ports::market_data::{MarketDataSource, MarketEventSink};
+use crate::ports::recorder::FrameRecorder;
+use crate::ports::supervisor::{ConnectionResult, FeedSupervisor};
+
+// Venue of the combined stream, its quotes are consolidated with the other venues under this name
+pub const VENUE_BINANCE: &str = "binance";
+// Binance serves at most 1024 streams on a single connection, more streams are spread over several connections
+pub const MAX_STREAMS_PER_CONNECTION: usize = 1024;
+
+// A market data feed carried by the combined stream, e.g. the book tickers of all trading pairs
+#[async_trait]
+pub trait StreamFeed: Send + Sync {
+    // Name used by the health checks, the metrics and the recorder (e.g. book_ticker)
+    fn name(&self) -> &'static str;
+    // Streams to subscribe to for the trading pairs
+    fn streams(&self, trading_pairs: &[String]) -> Vec<Stream>;
+    // Called for every new connection with the trading pairs it carries for the feed, updates missed while
+    // disconnected may invalidate their local state
+    async fn connected(&self, _trading_pairs: &[String], _sink: &dyn MarketEventSink) {}
+    // Publish the events of a raw frame received on one of the feed's streams and record the applied updates in
+    // `health`, `recorder` (when the feed is recorded) already has the frame and takes the frames the feed derives from it
+    async fn handle_frame(&self, frame: &[u8], sink: &dyn MarketEventSink, health: &HealthRegistry, recorder: Option<&FrameRecorder>);
+}
+
+// Frames of the combined stream are {"stream": ..., "data": ...}, the (un)subscribe replies {"result": ..., "id": ...}
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CombinedFrame {
+    Stream { stream: String },
+    Reply { id: u64 },
+}
+
+// Streams of one connection, the feed every stream name belongs to and the trading pairs of every feed
+struct Connection {
+    streams: Vec<String>,
+    routes: HashMap<String, Arc<dyn StreamFeed>>,
+    feeds: Vec<Arc<dyn StreamFeed>>,
+    names: Vec<&'static str>,
+    trading_pairs: HashMap<&'static str, Vec<String>>,
+}
+
+// The Binance market data source: ingests all feeds of the trading pairs over as few connections as possible,
+// every frame is dispatched by its stream name to the feed that subscribed to it
+pub struct CombinedStream {
+    ws_url: String,
+    trading_pairs: Vec<String>,
+    feeds: Vec<Arc<dyn StreamFeed>>,
+    max_streams: usize,
+}
+
+impl CombinedStream {
+    pub fn new(ws_url: &str, trading_pairs: &[String]) -> Self {
+        CombinedStream {
+            ws_url: ws_url.to_string(),
+            trading_pairs: trading_pairs.to_vec(),
+            feeds: Vec::new(),
+            max_streams: MAX_STREAMS_PER_CONNECTION,
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(&CONFIG.default.ws_url, &CONFIG.default.trading_pairs).with_max_streams(CONFIG.default.ws_max_streams)
+    }
+
+    pub fn with_feed(mut self, feed: Arc<dyn StreamFeed>) -> Self {
+        self.feeds.push(feed);
+        self
+    }
+
+    // Streams subscribed to on a single connection, capped at the Binance limit
+    pub fn with_max_streams(mut self, max_streams: usize) -> Self {
+        self.max_streams = max_streams.clamp(1, MAX_STREAMS_PER_CONNECTION);
+        self
+    }
+
+    // Split the streams of all feeds into connections of at most `max_streams` streams
+    fn connections(&self) -> Vec<Connection> {
+        let streams: Vec<(String, &Arc<dyn StreamFeed>)> = self.feeds.iter()
+            .flat_map(|feed| feed.streams(&self.trading_pairs).into_iter().map(move |stream| (stream.as_str().to_string(), feed)))
+            .collect();
+
+        streams.chunks(self.max_streams)
+            .map(|chunk| {
+                let mut feeds: Vec<Arc<dyn StreamFeed>> = Vec::new();
+                let mut trading_pairs: HashMap<&'static str, Vec<String>> = HashMap::new();
+                for (stream, feed) in chunk {
+                    if !feeds.iter().any(|known| known.name() == feed.name()) {
+                        feeds.push(Arc::clone(feed));
+                    }
+                    trading_pairs.entry(feed.name()).or_default().push(symbol_from_stream(stream));
+                }
+                Connection {
+                    streams: chunk.iter().map(|(stream, _)| stream.clone()).collect(),
+                    routes: chunk.iter().map(|(stream, feed)| (stream.clone(), Arc::clone(feed))).collect(),
+                    names: feeds.iter().map(|feed| feed.name()).collect(),
+                    feeds,
+                    trading_pairs,
+                }
+            })
+            .collect()
+    }
+
+    // Dispatch the frames of a single connection until it drops or the shutdown, Err when it cannot be established
+    async fn connect(&self, connection: &Connection, recorders: &HashMap<&str, FrameRecorder>, sink: &dyn MarketEventSink,
+                     supervisor: &FeedSupervisor) -> ConnectionResult {
+        let (shutdown, health) = (supervisor.shutdown(), supervisor.health());
+        let (mut conn, _) = BinanceWebSocketClient::connect_async(&self.ws_url).await.map_err(|e| e.to_string())?;
+        log::info!("WebSocket: connection established for {} streams of {}.", connection.streams.len(), connection.names.join(", "));
+
+        let streams: Vec<Stream> = connection.streams.iter().map(|stream| Stream::new(stream)).collect();
+        conn.subscribe(streams.iter()).await;
+        for feed in &connection.feeds {
+            let trading_pairs = connection.trading_pairs.get(feed.name()).map(Vec::as_slice).unwrap_or_default();
+            feed.connected(trading_pairs, sink).await;
+        }
+
+        // A feed is live once a frame of its streams arrived, not as soon as it is subscribed
+        let mut live: HashSet<&'static str> = HashSet::new();
+        while let Some(message) = tokio::select! {
+            message = conn.as_mut().next() => message,
+            _ = shutdown.cancelled() => None,
+        } {
+            match message {
+                // Only data frames are routed, tungstenite answers the pings and a close ends the connection
+                Ok(message) if message.is_text() || message.is_binary() => {
+                    if let Some(feed) = dispatch(&connection.routes, recorders, sink, health, &message.into_data()).await {
+                        if live.insert(feed) {
+                            health.set_state(feed, FeedTaskState::Live);
+                        }
+                    }
+                }
+                Ok(message) if message.is_close() => break,
+                Ok(_) => {}
+                Err(e) => {
+                    log::error!("Error receiving message: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if shutdown.is_cancelled() {
+            // Close the connection cleanly instead of dropping it
+            if let Err(e) = conn.close().await {
+                log::warn!("Failed to close the WebSocket connection: {}", e);
+            }
+        } else if live.is_empty() {
+            return Err("connection closed before any data was received".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for CombinedStream {
+    fn name(&self) -> &'static str {
+        VENUE_BINANCE
+    }
+
+    // Run every connection under the supervisor until the shutdown (or until they are dead)
+    async fn run(&self, sink: &dyn MarketEventSink, supervisor: &FeedSupervisor) {
+        for feed in &self.feeds {
+            supervisor.health().expect_updates(feed.name(), &self.trading_pairs);
+        }
+        let recorders: HashMap<&str, FrameRecorder> = self.feeds.iter()
+            .filter_map(|feed| FrameRecorder::for_stream(feed.name()).map(|recorder| (feed.name(), recorder)))
+            .collect();
+        let connections = self.connections();
+        if connections.len() > 1 {
+            log::info!("Spreading the streams over {} connections of at most {} streams", connections.len(), self.max_streams);
+        }
+
+        futures_util::future::join_all(connections.iter().map(|connection| {
+            supervisor.supervise(&connection.names, || self.connect(connection, &recorders, sink, supervisor))
+        })).await;
+
+        // Frames received before the shutdown are written before the task ends
+        for recorder in recorders.into_values() {
+            recorder.finish().await;
+        }
+    }
+}
+
+// Hand a raw frame to the feed of its stream, recording it first when the feed is recorded.
+// Returns the name of the feed for the frames of a stream.
+async fn dispatch(routes: &HashMap<String, Arc<dyn StreamFeed>>, recorders: &HashMap<&str, FrameRecorder>, sink: &dyn MarketEventSink,
+                  health: &HealthRegistry, binary_data: &[u8]) -> Option<&'static str> {
+    match serde_json::from_slice::<CombinedFrame>(binary_data) {
+        Ok(CombinedFrame::Stream { stream }) => match routes.get(&stream) {
+            Some(feed) => {
+                WS_MESSAGES_RECEIVED.with_label_values(&[feed.name()]).inc();
+                let recorder = recorders.get(feed.name());
+                if let Some(recorder) = recorder {
+                    recorder.record(binary_data);
+                }
+                feed.handle_frame(binary_data, sink, health, recorder).await;
+                return Some(feed.name());
+            }
+            None => {
+                WS_MESSAGES_RECEIVED.with_label_values(&[STREAM_COMBINED]).inc();
+                log::warn!("Frame of the unsubscribed stream {}", stream);
+            }
+        },
+        Ok(CombinedFrame::Reply { id }) => {
+            WS_MESSAGES_RECEIVED.with_label_values(&[STREAM_COMBINED]).inc();
+            log::debug!("Reply to request {}: {}", id, String::from_utf8_lossy(binary_data));
+        }
+        Err(e) => {
+            WS_MESSAGES_RECEIVED.with_label_values(&[STREAM_COMBINED]).inc();
+            WS_PARSE_FAILURES.with_label_values(&[STREAM_COMBINED]).inc();
+            log::error!("Failed to route frame: {}: {}", e, String::from_utf8_lossy(binary_data));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+    use crate::config::{DeadFeedPolicy, OrderBookMode};
+    use crate::ports::supervisor::Backoff;
+    use crate::domain::services::clock::{Clock, SystemClock};
+    use crate::metrics::{STREAM_ORDER_BOOK, STREAM_TRADE};
+    use crate::ports::market_data::MarketDataServices;
+    use crate::ports::mock_binance::{eventually, test_services, test_supervisor, MockBinanceServer, MockFrame};
+    use crate::ports::ws_client_book_ticker::BookTickerFeed;
+    use crate::ports::ws_client_order_book::OrderBookFeed;
+    use crate::ports::ws_client_trade::TradeFeed;
+
+    fn ingestion(url: &str, symbol: &str) -> CombinedStream {
+        CombinedStream::new(url, &[symbol.to_string()])
+            .with_feed(Arc::new(OrderBookFeed::new(OrderBookMode::Partial)))
+            .with_feed(Arc::new(BookTickerFeed))
+            .with_feed(Arc::new(TradeFeed))
+    }
+
+    async fn all_updated(services: &MarketDataServices, symbol: &str) -> bool {
+        services.book_ticker.get_ticker_data(symbol).await.is_some()
+            && services.order_book.get_top_of_book(symbol).await.is_some()
+            && services.trade_history.history_len(symbol).await == Some(1)
+    }
+
+    fn frames(symbol: &str) -> Vec<MockFrame> {
+        vec![
+            MockFrame::book_ticker(symbol, 1, "99", "101"),
+            MockFrame::partial_depth(symbol, CONFIG.default.book_depth, 1, &[("99", "1")], &[("101", "1")]),
+            MockFrame::trade(symbol, 1, "100", "1", SystemClock.now_millis()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_one_connection_carries_every_feed() {
+        let mut script = vec![
+            MockFrame::Raw("not json".to_string()),
+            MockFrame::null_data("e2eother@trade"),
+        ];
+        script.extend(frames("E2ECS"));
+        let server = MockBinanceServer::start(vec![script]);
+        let services = test_services();
+        let client = tokio::spawn({
+            let (ingestion, sink) = (ingestion(&server.url(), "E2ECS"), services.clone());
+            async move { ingestion.run(sink.as_ref(), &test_supervisor(CancellationToken::new())).await }
+        });
+
+        eventually("the updates of every feed", || all_updated(&services, "E2ECS")).await;
+        client.abort();
+
+        assert_eq!(server.connections(), 1);
+        assert_eq!(server.subscriptions()[0], vec![
+            format!("e2ecs@depth{}@100ms", CONFIG.default.book_depth),
+            "e2ecs@bookTicker".to_string(),
+            "e2ecs@trade".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_streams_beyond_the_limit_open_another_connection() {
+        // Both connections get every frame, each one only dispatches the streams it subscribed to
+        let server = MockBinanceServer::start(vec![frames("E2ESPLIT"), frames("E2ESPLIT")]);
+        let services = test_services();
+        let client = tokio::spawn({
+            let (ingestion, sink) = (ingestion(&server.url(), "E2ESPLIT").with_max_streams(2), services.clone());
+            async move { ingestion.run(sink.as_ref(), &test_supervisor(CancellationToken::new())).await }
+        });
+
+        eventually("the updates of every feed", || all_updated(&services, "E2ESPLIT")).await;
+        client.abort();
+
+        assert_eq!(server.connections(), 2);
+        let mut sizes: Vec<usize> = server.subscriptions().iter().map(Vec::len).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 2]);
+    }
+
+    // Feed that never publishes anything
+    struct SilentFeed;
+
+    #[async_trait]
+    impl StreamFeed for SilentFeed {
+        fn name(&self) -> &'static str {
+            "silent"
+        }
+
+        fn streams(&self, trading_pairs: &[String]) -> Vec<Stream> {
+            trading_pairs.iter().map(|pair| Stream::new(&format!("{}@trade", pair.to_lowercase()))).collect()
+        }
+
+        async fn handle_frame(&self, _frame: &[u8], _sink: &dyn MarketEventSink, _health: &HealthRegistry, _recorder: Option<&FrameRecorder>) {}
+    }
+
+    // Feed that counts the frames handed to it
+    #[derive(Default)]
+    struct CountingFeed {
+        frames: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl StreamFeed for CountingFeed {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn streams(&self, trading_pairs: &[String]) -> Vec<Stream> {
+            trading_pairs.iter().map(|pair| Stream::new(&format!("{}@trade", pair.to_lowercase()))).collect()
+        }
+
+        async fn handle_frame(&self, _frame: &[u8], _sink: &dyn MarketEventSink, _health: &HealthRegistry, _recorder: Option<&FrameRecorder>) {
+            self.frames.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_control_frames_are_not_dispatched() {
+        // The ping carries a payload that would route to the feed if it was taken for a data frame
+        let server = MockBinanceServer::start(vec![vec![
+            MockFrame::Ping(r#"{"stream":"e2eping@trade","data":null}"#.to_string()),
+            MockFrame::null_data("e2eping@trade"),
+        ]]);
+        let feed = Arc::new(CountingFeed::default());
+        let client = tokio::spawn({
+            let ingestion = CombinedStream::new(&server.url(), &["E2EPING".to_string()]).with_feed(feed.clone());
+            async move { ingestion.run(test_services().as_ref(), &test_supervisor(CancellationToken::new())).await }
+        });
+
+        // Frames arrive in order, the ping was handled once the data frame is counted
+        eventually("the data frame", || async { feed.frames.load(Ordering::SeqCst) > 0 }).await;
+        client.abort();
+
+        assert_eq!(feed.frames.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_connections_closed_before_any_data_are_failures() {
+        // Every connection is accepted and subscribed, then dropped before a single frame
+        let server = MockBinanceServer::start(vec![vec![MockFrame::Disconnect]; 3]);
+        let ingestion = CombinedStream::new(&server.url(), &["E2ESILENT".to_string()]).with_feed(Arc::new(SilentFeed));
+        let supervisor = FeedSupervisor::new(Backoff::new(Duration::from_millis(1), Duration::from_millis(5)), 3,
+            DeadFeedPolicy::Unready, CancellationToken::new(), Arc::new(HealthRegistry::new()));
+
+        ingestion.run(test_services().as_ref(), &supervisor).await;
+
+        assert_eq!(server.connections(), 3);
+        let status = supervisor.health().feed_status("silent").unwrap();
+        assert_eq!((status.state, status.failures), (FeedTaskState::Dead, 3));
+        assert_eq!(status.last_error.as_deref(), Some("connection closed before any data was received"));
+    }
+
+    #[test]
+    fn test_connections_know_the_trading_pairs_of_their_feeds() {
+        let pairs = ["E2EPAIRA".to_string(), "E2EPAIRB".to_string()];
+        let ingestion = CombinedStream::new("ws://unused", &pairs)
+            .with_feed(Arc::new(OrderBookFeed::new(OrderBookMode::Partial)))
+            .with_feed(Arc::new(TradeFeed))
+            .with_max_streams(3);
+
+        // A reconnect of either connection only invalidates the pairs it carries
+        let connections = ingestion.connections();
+        assert_eq!(connections[0].trading_pairs[STREAM_ORDER_BOOK], pairs.to_vec());
+        assert_eq!(connections[0].trading_pairs[STREAM_TRADE], vec!["E2EPAIRA".to_string()]);
+        assert_eq!(connections[1].trading_pairs[STREAM_TRADE], vec!["E2EPAIRB".to_string()]);
+        assert!(!connections[1].trading_pairs.contains_key(STREAM_ORDER_BOOK));
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::domain::entities::market_event::{BookDelta, MarketEvent};
+use crate::domain::entities::order_book::DepthData;
+use crate::domain::entities::symbol::normalize_symbol;
+use crate::ports::market_data::MarketEventSink;
+use crate::ports::recorder::FrameRecorder;
+use crate::ports::rest_depth_snapshot::fetch_depth_snapshot;
+use crate::ports::supervisor::Backoff;
+
+// Upper bound of diff events kept per symbol while waiting for a usable snapshot
+const MAX_PENDING_EVENTS: usize = 1_000;
+// Snapshot attempts per sync round, a round that runs out waits the longest backoff delay before the next one
+const SNAPSHOT_ATTEMPTS: u32 = 5;
+// Delays between the snapshot attempts of a round
+const SNAPSHOT_BACKOFF_INITIAL: Duration = Duration::from_millis(500);
+const SNAPSHOT_BACKOFF_MAX: Duration = Duration::from_secs(8);
+
+// Stream name suffix of the depth snapshots written to the order book recording
+pub const DEPTH_SNAPSHOT_STREAM: &str = "depthSnapshot";
+
+// Where the snapshots the diff events are applied on come from
+enum SnapshotSource {
+    // Fetched from the REST API
+    Rest { url: String, limit: u16 },
+    // Read from the recording, the snapshots applied live were recorded with the diff events
+    Recording,
+}
+
+// Sync state of the book of one symbol
+#[derive(Default)]
+struct BookSync {
+    synced: bool,
+    pending: VecDeque<BookDelta>,
+    // Snapshot fetch running in the background, `None` when its attempts ran out
+    fetch: Option<JoinHandle<Option<DepthData>>>,
+}
+
+// Keeps local order books in sync with the diff depth stream:
+// buffer events, load a REST snapshot, replay the buffer and resync on sequence gaps.
+// Snapshots are fetched in the background so a slow snapshot never holds up the stream, the events keep being
+// buffered meanwhile and the snapshot is applied with the buffer on the first event after it arrived.
+// Every symbol has its own lock, the events of one symbol never wait for another one.
+// The applied snapshots are recorded on the order book stream, a replay applies them in place of the REST fetch.
+pub struct DepthSync {
+    source: SnapshotSource,
+    backoff: Backoff,
+    books: Mutex<HashMap<String, Arc<tokio::sync::Mutex<BookSync>>>>,
+}
+
+impl DepthSync {
+    pub fn new(snapshot_url: &str, snapshot_limit: u16) -> Self {
+        Self::with_source(SnapshotSource::Rest { url: snapshot_url.to_string(), limit: snapshot_limit })
+    }
+
+    // Sync for a replay: never fetches a snapshot, the recorded ones are handed to `handle_snapshot`
+    pub fn recorded() -> Self {
+        Self::with_source(SnapshotSource::Recording)
+    }
+
+    fn with_source(source: SnapshotSource) -> Self {
+        DepthSync {
+            source,
+            backoff: Backoff::new(SNAPSHOT_BACKOFF_INITIAL, SNAPSHOT_BACKOFF_MAX),
+            books: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn book(&self, symbol: &str) -> Arc<tokio::sync::Mutex<BookSync>> {
+        self.books.lock().unwrap().entry(symbol.to_string()).or_default().clone()
+    }
+
+    // Forget the sync state of the symbols and clear their books, e.g. after a reconnect of the connection carrying
+    // them where events may have been lost. The other symbols are left alone.
+    pub async fn reset(&self, sink: &dyn MarketEventSink, symbols: &[String]) {
+        for symbol in symbols.iter().map(|symbol| normalize_symbol(symbol)) {
+            let Some(book) = self.books.lock().unwrap().remove(&symbol) else { continue };
+            let mut book = book.lock().await;
+            if let Some(fetch) = book.fetch.take() {
+                fetch.abort();
+            }
+            clear(sink, &symbol, &mut book).await;
+        }
+    }
+
+    // Handle a single diff event from the stream, true when the book of its symbol is synced afterwards.
+    // A snapshot applied with the event is written to `recorder`.
+    pub async fn handle_event(&self, sink: &dyn MarketEventSink, diff: BookDelta, recorder: Option<&FrameRecorder>) -> bool {
+        let symbol = normalize_symbol(&diff.symbol);
+        let book = self.book(&symbol);
+        let mut book = book.lock().await;
+        let book = &mut *book;
+
+        if book.synced {
+            match sink.publish(&MarketEvent::BookDelta(diff.clone())).await {
+                Ok(()) => return true,
+                Err(e) => {
+                    log::warn!("OrderBook {}: {}. Resyncing from snapshot...", symbol, e);
+                    clear(sink, &symbol, book).await;
+                }
+            }
+        }
+
+        if book.pending.len() >= MAX_PENDING_EVENTS {
+            book.pending.pop_front();
+        }
+        book.pending.push_back(diff);
+
+        match book.fetch.take() {
+            Some(fetch) if fetch.is_finished() => match fetch.await {
+                Ok(Some(snapshot)) => {
+                    if let Some(recorder) = recorder {
+                        recorder.record(&snapshot_frame(&symbol, &snapshot));
+                    }
+                    apply_snapshot(sink, &symbol, book, snapshot).await;
+                }
+                // The attempts ran out, the next event starts another round
+                Ok(None) => {}
+                Err(e) => log::error!("OrderBook {}: depth snapshot task failed: {}", symbol, e),
+            },
+            Some(fetch) => book.fetch = Some(fetch),
+            None => if let SnapshotSource::Rest { url, limit } = &self.source {
+                let first_update_id = book.pending.front().map(|event| event.first_update_id).unwrap_or_default();
+                book.fetch = Some(tokio::spawn(fetch_snapshot(url.clone(), *limit, symbol, first_update_id, self.backoff)));
+            },
+        }
+        book.synced
+    }
+
+    // Apply a recorded snapshot and the events buffered before it, true when the book is synced afterwards
+    pub async fn handle_snapshot(&self, sink: &dyn MarketEventSink, symbol: &str, snapshot: DepthData) -> bool {
+        let symbol = normalize_symbol(symbol);
+        let book = self.book(&symbol);
+        let mut book = book.lock().await;
+        apply_snapshot(sink, &symbol, &mut book, snapshot).await;
+        book.synced
+    }
+}
+
+// Frame of the order book stream recording a snapshot, in the shape of the partial depth frames
+fn snapshot_frame(symbol: &str, snapshot: &DepthData) -> Vec<u8> {
+    serde_json::json!({
+        "stream": format!("{}@{}", symbol.to_lowercase(), DEPTH_SNAPSHOT_STREAM),
+        "data": snapshot,
+    }).to_string().into_bytes()
+}
+
+// Drop the book and its buffered events, it is not served until the next snapshot
+async fn clear(sink: &dyn MarketEventSink, symbol: &str, book: &mut BookSync) {
+    book.synced = false;
+    book.pending.clear();
+    let _ = sink.publish(&MarketEvent::BookCleared(symbol.to_string())).await;
+}
+
+// Apply the snapshot and replay the buffered events on top of it
+async fn apply_snapshot(sink: &dyn MarketEventSink, symbol: &str, book: &mut BookSync, snapshot: DepthData) {
+    if let Err(e) = sink.publish(&MarketEvent::BookSnapshot(snapshot.to_snapshot(symbol))).await {
+        log::error!("OrderBook {}: {} while applying the snapshot", symbol, e);
+        clear(sink, symbol, book).await;
+        return;
+    }
+
+    while let Some(event) = book.pending.pop_front() {
+        if let Err(e) = sink.publish(&MarketEvent::BookDelta(event)).await {
+            // A half applied book is not served, the next event starts over from a new snapshot
+            log::warn!("OrderBook {}: {} while replaying buffered events", symbol, e);
+            clear(sink, symbol, book).await;
+            return;
+        }
+    }
+
+    log::info!("OrderBook {}: local book synced", symbol);
+    book.synced = true;
+}
+
+// Fetch a snapshot covering the first buffered event, retrying with backoff. After the last attempt the round waits
+// the longest delay, so a symbol that cannot be synced does not keep hitting the REST weight limit.
+async fn fetch_snapshot(url: String, limit: u16, symbol: String, first_update_id: u64, backoff: Backoff) -> Option<DepthData> {
+    for attempt in 1..=SNAPSHOT_ATTEMPTS {
+        match fetch_depth_snapshot(&url, &symbol, limit).await {
+            // The snapshot has to cover the first buffered event, otherwise fetch it again
+            Ok(snapshot) if snapshot.last_update_id >= first_update_id => return Some(snapshot),
+            Ok(snapshot) => log::info!("OrderBook {}: snapshot {} older than buffered event {}, attempt {}/{}",
+                symbol, snapshot.last_update_id, first_update_id, attempt, SNAPSHOT_ATTEMPTS),
+            Err(e) => log::warn!("OrderBook {}: failed to fetch depth snapshot: {}, attempt {}/{}",
+                symbol, e, attempt, SNAPSHOT_ATTEMPTS),
+        }
+        if attempt < SNAPSHOT_ATTEMPTS {
+            tokio::time::sleep(backoff.delay(attempt)).await;
+        }
+    }
+
+    let cooldown = backoff.delay(u32::MAX);
+    log::error!("OrderBook {}: no usable depth snapshot after {} attempts, the book stays unavailable, next round in {:?}",
+        symbol, SNAPSHOT_ATTEMPTS, cooldown);
+    tokio::time::sleep(cooldown).await;
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use warp::Filter;
+    use crate::domain::entities::order_book::DiffDepthData;
+    use crate::metrics::STREAM_ORDER_BOOK;
+    use crate::ports::mock_binance::{eventually, test_services};
+    use crate::ports::recorder::{RecordedFrame, RecorderSettings};
+
+    fn diff(symbol: &str, first_update_id: u64, final_update_id: u64, bid: [&str; 2]) -> BookDelta {
+        DiffDepthData {
+            event_type: "depthUpdate".to_string(),
+            event_time: 0,
+            symbol: symbol.to_string(),
+            first_update_id,
+            final_update_id,
+            bids: vec![[bid[0].to_string(), bid[1].to_string()]],
+            asks: vec![],
+        }.to_delta()
+    }
+
+    // Local stand-in for /api/v3/depth, every request returns the next lastUpdateId from `ids`
+    fn spawn_snapshot_server(ids: Vec<u64>) -> (String, Arc<AtomicU32>) {
+        let requests = Arc::new(AtomicU32::new(0));
+        let counter = requests.clone();
+        let depth = warp::path!("api" / "v3" / "depth").map(move || {
+            let n = counter.fetch_add(1, Ordering::SeqCst) as usize;
+            let id = ids[n.min(ids.len() - 1)];
+            warp::reply::json(&serde_json::json!({
+                "lastUpdateId": id,
+                "bids": [["100.0", "1.0"]],
+                "asks": [["101.0", "1.0"]]
+            }))
+        });
+        let (addr, server) = warp::serve(depth).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (format!("http://{}", addr), requests)
+    }
+
+    fn test_sync(url: &str) -> DepthSync {
+        let backoff = Backoff::new(Duration::from_millis(1), Duration::from_millis(1));
+        DepthSync { backoff, ..DepthSync::new(url, 100) }
+    }
+
+    async fn is_synced(sync: &DepthSync, symbol: &str) -> bool {
+        let book = sync.books.lock().unwrap().get(symbol).cloned();
+        match book {
+            Some(book) => book.lock().await.synced,
+            None => false,
+        }
+    }
+
+    // Wait for the background fetch of the symbol to finish, its snapshot is applied with the next event
+    async fn snapshot_fetched(sync: &DepthSync, symbol: &str) {
+        let book = sync.book(symbol);
+        eventually("the depth snapshot fetch", || async {
+            book.lock().await.fetch.as_ref().is_some_and(|fetch| fetch.is_finished())
+        }).await;
+    }
+
+    #[tokio::test]
+    async fn test_sync_replays_buffer_and_follows_stream() {
+        let (url, requests) = spawn_snapshot_server(vec![10]);
+        let services = test_services();
+        let sync = test_sync(&url);
+        let dir = std::env::temp_dir().join(format!("oracle_depth_sync_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let settings = RecorderSettings { dir: dir.clone(), max_file_bytes: u64::MAX, rotate_after: Duration::from_secs(3600), compress: false };
+        let recorder = FrameRecorder::start(STREAM_ORDER_BOOK, settings).unwrap();
+
+        // First event straddles the snapshot id and is replayed after the snapshot
+        sync.handle_event(services.as_ref(), diff("SYNCTEST1", 8, 12, ["99.5", "2.0"]), Some(&recorder)).await;
+        assert!(!is_synced(&sync, "SYNCTEST1").await);
+        snapshot_fetched(&sync, "SYNCTEST1").await;
+
+        sync.handle_event(services.as_ref(), diff("SYNCTEST1", 13, 15, ["100.5", "3.0"]), Some(&recorder)).await;
+        assert!(is_synced(&sync, "SYNCTEST1").await);
+        let top = services.order_book.get_top_of_book("SYNCTEST1").await.unwrap();
+        assert_eq!(top.best_bid.price, dec!(100.5));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // The applied snapshot is recorded for the replay, the diff events are recorded by the stream
+        recorder.finish().await;
+        let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+        let recorded: RecordedFrame = serde_json::from_str(std::fs::read_to_string(file).unwrap().trim()).unwrap();
+        let frame: serde_json::Value = serde_json::from_str(&recorded.frame).unwrap();
+        assert_eq!(frame["stream"], "synctest1@depthSnapshot");
+        assert_eq!(frame["data"]["lastUpdateId"], 10);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_gap_clears_the_book_and_triggers_resync() {
+        let (url, requests) = spawn_snapshot_server(vec![10, 30]);
+        let services = test_services();
+        let sync = test_sync(&url);
+
+        sync.handle_event(services.as_ref(), diff("SYNCTEST2", 9, 11, ["99.0", "1.0"]), None).await;
+        snapshot_fetched(&sync, "SYNCTEST2").await;
+        sync.handle_event(services.as_ref(), diff("SYNCTEST2", 12, 12, ["99.0", "1.0"]), None).await;
+        assert!(is_synced(&sync, "SYNCTEST2").await);
+
+        // Updates 13..=24 are missing - the book is not served until it is rebuilt from a new snapshot
+        sync.handle_event(services.as_ref(), diff("SYNCTEST2", 25, 31, ["100.8", "1.0"]), None).await;
+        assert!(!is_synced(&sync, "SYNCTEST2").await);
+        assert!(services.order_book.get_top_of_book("SYNCTEST2").await.is_none());
+
+        snapshot_fetched(&sync, "SYNCTEST2").await;
+        sync.handle_event(services.as_ref(), diff("SYNCTEST2", 32, 33, ["99.1", "1.0"]), None).await;
+        assert!(is_synced(&sync, "SYNCTEST2").await);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        let top = services.order_book.get_top_of_book("SYNCTEST2").await.unwrap();
+        assert_eq!(top.best_bid.price, dec!(100.8));
+    }
+
+    #[tokio::test]
+    async fn test_reset_only_clears_the_given_symbols() {
+        let (url, _) = spawn_snapshot_server(vec![10]);
+        let services = test_services();
+        let sync = test_sync(&url);
+
+        for symbol in ["SYNCTEST5", "SYNCTEST6"] {
+            sync.handle_event(services.as_ref(), diff(symbol, 9, 11, ["99.0", "1.0"]), None).await;
+            snapshot_fetched(&sync, symbol).await;
+            sync.handle_event(services.as_ref(), diff(symbol, 12, 12, ["99.0", "1.0"]), None).await;
+        }
+
+        sync.reset(services.as_ref(), &["SYNCTEST5".to_string()]).await;
+        assert!(!is_synced(&sync, "SYNCTEST5").await);
+        assert!(services.order_book.get_top_of_book("SYNCTEST5").await.is_none());
+        assert!(is_synced(&sync, "SYNCTEST6").await);
+        assert!(services.order_book.get_top_of_book("SYNCTEST6").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stale_snapshot_is_refetched() {
+        let (url, requests) = spawn_snapshot_server(vec![5, 20]);
+        let services = test_services();
+        let sync = test_sync(&url);
+
+        sync.handle_event(services.as_ref(), diff("SYNCTEST3", 18, 21, ["99.0", "1.0"]), None).await;
+        snapshot_fetched(&sync, "SYNCTEST3").await;
+        sync.handle_event(services.as_ref(), diff("SYNCTEST3", 22, 22, ["99.0", "1.0"]), None).await;
+        assert!(is_synced(&sync, "SYNCTEST3").await);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_sync_round_gives_up_and_the_next_event_starts_another() {
+        let (url, requests) = spawn_snapshot_server(vec![5]);
+        let services = test_services();
+        let sync = test_sync(&url);
+
+        sync.handle_event(services.as_ref(), diff("SYNCTEST4", 18, 21, ["99.0", "1.0"]), None).await;
+        snapshot_fetched(&sync, "SYNCTEST4").await;
+        sync.handle_event(services.as_ref(), diff("SYNCTEST4", 22, 22, ["99.0", "1.0"]), None).await;
+        assert!(!is_synced(&sync, "SYNCTEST4").await);
+        assert_eq!(requests.load(Ordering::SeqCst), SNAPSHOT_ATTEMPTS);
+
+        sync.handle_event(services.as_ref(), diff("SYNCTEST4", 23, 23, ["99.0", "1.0"]), None).await;
+        eventually("another sync round", || async { requests.load(Ordering::SeqCst) > SNAPSHOT_ATTEMPTS }).await;
+    }
+}
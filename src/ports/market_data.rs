@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::domain::entities::market_event::MarketEvent;
+use crate::domain::entities::order_book::DepthSyncError;
+use crate::domain::services::book_ticker_service::BookTickerServiceTrait;
+use crate::domain::services::consolidation_service::ConsolidationService;
+use crate::domain::services::order_book_service::OrderBookServiceTrait;
+use crate::domain::services::trade_history_service::TradeHistoryService;
+use crate::ports::supervisor::FeedSupervisor;
+
+// Receives the normalized events of the market data sources
+#[async_trait]
+pub trait MarketEventSink: Send + Sync {
+    // Apply a single event. A book delta that does not continue its book is refused, the source has to send a new
+    // snapshot before further deltas of that book.
+    async fn publish(&self, event: &MarketEvent) -> Result<(), DepthSyncError>;
+}
+
+// A venue, or anything else producing market data (e.g. a synthetic generator). A source translates its own
+// messages into normalized events, so adding one does not touch the services.
+#[async_trait]
+pub trait MarketDataSource: Send + Sync {
+    // Name of the source in the logs (e.g. binance)
+    fn name(&self) -> &'static str;
+    // Publish events to `sink` until the shutdown of the supervisor, or until the source is dead
+    async fn run(&self, sink: &dyn MarketEventSink, supervisor: &FeedSupervisor);
+}
+
+// Sink applying the events to the services owning the market state
+pub struct MarketDataServices {
+    pub order_book: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+    pub book_ticker: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+    pub trade_history: Arc<TradeHistoryService>,
+}
+
+impl MarketDataServices {
+    pub fn new(
+        order_book: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+        book_ticker: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+        trade_history: Arc<TradeHistoryService>,
+    ) -> Self {
+        MarketDataServices { order_book, book_ticker, trade_history }
+    }
+}
+
+#[async_trait]
+impl MarketEventSink for MarketDataServices {
+    async fn publish(&self, event: &MarketEvent) -> Result<(), DepthSyncError> {
+        match event {
+            MarketEvent::BookSnapshot(snapshot) => self.order_book.apply_snapshot(snapshot).await,
+            MarketEvent::BookDelta(delta) => return self.order_book.apply_delta(delta).await,
+            MarketEvent::BookCleared(symbol) => self.order_book.clear_book(symbol).await,
+            MarketEvent::TopOfBook(top) => self.book_ticker.update_ticker(top).await,
+            MarketEvent::Trade(trade) => self.trade_history.add_trade(trade).await,
+        }
+        Ok(())
+    }
+}
+
+// Sink of a single venue: its top of book is quoted to the cross-venue consolidation, then the events go on to the
+// services when the venue backs them. Only one venue does, the books and trades of different venues do not mix.
+pub struct VenueSink {
+    venue: &'static str,
+    consolidation: Arc<ConsolidationService>,
+    services: Option<Arc<dyn MarketEventSink>>,
+}
+
+impl VenueSink {
+    pub fn new(venue: &'static str, consolidation: Arc<ConsolidationService>) -> Self {
+        VenueSink { venue, consolidation, services: None }
+    }
+
+    pub fn with_services(mut self, services: Arc<dyn MarketEventSink>) -> Self {
+        self.services = Some(services);
+        self
+    }
+}
+
+#[async_trait]
+impl MarketEventSink for VenueSink {
+    async fn publish(&self, event: &MarketEvent) -> Result<(), DepthSyncError> {
+        if let MarketEvent::TopOfBook(top) = event {
+            self.consolidation.update(self.venue, top);
+        }
+        match &self.services {
+            Some(services) => services.publish(event).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use tokio_util::sync::CancellationToken;
+    use crate::domain::entities::market_event::{BookDelta, BookSnapshot, TopOfBook, TradeEvent};
+    use crate::domain::entities::order_book::OrderBookEntry;
+    use crate::domain::services::clock::{Clock, SystemClock};
+    use crate::ports::mock_binance::{test_services, test_supervisor};
+
+    // A source replaying fixed events, standing in for a synthetic generator
+    struct FixedSource(Vec<MarketEvent>);
+
+    #[async_trait]
+    impl MarketDataSource for FixedSource {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        async fn run(&self, sink: &dyn MarketEventSink, _supervisor: &FeedSupervisor) {
+            for event in &self.0 {
+                let _ = sink.publish(event).await;
+            }
+        }
+    }
+
+    fn level(price: &str, qty: &str) -> OrderBookEntry {
+        OrderBookEntry { price: price.parse().unwrap(), qty: qty.parse().unwrap() }
+    }
+
+    #[tokio::test]
+    async fn test_events_of_any_source_reach_the_services() {
+        let services = test_services();
+        let source = FixedSource(vec![
+            MarketEvent::BookSnapshot(BookSnapshot {
+                symbol: "SYNTH".to_string(),
+                bids: vec![level("99", "1")],
+                asks: vec![level("101", "1")],
+                update_id: 10,
+                event_time: None,
+            }),
+            MarketEvent::BookDelta(BookDelta {
+                symbol: "SYNTH".to_string(),
+                first_update_id: 11,
+                final_update_id: 11,
+                bids: vec![level("100", "2")],
+                asks: vec![],
+                event_time: Some(1_000),
+            }),
+            MarketEvent::TopOfBook(TopOfBook {
+                symbol: "SYNTH".to_string(),
+                update_id: 1,
+                best_bid: level("100", "2"),
+                best_ask: level("101", "1"),
+                event_time: None,
+            }),
+            MarketEvent::Trade(TradeEvent {
+                symbol: "SYNTH".to_string(),
+                trade_id: 1,
+                price: dec!(100.5),
+                quantity: dec!(0.5),
+                trade_time: SystemClock.now_millis(),
+                event_time: None,
+                is_buyer_market_maker: false,
+            }),
+        ]);
+
+        source.run(services.as_ref(), &test_supervisor(CancellationToken::new())).await;
+
+        assert_eq!(services.order_book.get_top_of_book("SYNTH").await.unwrap().best_bid.price, dec!(100));
+        assert_eq!(services.book_ticker.mid_price("synth").await, Some(dec!(100.5)));
+        assert_eq!(services.trade_history.history_len("SYNTH").await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_delta_not_continuing_the_book_is_refused() {
+        let services = test_services();
+        let delta = |first_update_id| MarketEvent::BookDelta(BookDelta {
+            symbol: "SYNTHGAP".to_string(),
+            first_update_id,
+            final_update_id: first_update_id,
+            bids: vec![level("100", "1")],
+            asks: vec![],
+            event_time: None,
+        });
+
+        assert_eq!(services.publish(&delta(1)).await, Err(DepthSyncError::NoSnapshot));
+        services.publish(&MarketEvent::BookSnapshot(BookSnapshot {
+            symbol: "SYNTHGAP".to_string(),
+            bids: vec![],
+            asks: vec![level("101", "1")],
+            update_id: 5,
+            event_time: None,
+        })).await.unwrap();
+        assert_eq!(services.publish(&delta(7)).await, Err(DepthSyncError::Gap { expected: 6, first_update_id: 7 }));
+        assert_eq!(services.publish(&delta(6)).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_every_venue_is_quoted_but_only_one_backs_the_services() {
+        let services = test_services();
+        let consolidation = Arc::new(ConsolidationService::new());
+        let binance = VenueSink::new("binance", consolidation.clone()).with_services(services.clone());
+        let coinbase = VenueSink::new("coinbase", consolidation.clone());
+        let top = |bid: &str, ask: &str| MarketEvent::TopOfBook(TopOfBook {
+            symbol: "SYNTHVENUE".to_string(),
+            update_id: 1,
+            best_bid: level(bid, "1"),
+            best_ask: level(ask, "1"),
+            event_time: None,
+        });
+
+        binance.publish(&top("99", "101")).await.unwrap();
+        coinbase.publish(&top("103", "105")).await.unwrap();
+
+        assert_eq!(consolidation.consolidated("SYNTHVENUE").unwrap().price, dec!(102));
+        assert_eq!(services.book_ticker.mid_price("SYNTHVENUE").await, Some(dec!(100)));
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+use crate::config::DeadFeedPolicy;
+use crate::domain::services::book_ticker_service::BookTickerService;
+use crate::domain::services::order_book_service::OrderBookService;
+use crate::domain::services::trade_history_service::TradeHistoryService;
+use crate::health::HealthRegistry;
+use crate::ports::market_data::MarketDataServices;
+use crate::ports::supervisor::{Backoff, FeedSupervisor};
+
+// How long `eventually` waits for a condition before failing the test
+const EVENTUALLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// A frame the mock server plays to a connected client
+#[derive(Debug, Clone)]
+pub enum MockFrame {
+    // Combined stream frame: {"stream": ..., "data": ...}
+    Stream { stream: String, data: serde_json::Value },
+    // Sent as is, e.g. malformed JSON
+    Raw(String),
+    // Control frame with the given payload
+    Ping(String),
+    // Drop the connection without a close handshake
+    Disconnect,
+}
+
+impl MockFrame {
+    pub fn book_ticker(symbol: &str, update_id: u64, bid: &str, ask: &str) -> Self {
+        MockFrame::Stream {
+            stream: format!("{}@bookTicker", symbol.to_lowercase()),
+            data: json!({"u": update_id, "s": symbol, "b": bid, "B": "1", "a": ask, "A": "1"}),
+        }
+    }
+
+    pub fn trade(symbol: &str, trade_id: u64, price: &str, quantity: &str, trade_time: u64) -> Self {
+        MockFrame::Stream {
+            stream: format!("{}@trade", symbol.to_lowercase()),
+            data: json!({
+                "e": "trade", "E": trade_time, "s": symbol, "t": trade_id,
+                "p": price, "q": quantity, "T": trade_time, "m": true, "M": true
+            }),
+        }
+    }
+
+    // Partial book depth snapshot (<symbol>@depth<levels>@100ms), levels are (price, qty)
+    pub fn partial_depth(symbol: &str, levels: u16, last_update_id: u64, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> Self {
+        MockFrame::Stream {
+            stream: format!("{}@depth{}@100ms", symbol.to_lowercase(), levels),
+            data: json!({"lastUpdateId": last_update_id, "bids": bids, "asks": asks}),
+        }
+    }
+
+    // Stream frame without data, counted as an empty row by the clients
+    pub fn null_data(stream: &str) -> Self {
+        MockFrame::Stream { stream: stream.to_string(), data: serde_json::Value::Null }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    scripts: Mutex<VecDeque<Vec<MockFrame>>>,
+    subscriptions: Mutex<Vec<Vec<String>>>,
+    connections: AtomicUsize,
+}
+
+// Local stand-in for the Binance combined stream endpoint (`/stream`).
+// Every connection has to SUBSCRIBE first, which is answered with `{"result":null,"id":..}`,
+// then the next script is played to it. Connections beyond the scripts stay idle.
+pub struct MockBinanceServer {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+    server: JoinHandle<()>,
+}
+
+impl MockBinanceServer {
+    // Serve the scripts, one per connection in order, on an ephemeral local port
+    pub fn start(scripts: Vec<Vec<MockFrame>>) -> Self {
+        let state = Arc::new(MockState {
+            scripts: Mutex::new(scripts.into()),
+            ..Default::default()
+        });
+
+        let connection_state = state.clone();
+        let routes = warp::path("stream")
+            .and(warp::ws())
+            .map(move |ws: warp::ws::Ws| {
+                let state = connection_state.clone();
+                ws.on_upgrade(move |socket| serve_connection(socket, state))
+            });
+        let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+
+        MockBinanceServer {
+            addr,
+            state,
+            server: tokio::spawn(server),
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}/stream", self.addr)
+    }
+
+    // Stream names subscribed to, per connection
+    pub fn subscriptions(&self) -> Vec<Vec<String>> {
+        self.state.subscriptions.lock().unwrap().clone()
+    }
+
+    pub fn connections(&self) -> usize {
+        self.state.connections.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for MockBinanceServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn serve_connection(mut socket: WebSocket, state: Arc<MockState>) {
+    state.connections.fetch_add(1, Ordering::SeqCst);
+
+    // Like Binance, nothing is streamed before the client subscribed
+    let request: serde_json::Value = loop {
+        match socket.next().await {
+            Some(Ok(message)) if message.is_text() => {
+                break serde_json::from_str(message.to_str().unwrap_or_default()).unwrap_or_default();
+            }
+            Some(Ok(_)) => continue,
+            _ => return,
+        }
+    };
+    if request["method"] != "SUBSCRIBE" {
+        return;
+    }
+    let streams = request["params"].as_array().into_iter().flatten()
+        .filter_map(|stream| stream.as_str().map(str::to_string))
+        .collect();
+    state.subscriptions.lock().unwrap().push(streams);
+    let result = json!({"result": null, "id": request["id"]});
+    if socket.send(Message::text(result.to_string())).await.is_err() {
+        return;
+    }
+
+    let script = state.scripts.lock().unwrap().pop_front().unwrap_or_default();
+    for frame in script {
+        let message = match frame {
+            MockFrame::Stream { stream, data } => Message::text(json!({"stream": stream, "data": data}).to_string()),
+            MockFrame::Raw(text) => Message::text(text),
+            MockFrame::Ping(payload) => Message::ping(payload),
+            MockFrame::Disconnect => return,
+        };
+        if socket.send(message).await.is_err() {
+            return;
+        }
+    }
+
+    // Keep the connection open until the client goes away
+    while let Some(Ok(_)) = socket.next().await {}
+}
+
+// Poll `condition` until it holds, panics after EVENTUALLY_TIMEOUT
+pub async fn eventually<F, Fut>(description: &str, condition: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let deadline = Instant::now() + EVENTUALLY_TIMEOUT;
+    while !condition().await {
+        if Instant::now() >= deadline {
+            panic!("Timed out waiting for {}", description);
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+}
+
+// Supervisor retrying failed connections quickly with a health registry of its own, the clients stop once `shutdown`
+// is cancelled
+pub fn test_supervisor(shutdown: CancellationToken) -> FeedSupervisor {
+    FeedSupervisor::new(Backoff::new(Duration::from_millis(10), Duration::from_millis(100)), 3, DeadFeedPolicy::Retry, shutdown,
+        Arc::new(HealthRegistry::new()))
+}
+
+// Sink over fresh services with the default settings
+pub fn test_services() -> Arc<MarketDataServices> {
+    Arc::new(MarketDataServices::new(
+        Arc::new(OrderBookService::new()),
+        Arc::new(BookTickerService::new()),
+        Arc::new(TradeHistoryService::new()),
+    ))
+}
@@ -1,3 +1,13 @@
+pub mod combined_stream;
+pub mod depth_sync;
+pub mod market_data;
+#[cfg(test)]
+pub mod mock_binance;
+pub mod recorder;
+pub mod replay;
+pub mod rest_depth_snapshot;
+pub mod supervisor;
 pub mod ws_client_order_book;
 pub mod ws_client_book_ticker;
-pub mod ws_client_trade;
\ No newline at end of file
+pub mod ws_client_trade;
+pub mod ws_client_coinbase;
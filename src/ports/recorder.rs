@@ -0,0 +1,308 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use crate::config::CONFIG;
+use crate::metrics::RECORDER_DROPPED_FRAMES;
+
+// Frames buffered between a WebSocket client and its recorder, further frames are dropped
+const RECORDER_CHANNEL_CAPACITY: usize = 10_000;
+// zstd compression level of the recording files
+const COMPRESSION_LEVEL: i32 = 3;
+
+// A single line of a recording file (JSON Lines)
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecordedFrame {
+    pub received_at: u64, // Local receive time in milliseconds since the UNIX epoch
+    pub stream: String,   // Stream the frame was received on (e.g., book_ticker)
+    pub frame: String,    // Raw frame as received
+}
+
+// Where and how recording files are written
+#[derive(Debug, Clone)]
+pub struct RecorderSettings {
+    pub dir: PathBuf,
+    pub max_file_bytes: u64,
+    pub rotate_after: Duration,
+    pub compress: bool,
+}
+
+impl RecorderSettings {
+    pub fn from_config() -> Self {
+        RecorderSettings {
+            dir: PathBuf::from(&CONFIG.default.recorder_dir),
+            max_file_bytes: CONFIG.default.recorder_max_file_mb * 1024 * 1024,
+            rotate_after: Duration::from_secs(CONFIG.default.recorder_rotate_secs),
+            compress: CONFIG.default.recorder_compress,
+        }
+    }
+}
+
+// Handle used by a WebSocket client to record every raw frame it receives.
+// Writing happens on a blocking task so the ingestion is never slowed down by the disk.
+pub struct FrameRecorder {
+    stream: String,
+    sender: mpsc::Sender<RecordedFrame>,
+    writer: JoinHandle<()>,
+}
+
+impl FrameRecorder {
+    // Recorder of the given stream, `None` when the stream is not enabled in the config
+    pub fn for_stream(stream: &str) -> Option<Self> {
+        if !CONFIG.default.recorder_streams.iter().any(|enabled| enabled == stream) {
+            return None;
+        }
+        match Self::start(stream, RecorderSettings::from_config()) {
+            Ok(recorder) => {
+                log::info!("Recording {} frames to {}", stream, CONFIG.default.recorder_dir);
+                Some(recorder)
+            }
+            Err(e) => {
+                log::error!("Failed to start the {} recorder: {}", stream, e);
+                None
+            }
+        }
+    }
+
+    pub fn start(stream: &str, settings: RecorderSettings) -> io::Result<Self> {
+        fs::create_dir_all(&settings.dir)?;
+        let (sender, receiver) = mpsc::channel(RECORDER_CHANNEL_CAPACITY);
+        let writer = RotatingWriter::new(stream, settings);
+        let writer = tokio::task::spawn_blocking(move || writer.run(receiver));
+
+        Ok(FrameRecorder {
+            stream: stream.to_string(),
+            sender,
+            writer,
+        })
+    }
+
+    // Record a raw frame stamped with the current time, never blocks
+    pub fn record(&self, frame: &[u8]) {
+        let recorded = RecordedFrame {
+            received_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+            stream: self.stream.clone(),
+            frame: String::from_utf8_lossy(frame).into_owned(),
+        };
+        if self.sender.try_send(recorded).is_err() {
+            RECORDER_DROPPED_FRAMES.with_label_values(&[&self.stream]).inc();
+        }
+    }
+
+    // Stop recording and wait until the queued frames are written and the file is closed
+    pub async fn finish(self) {
+        drop(self.sender);
+        if let Err(e) = self.writer.await {
+            log::error!("Recorder {}: writer failed: {}", self.stream, e);
+        }
+    }
+}
+
+struct OpenFile {
+    writer: Box<dyn Write + Send>,
+    opened_at: Instant,
+    written: u64,
+}
+
+// Writes frames of one stream to `<dir>/<stream>-<opened at ms>-<index>.jsonl[.zst]`,
+// a new file is started once the current one exceeds the size or age limit
+struct RotatingWriter {
+    stream: String,
+    settings: RecorderSettings,
+    file_index: u64,
+    current: Option<OpenFile>,
+}
+
+impl RotatingWriter {
+    fn new(stream: &str, settings: RecorderSettings) -> Self {
+        RotatingWriter {
+            stream: stream.to_string(),
+            settings,
+            file_index: 0,
+            current: None,
+        }
+    }
+
+    fn run(mut self, mut receiver: mpsc::Receiver<RecordedFrame>) {
+        while let Some(frame) = receiver.blocking_recv() {
+            self.write_logged(&frame);
+            // Drain what is already queued and flush once per batch
+            while let Ok(frame) = receiver.try_recv() {
+                self.write_logged(&frame);
+            }
+            if let Some(file) = self.current.as_mut() {
+                if let Err(e) = file.writer.flush() {
+                    log::error!("Recorder {}: failed to flush: {}", self.stream, e);
+                }
+            }
+        }
+        self.close();
+    }
+
+    fn write_logged(&mut self, frame: &RecordedFrame) {
+        if let Err(e) = self.write(frame) {
+            log::error!("Recorder {}: failed to write frame: {}", self.stream, e);
+            // Start over with a new file on the next frame
+            self.current = None;
+        }
+    }
+
+    fn write(&mut self, frame: &RecordedFrame) -> io::Result<()> {
+        let mut line = serde_json::to_vec(frame)?;
+        line.push(b'\n');
+
+        let expired = self.current.as_ref().is_some_and(|file| {
+            file.written >= self.settings.max_file_bytes || file.opened_at.elapsed() >= self.settings.rotate_after
+        });
+        if expired {
+            self.close();
+        }
+
+        if self.current.is_none() {
+            self.current = Some(self.open()?);
+        }
+        let file = self.current.as_mut().expect("recording file is open");
+        file.writer.write_all(&line)?;
+        file.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn open(&mut self) -> io::Result<OpenFile> {
+        let opened_at_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
+        let extension = if self.settings.compress { "jsonl.zst" } else { "jsonl" };
+        let path = self.settings.dir.join(format!("{}-{}-{:06}.{}", self.stream, opened_at_ms, self.file_index, extension));
+        self.file_index += 1;
+
+        let file = BufWriter::new(File::create(&path)?);
+        let writer: Box<dyn Write + Send> = if self.settings.compress {
+            Box::new(zstd::stream::write::Encoder::new(file, COMPRESSION_LEVEL)?.auto_finish())
+        } else {
+            Box::new(file)
+        };
+        log::info!("Recorder {}: writing to {}", self.stream, path.display());
+
+        Ok(OpenFile {
+            writer,
+            opened_at: Instant::now(),
+            written: 0,
+        })
+    }
+
+    // Flush and close the current file, a compressed file is finished when its writer is dropped
+    fn close(&mut self) {
+        if let Some(mut file) = self.current.take() {
+            if let Err(e) = file.writer.flush() {
+                log::error!("Recorder {}: failed to flush: {}", self.stream, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::path::Path;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("oracle_recorder_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn frame(index: u64) -> RecordedFrame {
+        RecordedFrame {
+            received_at: 1_700_000_000_000 + index,
+            stream: "trade".to_string(),
+            frame: format!(r#"{{"stream":"btcfdusd@trade","data":{{"t":{}}}}}"#, index),
+        }
+    }
+
+    // Files sorted by name are in recording order
+    fn read_frames(dir: &Path) -> (usize, Vec<RecordedFrame>) {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        paths.sort();
+
+        let mut frames = Vec::new();
+        for path in paths.iter() {
+            let reader: Box<dyn Read> = if path.to_string_lossy().ends_with(".zst") {
+                Box::new(zstd::stream::read::Decoder::new(File::open(path).unwrap()).unwrap())
+            } else {
+                Box::new(File::open(path).unwrap())
+            };
+            for line in BufReader::new(reader).lines() {
+                frames.push(serde_json::from_str(&line.unwrap()).unwrap());
+            }
+        }
+        (paths.len(), frames)
+    }
+
+    fn settings(dir: &Path, max_file_bytes: u64, compress: bool) -> RecorderSettings {
+        RecorderSettings {
+            dir: dir.to_path_buf(),
+            max_file_bytes,
+            rotate_after: Duration::from_secs(3600),
+            compress,
+        }
+    }
+
+    #[test]
+    fn test_files_are_rotated_by_size() {
+        let dir = test_dir("size");
+        let mut writer = RotatingWriter::new("trade", settings(&dir, 200, false));
+
+        for index in 0..6 {
+            writer.write(&frame(index)).unwrap();
+        }
+        writer.close();
+
+        // Every line is ~100 bytes, so every file holds two frames
+        let (files, frames) = read_frames(&dir);
+        assert_eq!(files, 3);
+        assert_eq!(frames, (0..6).map(frame).collect::<Vec<_>>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_files_are_rotated_by_age() {
+        let dir = test_dir("age");
+        let mut writer = RotatingWriter::new("trade", RecorderSettings {
+            rotate_after: Duration::ZERO,
+            ..settings(&dir, u64::MAX, false)
+        });
+
+        writer.write(&frame(0)).unwrap();
+        writer.write(&frame(1)).unwrap();
+        writer.close();
+
+        assert_eq!(read_frames(&dir).0, 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recorder_writes_compressed_frames() {
+        let dir = test_dir("zstd");
+        let (sender, receiver) = mpsc::channel(RECORDER_CHANNEL_CAPACITY);
+        let writer = RotatingWriter::new("book_ticker", settings(&dir, u64::MAX, true));
+        let writer = tokio::task::spawn_blocking(move || writer.run(receiver));
+        let recorder = FrameRecorder { stream: "book_ticker".to_string(), sender, writer };
+
+        recorder.record(br#"{"stream":"btcfdusd@bookTicker","data":{}}"#);
+        recorder.record(b"not json");
+        // Finishing stops the writer, which finishes the zstd file
+        recorder.finish().await;
+
+        let (files, frames) = read_frames(&dir);
+        assert_eq!(files, 1);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].stream, "book_ticker");
+        assert_eq!(frames[0].frame, r#"{"stream":"btcfdusd@bookTicker","data":{}}"#);
+        assert_eq!(frames[1].frame, "not json");
+        assert!(frames[0].received_at <= frames[1].received_at);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
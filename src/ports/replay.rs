@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Lines, Read};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use crate::config::{OrderBookMode, CONFIG};
+use crate::domain::services::book_ticker_service::BookTickerServiceTrait;
+use crate::domain::services::clock::ReplayClock;
+use crate::domain::services::consolidation_service::ConsolidationService;
+use crate::domain::services::order_book_service::OrderBookServiceTrait;
+use crate::domain::services::trade_history_service::TradeHistoryService;
+use crate::health::HealthRegistry;
+use crate::metrics::{STREAM_BOOK_TICKER, STREAM_ORDER_BOOK, STREAM_TRADE};
+use crate::ports::combined_stream::VENUE_BINANCE;
+use crate::ports::depth_sync::DepthSync;
+use crate::ports::market_data::{MarketDataServices, VenueSink};
+use crate::ports::recorder::RecordedFrame;
+use crate::ports::{ws_client_book_ticker, ws_client_order_book, ws_client_trade};
+
+// Frames read ahead of the replay
+const READ_AHEAD_FRAMES: usize = 1_000;
+
+// Services the replayed frames are applied to
+pub struct ReplayTargets {
+    pub book_ticker_service: Arc<dyn BookTickerServiceTrait + Send + Sync>,
+    pub order_book_service: Arc<dyn OrderBookServiceTrait + Send + Sync>,
+    pub trade_history_service: Arc<TradeHistoryService>,
+    pub consolidation_service: Arc<ConsolidationService>,
+    pub clock: Arc<ReplayClock>,
+    pub health: Arc<HealthRegistry>,
+}
+
+// Replay the recordings from the configured directory until `shutdown` is cancelled, REST and gRPC keep serving the
+// state afterwards
+pub async fn start_replay(targets: ReplayTargets, shutdown: CancellationToken) {
+    let dir = PathBuf::from(&CONFIG.default.replay_dir);
+    log::info!("Replaying recordings from {} at speed {}", dir.display(), CONFIG.default.replay_speed);
+
+    let result = tokio::select! {
+        result = replay(&dir, CONFIG.default.replay_speed, &CONFIG.default.order_book_mode, &targets) => result,
+        _ = shutdown.cancelled() => {
+            log::info!("Replay stopped by the shutdown");
+            return;
+        }
+    };
+    match result {
+        Ok(frames) => log::info!("Replay finished after {} frames", frames),
+        Err(e) => log::error!("Replay of {} failed: {}", dir.display(), e),
+    }
+}
+
+// Feed all frames of the recordings in `dir` through the WebSocket message handlers in receive order
+pub async fn replay(dir: &Path, speed: f64, mode: &OrderBookMode, targets: &ReplayTargets) -> io::Result<u64> {
+    let recording = Recording::open(dir)?;
+    let (sender, mut receiver) = mpsc::channel(READ_AHEAD_FRAMES);
+    tokio::task::spawn_blocking(move || {
+        for frame in recording {
+            if sender.blocking_send(frame).is_err() {
+                break;
+            }
+        }
+    });
+
+    // The recordings are of the Binance streams, the replayed tickers are its quotes
+    let sink = VenueSink::new(VENUE_BINANCE, targets.consolidation_service.clone()).with_services(Arc::new(MarketDataServices::new(
+        targets.order_book_service.clone(),
+        targets.book_ticker_service.clone(),
+        targets.trade_history_service.clone(),
+    )));
+    // Diff events are applied on the depth snapshots recorded with them, the replay never calls the REST API
+    let depth_sync = DepthSync::recorded();
+    let mut pacer = Pacer::new(speed);
+    let mut frames = 0;
+
+    while let Some(frame) = receiver.recv().await {
+        if let Some(deadline) = pacer.deadline(frame.received_at, Instant::now()) {
+            tokio::time::sleep_until(deadline).await;
+        }
+        targets.clock.set(frame.received_at);
+
+        let data = frame.frame.as_bytes();
+        match frame.stream.as_str() {
+            STREAM_BOOK_TICKER => ws_client_book_ticker::handle_message(&sink, &targets.health, data).await,
+            STREAM_ORDER_BOOK => ws_client_order_book::handle_message(&sink, &targets.health, mode, &depth_sync, data, None).await,
+            STREAM_TRADE => ws_client_trade::handle_message(&sink, &targets.health, data).await,
+            other => {
+                log::warn!("Skipping frame of unknown stream {}", other);
+                continue;
+            }
+        }
+        frames += 1;
+    }
+
+    Ok(frames)
+}
+
+// Keeps the recorded gaps between frames, divided by the speed (0 means no waiting at all)
+struct Pacer {
+    speed: f64,
+    start: Option<(u64, Instant)>,
+}
+
+impl Pacer {
+    fn new(speed: f64) -> Self {
+        Pacer { speed, start: None }
+    }
+
+    // When the frame received at `received_at` is due, `None` when it can be applied right away
+    fn deadline(&mut self, received_at: u64, now: Instant) -> Option<Instant> {
+        if self.speed <= 0.0 {
+            return None;
+        }
+        let (first_received_at, started) = *self.start.get_or_insert((received_at, now));
+        let recorded_gap = Duration::from_millis(received_at.saturating_sub(first_received_at));
+        Some(started + recorded_gap.div_f64(self.speed))
+    }
+}
+
+// Frames of all recorded streams merged by their receive time
+struct Recording {
+    streams: Vec<Peekable<StreamFrames>>,
+}
+
+impl Recording {
+    // Recording files are named `<stream>-<opened at ms>-<index>.jsonl[.zst]`, so sorting by name keeps their order
+    fn open(dir: &Path) -> io::Result<Self> {
+        let mut files: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if !(name.ends_with(".jsonl") || name.ends_with(".jsonl.zst")) {
+                continue;
+            }
+            if let Some((stream, _)) = name.split_once('-') {
+                files.entry(stream.to_string()).or_default().push(path);
+            }
+        }
+
+        let streams = files.into_values()
+            .map(|mut paths| {
+                paths.sort();
+                StreamFrames { files: paths.into(), lines: None }.peekable()
+            })
+            .collect();
+        Ok(Recording { streams })
+    }
+}
+
+impl Iterator for Recording {
+    type Item = RecordedFrame;
+
+    fn next(&mut self) -> Option<RecordedFrame> {
+        let earliest = self.streams.iter_mut()
+            .enumerate()
+            .filter_map(|(index, stream)| stream.peek().map(|frame| (frame.received_at, index)))
+            .min()?;
+        self.streams[earliest.1].next()
+    }
+}
+
+// Frames of one stream, read file by file
+struct StreamFrames {
+    files: VecDeque<PathBuf>,
+    lines: Option<Lines<BufReader<Box<dyn Read + Send>>>>,
+}
+
+impl StreamFrames {
+    fn open(path: &Path) -> io::Result<Lines<BufReader<Box<dyn Read + Send>>>> {
+        let file = File::open(path)?;
+        let reader: Box<dyn Read + Send> = if path.to_string_lossy().ends_with(".zst") {
+            Box::new(zstd::stream::read::Decoder::new(file)?)
+        } else {
+            Box::new(file)
+        };
+        Ok(BufReader::new(reader).lines())
+    }
+}
+
+impl Iterator for StreamFrames {
+    type Item = RecordedFrame;
+
+    fn next(&mut self) -> Option<RecordedFrame> {
+        loop {
+            if self.lines.is_none() {
+                let path = self.files.pop_front()?;
+                match Self::open(&path) {
+                    Ok(lines) => self.lines = Some(lines),
+                    Err(e) => {
+                        log::error!("Skipping recording {}: {}", path.display(), e);
+                        continue;
+                    }
+                }
+            }
+
+            match self.lines.as_mut()?.next() {
+                Some(Ok(line)) => match serde_json::from_str::<RecordedFrame>(&line) {
+                    Ok(frame) => return Some(frame),
+                    Err(e) => log::error!("Skipping malformed recorded frame: {}", e),
+                },
+                // A file cut short (e.g. by a crash) ends its part of the recording
+                Some(Err(e)) => {
+                    log::error!("Failed to read recording: {}", e);
+                    self.lines = None;
+                }
+                None => self.lines = None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use rust_decimal_macros::dec;
+    use crate::domain::services::book_ticker_service::BookTickerService;
+    use crate::domain::services::clock::Clock;
+    use crate::domain::services::order_book_service::OrderBookService;
+
+    fn write_recording(dir: &Path, name: &str, frames: &[(u64, &str, String)]) {
+        let mut file = File::create(dir.join(name)).unwrap();
+        for (received_at, stream, frame) in frames {
+            let recorded = RecordedFrame {
+                received_at: *received_at,
+                stream: stream.to_string(),
+                frame: frame.clone(),
+            };
+            writeln!(file, "{}", serde_json::to_string(&recorded).unwrap()).unwrap();
+        }
+    }
+
+    fn ticker_frame(update_id: u64, bid: &str, ask: &str) -> String {
+        format!(r#"{{"stream":"replaytest@bookTicker","data":{{"u":{},"s":"REPLAYTEST","b":"{}","B":"1","a":"{}","A":"1"}}}}"#, update_id, bid, ask)
+    }
+
+    fn trade_frame(trade_id: u64, trade_time: u64, quantity: &str) -> String {
+        format!(r#"{{"stream":"replaytest@trade","data":{{"e":"trade","E":{},"s":"REPLAYTEST","t":{},"p":"100","q":"{}","T":{},"m":true,"M":true}}}}"#,
+            trade_time, trade_id, quantity, trade_time)
+    }
+
+    #[tokio::test]
+    async fn test_replay_applies_frames_in_receive_order() {
+        let dir = std::env::temp_dir().join(format!("oracle_replay_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // Trades recorded a long time ago still count, the window follows the replay clock
+        write_recording(&dir, "book_ticker-1000-000000.jsonl", &[
+            (1_000, STREAM_BOOK_TICKER, ticker_frame(1, "99", "101")),
+            (3_000, STREAM_BOOK_TICKER, ticker_frame(2, "100", "102")),
+        ]);
+        write_recording(&dir, "book_ticker-1000-000001.jsonl", &[
+            (5_000, STREAM_BOOK_TICKER, ticker_frame(3, "101", "103")),
+        ]);
+        write_recording(&dir, "trade-2000-000000.jsonl", &[
+            (2_000, STREAM_TRADE, trade_frame(1, 2_000, "0.5")),
+            (4_000, STREAM_TRADE, trade_frame(2, 4_000, "0.25")),
+        ]);
+
+        let clock = Arc::new(ReplayClock::default());
+        let targets = ReplayTargets {
+            book_ticker_service: Arc::new(BookTickerService::new()),
+            order_book_service: Arc::new(OrderBookService::new()),
+            trade_history_service: Arc::new(TradeHistoryService::with_clock(clock.clone())),
+            consolidation_service: Arc::new(ConsolidationService::with_clock(clock.clone())),
+            clock,
+            health: Arc::new(HealthRegistry::new()),
+        };
+
+        let frames = replay(&dir, 0.0, &OrderBookMode::Partial, &targets).await.unwrap();
+        assert_eq!(frames, 5);
+
+        let ticker = targets.book_ticker_service.get_ticker_data("REPLAYTEST").await.unwrap();
+        assert_eq!(ticker.update_id, 3);
+        assert_eq!(targets.consolidation_service.consolidated("REPLAYTEST").unwrap().price, dec!(102));
+        assert_eq!(targets.trade_history_service.total_volume("REPLAYTEST", 60).await, Some(dec!(0.75)));
+        assert_eq!(targets.clock.now_millis(), 5_000);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn diff_frame(first_update_id: u64, final_update_id: u64, bid: &str) -> String {
+        format!(r#"{{"stream":"replaydiff@depth@100ms","data":{{"e":"depthUpdate","E":0,"s":"REPLAYDIFF","U":{},"u":{},"b":[["{}","1"]],"a":[]}}}}"#,
+            first_update_id, final_update_id, bid)
+    }
+
+    fn depth_snapshot_frame(last_update_id: u64) -> String {
+        format!(r#"{{"stream":"replaydiff@depthSnapshot","data":{{"lastUpdateId":{},"bids":[["100","1"]],"asks":[["101","1"]]}}}}"#,
+            last_update_id)
+    }
+
+    #[tokio::test]
+    async fn test_diff_replay_applies_the_recorded_snapshots() {
+        let dir = std::env::temp_dir().join(format!("oracle_replay_diff_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // As recorded live: every snapshot follows the diff event it was applied with, the gap at 25 forced a resync
+        write_recording(&dir, "order_book-1000-000000.jsonl", &[
+            (1_000, STREAM_ORDER_BOOK, diff_frame(8, 12, "99.5")),
+            (1_000, STREAM_ORDER_BOOK, depth_snapshot_frame(10)),
+            (2_000, STREAM_ORDER_BOOK, diff_frame(13, 15, "100.5")),
+            (3_000, STREAM_ORDER_BOOK, diff_frame(25, 31, "100.9")),
+            (3_000, STREAM_ORDER_BOOK, depth_snapshot_frame(30)),
+        ]);
+
+        let clock = Arc::new(ReplayClock::default());
+        let targets = ReplayTargets {
+            book_ticker_service: Arc::new(BookTickerService::new()),
+            order_book_service: Arc::new(OrderBookService::new()),
+            trade_history_service: Arc::new(TradeHistoryService::with_clock(clock.clone())),
+            consolidation_service: Arc::new(ConsolidationService::with_clock(clock.clone())),
+            clock,
+            health: Arc::new(HealthRegistry::new()),
+        };
+
+        // No snapshot server is running, the snapshots can only come from the recording
+        let frames = replay(&dir, 0.0, &OrderBookMode::Diff, &targets).await.unwrap();
+        assert_eq!(frames, 5);
+
+        let top = targets.order_book_service.get_top_of_book("REPLAYDIFF").await.unwrap();
+        assert_eq!((top.best_bid.price, top.best_ask.price), (dec!(100.9), dec!(101)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pacer_scales_recorded_gaps() {
+        let now = Instant::now();
+
+        let mut real_time = Pacer::new(1.0);
+        assert_eq!(real_time.deadline(10_000, now), Some(now));
+        assert_eq!(real_time.deadline(10_500, now), Some(now + Duration::from_millis(500)));
+
+        let mut accelerated = Pacer::new(10.0);
+        accelerated.deadline(10_000, now);
+        assert_eq!(accelerated.deadline(12_000, now), Some(now + Duration::from_millis(200)));
+
+        let mut as_fast_as_possible = Pacer::new(0.0);
+        assert_eq!(as_fast_as_possible.deadline(10_000, now), None);
+    }
+}
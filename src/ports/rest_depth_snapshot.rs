@@ -0,0 +1,48 @@
+use std::error::Error;
+use binance_spot_connector_rust::market;
+use binance_spot_connector_rust::ureq::BinanceHttpClient;
+use crate::domain::entities::order_book::DepthData;
+
+// Fetch an order book snapshot from <base_url>/api/v3/depth
+pub async fn fetch_depth_snapshot(base_url: &str, symbol: &str, limit: u16) -> Result<DepthData, Box<dyn Error + Send + Sync>> {
+    let client = BinanceHttpClient::with_url(base_url);
+    let request = market::depth(symbol).limit(limit.into());
+
+    // The connector's REST client is blocking, keep it off the async workers
+    let body = tokio::task::spawn_blocking(move || {
+        client.send(request)
+            .and_then(|response| response.into_body_str())
+            .map_err(|e| format!("Depth snapshot request failed: {:?}", e))
+    }).await??;
+
+    Ok(serde_json::from_str::<DepthData>(&body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use warp::Filter;
+
+    #[tokio::test]
+    async fn test_fetch_depth_snapshot_from_local_stand_in() {
+        let depth = warp::path!("api" / "v3" / "depth")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|params: HashMap<String, String>| {
+                assert_eq!(params.get("symbol").map(String::as_str), Some("BTCFDUSD"));
+                assert_eq!(params.get("limit").map(String::as_str), Some("100"));
+                warp::reply::json(&serde_json::json!({
+                    "lastUpdateId": 42,
+                    "bids": [["10000.0", "1.0"]],
+                    "asks": [["10100.0", "2.0"], ["10200.0", "3.0"]]
+                }))
+            });
+        let (addr, server) = warp::serve(depth).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let snapshot = fetch_depth_snapshot(&format!("http://{}", addr), "BTCFDUSD", 100).await.unwrap();
+        assert_eq!(snapshot.last_update_id, 42);
+        assert_eq!(snapshot.bids.len(), 1);
+        assert_eq!(snapshot.asks.len(), 2);
+    }
+}
@@ -0,0 +1,237 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rand::Rng;
+use tokio_util::sync::CancellationToken;
+use crate::config::{DeadFeedPolicy, CONFIG};
+use crate::health::{FeedTaskState, HealthRegistry};
+use crate::metrics::{WS_RECONNECT_ATTEMPTS, WS_RETRY_COUNT};
+use crate::shutdown;
+
+// Exponential delay between connection attempts: doubled after every failure up to `max`,
+// the second half of every delay is random so feeds failing together do not reconnect together
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff { initial, max: max.max(initial) }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(Duration::from_millis(CONFIG.default.ws_backoff_initial_ms), Duration::from_secs(CONFIG.default.ws_backoff_max_secs))
+    }
+
+    // Delay after the `failures`-th failed attempt in a row
+    pub fn delay(&self, failures: u32) -> Duration {
+        self.delay_with_jitter(failures, rand::thread_rng().gen_range(0.0..=1.0))
+    }
+
+    // `jitter` between 0 and 1 picks the delay between half and all of the exponential delay
+    fn delay_with_jitter(&self, failures: u32, jitter: f64) -> Duration {
+        let exponent = failures.saturating_sub(1).min(31);
+        let delay = self.initial.saturating_mul(1 << exponent).min(self.max);
+        delay.div_f64(2.0) + delay.div_f64(2.0).mul_f64(jitter)
+    }
+}
+
+// How a single connection of the feeds ended
+pub type ConnectionResult = Result<(), String>;
+
+// A connection that delivered data but closed sooner than this is reopened after the first backoff delay,
+// so a server that keeps dropping connections is not hammered
+const MIN_HEALTHY_UPTIME: Duration = Duration::from_secs(10);
+
+// Owns the connection loop of every feed: reconnects with exponential backoff, tracks the lifecycle state
+// (connecting, live, backing-off, dead) and applies the dead feed policy after too many failures in a row.
+// A connection returns Ok once it delivered data and then closed, Err when it could not be established or closed
+// before any data (e.g. a refused subscription), which counts as a failure.
+#[derive(Debug, Clone)]
+pub struct FeedSupervisor {
+    backoff: Backoff,
+    max_failures: u32,
+    policy: DeadFeedPolicy,
+    shutdown: CancellationToken,
+    health: Arc<HealthRegistry>,
+}
+
+impl FeedSupervisor {
+    pub fn new(backoff: Backoff, max_failures: u32, policy: DeadFeedPolicy, shutdown: CancellationToken, health: Arc<HealthRegistry>) -> Self {
+        FeedSupervisor { backoff, max_failures: max_failures.max(1), policy, shutdown, health }
+    }
+
+    pub fn from_config(shutdown: CancellationToken, health: Arc<HealthRegistry>) -> Self {
+        Self::new(Backoff::from_config(), CONFIG.default.ws_config_retry_max as u32, CONFIG.default.feed_dead_policy,
+            shutdown, health)
+    }
+
+    // Cancelled when the feeds have to stop, connections close when it is
+    pub fn shutdown(&self) -> &CancellationToken {
+        &self.shutdown
+    }
+
+    // Where the supervised feeds report their lifecycle and updates
+    pub fn health(&self) -> &HealthRegistry {
+        &self.health
+    }
+
+    // Run connections carrying `feeds` one after the other until the shutdown or until they are dead
+    pub async fn supervise<F, Fut>(&self, feeds: &[&str], mut connect: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ConnectionResult>,
+    {
+        let label = feeds.join(",");
+        let mut failures: u32 = 0;
+        let mut first_attempt = true;
+
+        while !self.shutdown.is_cancelled() {
+            if !first_attempt {
+                feeds.iter().for_each(|feed| WS_RECONNECT_ATTEMPTS.with_label_values(&[feed]).inc());
+            }
+            first_attempt = false;
+            feeds.iter().for_each(|feed| self.health.set_state(feed, FeedTaskState::Connecting));
+
+            let connected_at = Instant::now();
+            match connect().await {
+                Ok(()) => {
+                    // The connection delivered data, a drop is not a failure and a long lived one is reopened right away
+                    failures = 0;
+                    feeds.iter().for_each(|feed| WS_RETRY_COUNT.with_label_values(&[feed]).set(0));
+                    if self.shutdown.is_cancelled() {
+                        continue;
+                    }
+                    if connected_at.elapsed() >= MIN_HEALTHY_UPTIME {
+                        log::info!("Feeds {}: connection closed, reconnecting...", label);
+                        continue;
+                    }
+                    let delay = self.backoff.delay(1);
+                    log::warn!("Feeds {}: connection closed after {:?}, reconnecting in {:?}", label, connected_at.elapsed(), delay);
+                    feeds.iter().for_each(|feed| self.health.back_off(feed, delay));
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = self.shutdown.cancelled() => {}
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    for feed in feeds {
+                        WS_RETRY_COUNT.with_label_values(&[feed]).set(failures as i64);
+                        self.health.record_failure(feed, failures, &e);
+                    }
+                    log::error!("Feeds {}: failed to connect: {}. Attempt {}/{}", label, e, failures, self.max_failures);
+
+                    if failures == self.max_failures {
+                        match self.policy {
+                            DeadFeedPolicy::Retry => {
+                                log::error!("Feeds {}: {} failed attempts in a row, still retrying", label, failures);
+                            }
+                            DeadFeedPolicy::Exit => {
+                                log::error!("Feeds {}: dead after {} failed attempts, stopping the application", label, failures);
+                                feeds.iter().for_each(|feed| self.health.set_state(feed, FeedTaskState::Dead));
+                                shutdown::fail(&self.shutdown);
+                                return;
+                            }
+                            DeadFeedPolicy::Unready => {
+                                log::error!("Feeds {}: dead after {} failed attempts, not reconnecting anymore", label, failures);
+                                feeds.iter().for_each(|feed| self.health.set_state(feed, FeedTaskState::Dead));
+                                return;
+                            }
+                        }
+                    }
+
+                    let delay = self.backoff.delay(failures);
+                    feeds.iter().for_each(|feed| self.health.back_off(feed, delay));
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = self.shutdown.cancelled() => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+
+    #[test]
+    fn test_delays_grow_exponentially_up_to_the_maximum() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_secs(1));
+
+        assert_eq!(backoff.delay_with_jitter(1, 1.0), Duration::from_millis(100));
+        assert_eq!(backoff.delay_with_jitter(2, 1.0), Duration::from_millis(200));
+        assert_eq!(backoff.delay_with_jitter(4, 1.0), Duration::from_millis(800));
+        assert_eq!(backoff.delay_with_jitter(5, 1.0), Duration::from_secs(1));
+        assert_eq!(backoff.delay_with_jitter(1_000, 1.0), Duration::from_secs(1));
+        // The jitter only shortens a delay down to its half
+        assert_eq!(backoff.delay_with_jitter(2, 0.0), Duration::from_millis(100));
+        assert!((0..100).all(|_| (Duration::from_millis(50)..=Duration::from_millis(100)).contains(&backoff.delay(1))));
+    }
+
+    #[tokio::test]
+    async fn test_unready_feed_is_dead_after_the_maximum_failures() {
+        let supervisor = FeedSupervisor::new(Backoff::new(Duration::from_millis(1), Duration::from_millis(5)), 3,
+            DeadFeedPolicy::Unready, CancellationToken::new(), Arc::new(HealthRegistry::new()));
+        let attempts = AtomicU32::new(0);
+
+        // A connection that was up does not count as a failure
+        supervisor.supervise(&["trade"], || async {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                1 => Ok(()),
+                _ => Err("connection refused".to_string()),
+            }
+        }).await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 5);
+        let status = supervisor.health().feed_status("trade").unwrap();
+        assert_eq!((status.state, status.failures), (FeedTaskState::Dead, 3));
+        assert_eq!(status.last_error.as_deref(), Some("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_short_lived_connections_are_reopened_after_a_delay() {
+        let shutdown = CancellationToken::new();
+        let supervisor = FeedSupervisor::new(Backoff::new(Duration::from_millis(20), Duration::from_millis(20)), 2,
+            DeadFeedPolicy::Unready, shutdown.clone(), Arc::new(HealthRegistry::new()));
+        let attempts = AtomicU32::new(0);
+        let started = Instant::now();
+
+        // Connections that delivered data are never failures, even when they keep closing right away
+        supervisor.supervise(&["trade"], || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 4 {
+                shutdown.cancel();
+            }
+            Ok(())
+        }).await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 5);
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(supervisor.health().feed_status("trade").unwrap().failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_feed_stops_on_shutdown() {
+        let shutdown = CancellationToken::new();
+        let supervisor = FeedSupervisor::new(Backoff::new(Duration::from_millis(1), Duration::from_millis(5)), 2,
+            DeadFeedPolicy::Retry, shutdown.clone(), Arc::new(HealthRegistry::new()));
+        let attempts = AtomicU32::new(0);
+
+        supervisor.supervise(&["trade"], || async {
+            // Cancelled during the 10th attempt, well after the feed would have been declared dead
+            if attempts.fetch_add(1, Ordering::SeqCst) == 9 {
+                shutdown.cancel();
+            }
+            Err("connection refused".to_string())
+        }).await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 10);
+        assert_eq!(supervisor.health().feed_status("trade").unwrap().failures, 10);
+    }
+}
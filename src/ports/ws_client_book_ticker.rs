@@ -1,70 +1,110 @@
 use binance_spot_connector_rust::market_stream::book_ticker::BookTickerStream;
-use crate::config::CONFIG;
-use binance_spot_connector_rust::tokio_tungstenite::BinanceWebSocketClient;
-use futures_util::StreamExt;
+use crate::domain::entities::symbol::normalize_symbol;
+use binance_spot_connector_rust::websocket::Stream;
+use async_trait::async_trait;
 use log;
-use tokio::time::{sleep, Duration};
-use crate::domain::services::book_ticker_service::{BookTickerService, BookTickerServiceTrait};
+use crate::health::HealthRegistry;
+use crate::ports::combined_stream::StreamFeed;
+use crate::ports::market_data::MarketEventSink;
+use crate::ports::recorder::FrameRecorder;
+use crate::metrics::{STREAM_BOOK_TICKER, WS_EMPTY_ROWS, WS_PARSE_FAILURES};
 use crate::domain::entities::book_ticker::BookTickerSD;
+use crate::domain::entities::market_event::MarketEvent;
 
-pub async fn start_websocket() {
-    let service = BookTickerService;
-    let max_retries = CONFIG.default.ws_config_retry_max;
-    let mut retry_count = 0;
+// Book tickers of the trading pairs on the combined stream
+pub struct BookTickerFeed;
 
-    loop {
-        match BinanceWebSocketClient::connect_async_default().await {
-            Ok((mut conn, _)) => {
-                log::info!("WebSocket: BookTicker connection established.");
+#[async_trait]
+impl StreamFeed for BookTickerFeed {
+    fn name(&self) -> &'static str {
+        STREAM_BOOK_TICKER
+    }
 
-                conn.subscribe(vec![
-                    &BookTickerStream::from_symbol(CONFIG.default.trading_pair.as_str()).into()
-                ]).await;
+    fn streams(&self, trading_pairs: &[String]) -> Vec<Stream> {
+        trading_pairs.iter().map(|pair| BookTickerStream::from_symbol(pair).into()).collect()
+    }
 
-                // Reset retry count on successful connection
-                retry_count = 0;
+    async fn handle_frame(&self, frame: &[u8], sink: &dyn MarketEventSink, health: &HealthRegistry, _recorder: Option<&FrameRecorder>) {
+        handle_message(sink, health, frame).await;
+    }
+}
 
-                while let Some(message) = conn.as_mut().next().await {
-                    match message {
-                        Ok(message) => {
-                            let binary_data = message.into_data();
-                            if let Ok(data) = std::str::from_utf8(&binary_data) {
-                                //log::info!("DATA {}",data);
-                               if !data.contains(":null") {
-                                   if let Ok(result) = serde_json::from_str::<BookTickerSD>(data.trim()){
-                                       service.update_ticker(result).await;
-                                       //service.print_ticker().await;
-                                   }else {
-                                     log::error!("Failed to parse StreamData from JSON: {}", data);
-                                   }
-                                } else {
-                                    log::info!("Empty row: {}", data);
-                                }
-                            } else {
-                                log::error!("Failed to parse message to utf8");
-                            }
-                        }
-                        Err(e) => {
-                            log::error!("Error receiving message: {}", e);
-                            break;
-                        }
-                    }
-                }
+// Parse a raw frame and publish its top of book, shared by the live stream and the replay
+pub async fn handle_message(sink: &dyn MarketEventSink, health: &HealthRegistry, binary_data: &[u8]) {
+    if let Ok(data) = std::str::from_utf8(binary_data) {
+        //log::info!("DATA {}",data);
+       if !data.contains(":null") {
+           if let Ok(result) = serde_json::from_str::<BookTickerSD>(data.trim()){
+               let symbol = normalize_symbol(&result.data.symbol);
+               match sink.publish(&MarketEvent::TopOfBook(result.data.to_top_of_book())).await {
+                   Ok(()) => health.record_update(STREAM_BOOK_TICKER, &symbol),
+                   Err(e) => log::error!("Failed to apply the book ticker of {}: {}", symbol, e),
+               }
+               //service.print_ticker().await;
+           }else {
+             WS_PARSE_FAILURES.with_label_values(&[STREAM_BOOK_TICKER]).inc();
+             log::error!("Failed to parse StreamData from JSON: {}", data);
+           }
+        } else {
+            WS_EMPTY_ROWS.with_label_values(&[STREAM_BOOK_TICKER]).inc();
+            log::info!("Empty row: {}", data);
+        }
+    } else {
+        WS_PARSE_FAILURES.with_label_values(&[STREAM_BOOK_TICKER]).inc();
+        log::error!("Failed to parse message to utf8");
+    }
+}
 
-                log::info!("Connection closed. Reconnecting...");
-            }
-            Err(e) => {
-                retry_count += 1;
-                log::error!("Failed to connect to WebSocket: {}. Retry {}/{}", e, retry_count, max_retries);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tonic::Request;
+    use crate::adapters::proto::book_ticker_proto::book_ticker_proto_service_server::BookTickerProtoService;
+    use crate::adapters::proto::book_ticker_proto::SymbolRequest;
+    use crate::adapters::proto::book_ticker_proto_service::MyBookTickerService;
+    use crate::adapters::rest::book_ticker_api::create_book_ticker_rest_api;
+    use tokio_util::sync::CancellationToken;
+    use crate::ports::combined_stream::CombinedStream;
+    use crate::ports::market_data::MarketDataSource;
+    use crate::ports::mock_binance::{eventually, test_services, test_supervisor, MockBinanceServer, MockFrame};
 
-                if retry_count >= max_retries {
-                    log::error!("Max retries reached. Exiting...");
-                    break;
-                }
+    #[tokio::test]
+    async fn test_tickers_survive_bad_frames_and_disconnects() {
+        let server = MockBinanceServer::start(vec![
+            vec![
+                MockFrame::Raw("not json".to_string()),
+                MockFrame::null_data("e2ebt@bookTicker"),
+                MockFrame::book_ticker("E2EBT", 1, "99", "101"),
+                MockFrame::Disconnect,
+            ],
+            vec![MockFrame::book_ticker("E2EBT", 2, "100", "102.5")],
+        ]);
+        let services = test_services();
+        let service = services.book_ticker.clone();
+        let client = tokio::spawn({
+            let ingestion = CombinedStream::new(&server.url(), &["E2EBT".to_string()])
+                .with_feed(Arc::new(BookTickerFeed));
+            let sink = services.clone();
+            async move { ingestion.run(sink.as_ref(), &test_supervisor(CancellationToken::new())).await }
+        });
 
-                // Wait before attempting to reconnect
-                sleep(Duration::from_secs(5)).await;
-            }
-        }
+        eventually("the ticker from the second connection", || async {
+            service.get_ticker_data("E2EBT").await.is_some_and(|ticker| ticker.update_id == 2)
+        }).await;
+        client.abort();
+
+        assert_eq!(server.connections(), 2);
+        assert_eq!(server.subscriptions()[0], vec!["e2ebt@bookTicker".to_string()]);
+
+        let api = create_book_ticker_rest_api(service.clone());
+        let response = warp::test::request().path("/bookticker/E2EBT/midprice").reply(&api).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["mid_price"], "101.250");
+
+        let grpc = MyBookTickerService::new(service);
+        let mid_price = grpc.get_mid_price(Request::new(SymbolRequest { symbol: "E2EBT".to_string() })).await.unwrap();
+        assert_eq!(mid_price.into_inner().mid_price, "101.250");
     }
-}
\ No newline at end of file
+}
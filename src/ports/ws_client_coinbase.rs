@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::Message;
+use crate::config::CONFIG;
+use crate::domain::entities::market_event::{MarketEvent, TopOfBook};
+use crate::domain::entities::order_book::OrderBookEntry;
+use crate::domain::entities::symbol::VenueProduct;
+use crate::health::{FeedTaskState, HealthRegistry};
+use crate::metrics::{STREAM_COINBASE_TICKER, WS_MESSAGES_RECEIVED, WS_PARSE_FAILURES};
+use crate::ports::market_data::{MarketDataSource, MarketEventSink};
+use crate::ports::supervisor::{ConnectionResult, FeedSupervisor};
+
+// Messages of the Advanced Trade WebSocket: {"channel": ..., "sequence_num": ..., "events": [...]} for the ticker,
+// subscriptions and heartbeats channels, {"type": "error", "message": ...} when a request is refused
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CoinbaseMessage {
+    Channel {
+        channel: String,
+        #[serde(default)]
+        sequence_num: u64,
+        #[serde(default)]
+        events: Vec<ChannelEvent>,
+    },
+    Error { message: String },
+}
+
+#[derive(Debug, Deserialize)]
+struct ChannelEvent {
+    #[serde(default)]
+    tickers: Vec<CoinbaseTicker>,
+}
+
+// Ticker of a product, sent as a snapshot after subscribing and then on every trade
+#[derive(Debug, Deserialize)]
+struct CoinbaseTicker {
+    product_id: String,
+    best_bid: Decimal,
+    best_bid_quantity: Decimal,
+    best_ask: Decimal,
+    best_ask_quantity: Decimal,
+}
+
+// The Coinbase Advanced Trade market data source: the ticker channel of the configured products is published as
+// the top of book of the trading pairs they are mapped to. The ticker time is not used, so `event_time` is `None`.
+pub struct CoinbaseSource {
+    ws_url: String,
+    symbols: HashMap<String, String>, // Trading pair by product id
+}
+
+impl CoinbaseSource {
+    pub fn new(ws_url: &str, products: &[VenueProduct]) -> Self {
+        CoinbaseSource {
+            ws_url: ws_url.to_string(),
+            symbols: products.iter().map(|product| (product.product_id.clone(), product.symbol.clone())).collect(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(&CONFIG.default.coinbase_ws_url, &CONFIG.default.coinbase_products)
+    }
+
+    // Subscription to the ticker channel of every product (public, no authentication needed)
+    fn subscription(&self) -> String {
+        let mut product_ids: Vec<&String> = self.symbols.keys().collect();
+        product_ids.sort();
+        serde_json::json!({"type": "subscribe", "product_ids": product_ids, "channel": "ticker"}).to_string()
+    }
+
+    // Publish the messages of a single connection until it drops or the shutdown, Err when it cannot be established
+    async fn connect(&self, sink: &dyn MarketEventSink, supervisor: &FeedSupervisor) -> ConnectionResult {
+        let (shutdown, health) = (supervisor.shutdown(), supervisor.health());
+        let (mut conn, _) = tokio_tungstenite::connect_async(self.ws_url.as_str()).await.map_err(|e| e.to_string())?;
+        conn.send(Message::Text(self.subscription())).await.map_err(|e| e.to_string())?;
+        log::info!("WebSocket: Coinbase connection established for {} products.", self.symbols.len());
+
+        // The feed is live once a ticker arrived, not as soon as it is subscribed
+        let mut live = false;
+        while let Some(message) = tokio::select! {
+            message = conn.next() => message,
+            _ = shutdown.cancelled() => None,
+        } {
+            match message {
+                Ok(Message::Text(text)) => {
+                    if self.handle_message(sink, health, text.as_bytes()).await && !live {
+                        live = true;
+                        health.set_state(STREAM_COINBASE_TICKER, FeedTaskState::Live);
+                    }
+                }
+                Ok(Message::Close(_)) => break,
+                Ok(_) => {}
+                Err(e) => {
+                    log::error!("Error receiving Coinbase message: {}", e);
+                    break;
+                }
+            }
+        }
+
+        if shutdown.is_cancelled() {
+            // Close the connection cleanly instead of dropping it
+            if let Err(e) = conn.close(None).await {
+                log::warn!("Failed to close the Coinbase WebSocket connection: {}", e);
+            }
+        } else if !live {
+            return Err("connection closed before any ticker was received".to_string());
+        }
+        Ok(())
+    }
+
+    // Parse a raw message and publish the top of book of every ticker it carries, true for a ticker message
+    async fn handle_message(&self, sink: &dyn MarketEventSink, health: &HealthRegistry, binary_data: &[u8]) -> bool {
+        WS_MESSAGES_RECEIVED.with_label_values(&[STREAM_COINBASE_TICKER]).inc();
+        match serde_json::from_slice::<CoinbaseMessage>(binary_data) {
+            Ok(CoinbaseMessage::Channel { channel, sequence_num, events }) if channel == "ticker" => {
+                for ticker in events.into_iter().flat_map(|event| event.tickers) {
+                    let Some(symbol) = self.symbols.get(&ticker.product_id) else {
+                        log::warn!("Ticker of the unsubscribed product {}", ticker.product_id);
+                        continue;
+                    };
+                    let top = TopOfBook {
+                        symbol: symbol.clone(),
+                        update_id: sequence_num,
+                        best_bid: OrderBookEntry { price: ticker.best_bid, qty: ticker.best_bid_quantity },
+                        best_ask: OrderBookEntry { price: ticker.best_ask, qty: ticker.best_ask_quantity },
+                        event_time: None,
+                    };
+                    match sink.publish(&MarketEvent::TopOfBook(top)).await {
+                        Ok(()) => health.record_update(STREAM_COINBASE_TICKER, symbol),
+                        Err(e) => log::error!("Failed to apply the Coinbase ticker of {}: {}", symbol, e),
+                    }
+                }
+                return true;
+            }
+            Ok(CoinbaseMessage::Channel { channel, .. }) => {
+                log::debug!("Coinbase {} message: {}", channel, String::from_utf8_lossy(binary_data));
+            }
+            Ok(CoinbaseMessage::Error { message }) => log::error!("Coinbase refused a request: {}", message),
+            Err(e) => {
+                WS_PARSE_FAILURES.with_label_values(&[STREAM_COINBASE_TICKER]).inc();
+                log::error!("Failed to parse Coinbase message: {}: {}", e, String::from_utf8_lossy(binary_data));
+            }
+        }
+        false
+    }
+}
+
+#[async_trait]
+impl MarketDataSource for CoinbaseSource {
+    fn name(&self) -> &'static str {
+        "coinbase"
+    }
+
+    // Run the connection under the supervisor until the shutdown (or until it is dead)
+    async fn run(&self, sink: &dyn MarketEventSink, supervisor: &FeedSupervisor) {
+        supervisor.health().set_secondary(STREAM_COINBASE_TICKER);
+        supervisor.health().expect_updates(STREAM_COINBASE_TICKER, &self.symbols.values().cloned().collect::<Vec<_>>());
+        supervisor.supervise(&[STREAM_COINBASE_TICKER], || self.connect(sink, supervisor)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use rust_decimal_macros::dec;
+    use serde_json::json;
+    use tokio::task::JoinHandle;
+    use tokio_util::sync::CancellationToken;
+    use warp::ws::WebSocket;
+    use warp::Filter;
+    use crate::domain::services::consolidation_service::ConsolidationService;
+    use crate::ports::market_data::VenueSink;
+    use crate::ports::mock_binance::{eventually, test_supervisor};
+
+    // Local stand-in for the Advanced Trade endpoint: remembers the subscription, then plays the messages
+    struct MockCoinbaseServer {
+        addr: SocketAddr,
+        subscriptions: Arc<Mutex<Vec<serde_json::Value>>>,
+        server: JoinHandle<()>,
+    }
+
+    impl MockCoinbaseServer {
+        fn start(messages: Vec<String>) -> Self {
+            let subscriptions = Arc::new(Mutex::new(Vec::new()));
+            let state = (subscriptions.clone(), messages);
+            let routes = warp::ws().map(move |ws: warp::ws::Ws| {
+                let (subscriptions, messages) = state.clone();
+                ws.on_upgrade(move |socket| serve_connection(socket, subscriptions, messages))
+            });
+            let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+            MockCoinbaseServer { addr, subscriptions, server: tokio::spawn(server) }
+        }
+
+        fn url(&self) -> String {
+            format!("ws://{}", self.addr)
+        }
+    }
+
+    impl Drop for MockCoinbaseServer {
+        fn drop(&mut self) {
+            self.server.abort();
+        }
+    }
+
+    async fn serve_connection(mut socket: WebSocket, subscriptions: Arc<Mutex<Vec<serde_json::Value>>>, messages: Vec<String>) {
+        let Some(Ok(request)) = socket.next().await else { return };
+        subscriptions.lock().unwrap().push(serde_json::from_str(request.to_str().unwrap_or_default()).unwrap_or_default());
+        for message in messages {
+            if socket.send(warp::ws::Message::text(message)).await.is_err() {
+                return;
+            }
+        }
+        while let Some(Ok(_)) = socket.next().await {}
+    }
+
+    fn ticker_message(sequence_num: u64, product_id: &str, bid: &str, ask: &str) -> String {
+        json!({
+            "channel": "ticker", "client_id": "", "timestamp": "2024-01-01T00:00:00.000000000Z", "sequence_num": sequence_num,
+            "events": [{"type": "update", "tickers": [{
+                "type": "ticker", "product_id": product_id, "price": bid, "volume_24_h": "1000",
+                "best_bid": bid, "best_bid_quantity": "2", "best_ask": ask, "best_ask_quantity": "3"
+            }]}]
+        }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_tickers_of_the_products_are_quoted_for_their_trading_pairs() {
+        let server = MockCoinbaseServer::start(vec![
+            json!({"channel": "subscriptions", "events": [{"subscriptions": {"ticker": ["CB-USD"]}}]}).to_string(),
+            json!({"type": "error", "message": "rate limited"}).to_string(),
+            "not json".to_string(),
+            ticker_message(1, "OTHER-USD", "1", "2"),
+            ticker_message(2, "CB-USD", "99.5", "100.5"),
+        ]);
+        let products = vec!["E2ECOINBASE:cb-usd".parse::<VenueProduct>().unwrap()];
+        let consolidation = Arc::new(ConsolidationService::new());
+        let client = tokio::spawn({
+            let (source, sink) = (CoinbaseSource::new(&server.url(), &products), VenueSink::new("coinbase", consolidation.clone()));
+            async move { source.run(&sink, &test_supervisor(CancellationToken::new())).await }
+        });
+
+        eventually("the Coinbase quote", || async { consolidation.consolidated("E2ECOINBASE").is_ok() }).await;
+        client.abort();
+
+        let price = consolidation.consolidated("E2ECOINBASE").unwrap();
+        assert_eq!(price.price, dec!(100));
+        assert_eq!((price.quotes[0].venue.as_str(), price.quotes[0].best_ask.qty), ("coinbase", dec!(3)));
+        assert!(consolidation.quotes("OTHER-USD").is_empty());
+        assert_eq!(server.subscriptions.lock().unwrap()[0], json!({"type": "subscribe", "product_ids": ["CB-USD"], "channel": "ticker"}));
+    }
+}
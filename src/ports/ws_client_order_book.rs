@@ -1,72 +1,163 @@
-use crate::domain::services::order_book_service::{OrderBookService, OrderBookServiceTrait};
-use crate::domain::entities::order_book::OrderBookSD;
-use crate::config::CONFIG;
+use crate::domain::entities::market_event::MarketEvent;
+use crate::domain::entities::order_book::{DiffDepthSD, OrderBookSD};
+use crate::domain::entities::symbol::{normalize_symbol, symbol_from_stream};
+use crate::config::{OrderBookMode, CONFIG};
+use crate::ports::depth_sync::{DepthSync, DEPTH_SNAPSHOT_STREAM};
+use binance_spot_connector_rust::market_stream::diff_depth::DiffDepthStream;
 use binance_spot_connector_rust::market_stream::partial_depth::PartialDepthStream;
-use binance_spot_connector_rust::tokio_tungstenite::BinanceWebSocketClient;
-use futures_util::StreamExt;
+use binance_spot_connector_rust::websocket::Stream;
+use async_trait::async_trait;
 use log;
-use tokio::time::{sleep, Duration};
+use crate::health::HealthRegistry;
+use crate::ports::combined_stream::StreamFeed;
+use crate::ports::market_data::MarketEventSink;
+use crate::ports::recorder::FrameRecorder;
+use crate::metrics::{STREAM_ORDER_BOOK, WS_EMPTY_ROWS, WS_PARSE_FAILURES};
 
-pub async fn start_websocket() {
-    let service = OrderBookService;
-    let max_retries = CONFIG.default.ws_config_retry_max; // Maximum retries for reconnect
-    let mut retry_count = 0;
+// Partial or diff depth of the trading pairs on the combined stream
+pub struct OrderBookFeed {
+    mode: OrderBookMode,
+    depth_sync: DepthSync,
+}
 
-    loop {
-        match BinanceWebSocketClient::connect_async_default().await {
-            Ok((mut conn, _)) => {
-                log::info!("WebSocket: OrderBook connection established.");
+impl OrderBookFeed {
+    pub fn new(mode: OrderBookMode) -> Self {
+        OrderBookFeed {
+            mode,
+            depth_sync: DepthSync::new(&CONFIG.default.depth_snapshot_url, CONFIG.default.depth_snapshot_limit),
+        }
+    }
+}
+
+#[async_trait]
+impl StreamFeed for OrderBookFeed {
+    fn name(&self) -> &'static str {
+        STREAM_ORDER_BOOK
+    }
 
-                conn.subscribe(vec![
-                    &PartialDepthStream::from_100ms(
-                        CONFIG.default.trading_pair.as_str(),
-                        CONFIG.default.book_depth
-                    ).into()
-                ]).await;
+    fn streams(&self, trading_pairs: &[String]) -> Vec<Stream> {
+        trading_pairs.iter()
+            .map(|pair| match self.mode {
+                OrderBookMode::Partial => PartialDepthStream::from_100ms(pair, CONFIG.default.book_depth).into(),
+                OrderBookMode::Diff => DiffDepthStream::from_100ms(pair).into(),
+            })
+            .collect()
+    }
 
-                // Reset retry count on successful connection
-                retry_count = 0;
+    // Events missed while disconnected invalidate the local books of the pairs on this connection
+    async fn connected(&self, trading_pairs: &[String], sink: &dyn MarketEventSink) {
+        self.depth_sync.reset(sink, trading_pairs).await;
+    }
 
-                while let Some(message) = conn.as_mut().next().await {
-                    match message {
-                        Ok(message) => {
-                            let binary_data = message.into_data();
-                            if let Ok(data) = std::str::from_utf8(&binary_data) {
-                                if !data.contains(":null") {
-                                    if let Ok(result) = serde_json::from_str::<OrderBookSD>(data.trim()) {
-                                        service.update_order_book(result).await;
-                                        //service.print_top_of_book().await;
-                                    } else {
-                                        log::error!("Failed to parse StreamData from JSON: {}", data);
-                                    }
-                                } else {
-                                    log::info!("Empty row: {}", data);
-                                }
-                            } else {
-                                log::error!("Failed to parse message to utf8");
-                            }
+    async fn handle_frame(&self, frame: &[u8], sink: &dyn MarketEventSink, health: &HealthRegistry, recorder: Option<&FrameRecorder>) {
+        handle_message(sink, health, &self.mode, &self.depth_sync, frame, recorder).await;
+    }
+}
+
+// Parse a raw frame and publish its book snapshot or delta, shared by the live stream and the replay.
+// In diff mode the depth snapshots applied by `depth_sync` are written to `recorder`.
+pub async fn handle_message(sink: &dyn MarketEventSink, health: &HealthRegistry, mode: &OrderBookMode, depth_sync: &DepthSync,
+                            binary_data: &[u8], recorder: Option<&FrameRecorder>) {
+    if let Ok(data) = std::str::from_utf8(binary_data) {
+        if !data.contains(":null") {
+            match mode {
+                OrderBookMode::Partial => {
+                    if let Ok(result) = serde_json::from_str::<OrderBookSD>(data.trim()) {
+                        let symbol = symbol_from_stream(&result.stream);
+                        match sink.publish(&MarketEvent::BookSnapshot(result.to_snapshot())).await {
+                            Ok(()) => health.record_update(STREAM_ORDER_BOOK, &symbol),
+                            Err(e) => log::error!("Failed to apply the order book of {}: {}", symbol, e),
+                        }
+                        //service.print_top_of_book().await;
+                    } else {
+                        WS_PARSE_FAILURES.with_label_values(&[STREAM_ORDER_BOOK]).inc();
+                        log::error!("Failed to parse StreamData from JSON: {}", data);
+                    }
+                }
+                OrderBookMode::Diff => {
+                    if let Ok(result) = serde_json::from_str::<DiffDepthSD>(data.trim()) {
+                        let symbol = normalize_symbol(&result.data.symbol);
+                        if depth_sync.handle_event(sink, result.data.to_delta(), recorder).await {
+                            health.record_update(STREAM_ORDER_BOOK, &symbol);
                         }
-                        Err(e) => {
-                            log::error!("Error receiving message: {}", e);
-                            break;
+                    } else if let Some(snapshot) = serde_json::from_str::<OrderBookSD>(data.trim()).ok()
+                        .filter(|snapshot| snapshot.stream.ends_with(DEPTH_SNAPSHOT_STREAM)) {
+                        // A depth snapshot of the recording, applied in place of the REST snapshot
+                        let symbol = symbol_from_stream(&snapshot.stream);
+                        if depth_sync.handle_snapshot(sink, &symbol, snapshot.data).await {
+                            health.record_update(STREAM_ORDER_BOOK, &symbol);
                         }
+                    } else {
+                        WS_PARSE_FAILURES.with_label_values(&[STREAM_ORDER_BOOK]).inc();
+                        log::error!("Failed to parse DiffDepthSD from JSON: {}", data);
                     }
                 }
-
-                log::info!("WebSocket: OrderBook Connection closed. Reconnecting...");
             }
-            Err(e) => {
-                retry_count += 1;
-                log::error!("Failed to connect to WebSocket: {}. Retry {}/{}", e, retry_count, max_retries);
+        } else {
+            WS_EMPTY_ROWS.with_label_values(&[STREAM_ORDER_BOOK]).inc();
+            log::info!("Empty row: {}", data);
+        }
+    } else {
+        WS_PARSE_FAILURES.with_label_values(&[STREAM_ORDER_BOOK]).inc();
+        log::error!("Failed to parse message to utf8");
+    }
+}
 
-                if retry_count >= max_retries {
-                    log::error!("Max retries reached. Exiting...");
-                    break;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tonic::Request;
+    use crate::adapters::proto::order_book_proto::order_book_proto_service_server::OrderBookProtoService;
+    use crate::adapters::proto::order_book_proto::SymbolRequest;
+    use crate::adapters::proto::order_book_proto_service::MyOrderBookService;
+    use crate::adapters::rest::order_book_api::create_order_book_api;
+    use tokio_util::sync::CancellationToken;
+    use crate::ports::combined_stream::CombinedStream;
+    use crate::ports::market_data::MarketDataSource;
+    use crate::ports::mock_binance::{eventually, test_services, test_supervisor, MockBinanceServer, MockFrame};
 
-                // Wait before attempting to reconnect
-                sleep(Duration::from_secs(5)).await;
-            }
-        }
+    #[tokio::test]
+    async fn test_partial_depth_is_served_over_rest_and_grpc() {
+        let levels = CONFIG.default.book_depth;
+        let server = MockBinanceServer::start(vec![
+            vec![
+                MockFrame::partial_depth("E2EOB", levels, 1, &[("99", "1")], &[("101", "1")]),
+                MockFrame::Raw("{".to_string()),
+                MockFrame::Disconnect,
+            ],
+            vec![
+                MockFrame::null_data(&format!("e2eob@depth{}@100ms", levels)),
+                MockFrame::partial_depth("E2EOB", levels, 2, &[("100.5", "2"), ("100", "3")], &[("101.5", "4")]),
+            ],
+        ]);
+        let services = test_services();
+        let service = services.order_book.clone();
+        let client = tokio::spawn({
+            let ingestion = CombinedStream::new(&server.url(), &["E2EOB".to_string()])
+                .with_feed(Arc::new(OrderBookFeed::new(OrderBookMode::Partial)));
+            let sink = services.clone();
+            async move { ingestion.run(sink.as_ref(), &test_supervisor(CancellationToken::new())).await }
+        });
+
+        eventually("the book from the second connection", || async {
+            service.get_top_of_book("E2EOB").await.is_some_and(|top| top.best_ask.price.to_string() == "101.5")
+        }).await;
+        client.abort();
+
+        assert_eq!(server.connections(), 2);
+        assert_eq!(server.subscriptions()[0], vec![format!("e2eob@depth{}@100ms", levels)]);
+
+        let api = create_order_book_api(service.clone());
+        let response = warp::test::request().path("/orderbook/E2EOB/top").reply(&api).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["best_bid_price"], "100.5");
+        assert_eq!(body["best_ask_qty"], "4");
+
+        let grpc = MyOrderBookService::new(service);
+        let full_book = grpc.get_full_book(Request::new(SymbolRequest { symbol: "E2EOB".to_string() })).await.unwrap().into_inner();
+        assert_eq!(full_book.bids.len(), 2);
+        assert_eq!(full_book.asks[0].price, "101.5");
     }
-}
\ No newline at end of file
+}
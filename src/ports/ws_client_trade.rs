@@ -1,69 +1,146 @@
 use binance_spot_connector_rust::market_stream::trade::TradeStream;
-use crate::config::CONFIG;
-use binance_spot_connector_rust::tokio_tungstenite::BinanceWebSocketClient;
-use futures_util::StreamExt;
+use crate::domain::entities::symbol::normalize_symbol;
+use binance_spot_connector_rust::websocket::Stream;
+use async_trait::async_trait;
 use log;
-use tokio::time::{sleep, Duration};
-use crate::domain::services::trade_history_service::TradeHistoryService;
+use crate::health::HealthRegistry;
+use crate::ports::combined_stream::StreamFeed;
+use crate::ports::market_data::MarketEventSink;
+use crate::ports::recorder::FrameRecorder;
+use crate::metrics::{STREAM_TRADE, WS_EMPTY_ROWS, WS_PARSE_FAILURES};
+use crate::domain::entities::market_event::MarketEvent;
 use crate::domain::entities::trade::{TradeSD};
 
-pub async fn start_websocket() {
-    let service = TradeHistoryService;
-    let max_retries = CONFIG.default.ws_config_retry_max;
-    let mut retry_count = 0;
+// Trades of the trading pairs on the combined stream
+pub struct TradeFeed;
 
-    loop {
-        match BinanceWebSocketClient::connect_async_default().await {
-            Ok((mut conn, _)) => {
-                log::info!("WebSocket: TradeStream connection established.");
+#[async_trait]
+impl StreamFeed for TradeFeed {
+    fn name(&self) -> &'static str {
+        STREAM_TRADE
+    }
 
-                conn.subscribe(vec![
-                    &TradeStream::new(CONFIG.default.trading_pair.as_str()).into()
-                ]).await;
+    fn streams(&self, trading_pairs: &[String]) -> Vec<Stream> {
+        trading_pairs.iter().map(|pair| TradeStream::new(pair).into()).collect()
+    }
 
-                // Reset retry count on successful connection
-                retry_count = 0;
+    async fn handle_frame(&self, frame: &[u8], sink: &dyn MarketEventSink, health: &HealthRegistry, _recorder: Option<&FrameRecorder>) {
+        handle_message(sink, health, frame).await;
+    }
+}
 
-                while let Some(message) = conn.as_mut().next().await {
-                    match message {
-                        Ok(message) => {
-                            let binary_data = message.into_data();
-                            if let Ok(data) = std::str::from_utf8(&binary_data) {
-                                //log::info!("DATA {}",data);
-                                if !data.contains(":null") {
-                                    if let Ok(result) = serde_json::from_str::<TradeSD>(data.trim()){
-                                        service.add_trade(result).await;
-                                    } else {
-                                        log::error!("Failed to parse TradeSD from JSON: {}", data);
-                                    }
-                                } else {
-                                    log::info!("Empty row: {}", data);
-                                }
-                            } else {
-                                log::error!("Failed to parse message to utf8");
-                            }
-                        }
-                        Err(e) => {
-                            log::error!("Error receiving message: {}", e);
-                            break;
-                        }
-                    }
+// Parse a raw frame and publish its trade, shared by the live stream and the replay
+pub async fn handle_message(sink: &dyn MarketEventSink, health: &HealthRegistry, binary_data: &[u8]) {
+    if let Ok(data) = std::str::from_utf8(binary_data) {
+        //log::info!("DATA {}",data);
+        if !data.contains(":null") {
+            if let Ok(result) = serde_json::from_str::<TradeSD>(data.trim()){
+                let symbol = normalize_symbol(&result.data.symbol);
+                match sink.publish(&MarketEvent::Trade(result.data.to_event())).await {
+                    Ok(()) => health.record_update(STREAM_TRADE, &symbol),
+                    Err(e) => log::error!("Failed to apply the trade of {}: {}", symbol, e),
                 }
-
-                log::info!("Connection closed. Reconnecting...");
+            } else {
+                WS_PARSE_FAILURES.with_label_values(&[STREAM_TRADE]).inc();
+                log::error!("Failed to parse TradeSD from JSON: {}", data);
             }
-            Err(e) => {
-                retry_count += 1;
-                log::error!("Failed to connect to WebSocket: {}. Retry {}/{}", e, retry_count, max_retries);
+        } else {
+            WS_EMPTY_ROWS.with_label_values(&[STREAM_TRADE]).inc();
+            log::info!("Empty row: {}", data);
+        }
+    } else {
+        WS_PARSE_FAILURES.with_label_values(&[STREAM_TRADE]).inc();
+        log::error!("Failed to parse message to utf8");
+    }
+}
 
-                if retry_count >= max_retries {
-                    log::error!("Max retries reached. Exiting...");
-                    break;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tonic::Request;
+    use crate::adapters::proto::trade_history_proto::trade_history_proto_service_server::TradeHistoryProtoService;
+    use crate::adapters::proto::trade_history_proto::WindowRequest;
+    use crate::adapters::proto::trade_history_proto_service::MyTradeHistoryService;
+    use crate::adapters::rest::trade_history_rest::create_trade_history_rest_api;
+    use crate::domain::services::clock::{Clock, SystemClock};
+    use tokio_util::sync::CancellationToken;
+    use crate::ports::combined_stream::CombinedStream;
+    use crate::ports::market_data::MarketDataSource;
+    use crate::ports::mock_binance::{eventually, test_services, test_supervisor, MockBinanceServer, MockFrame};
 
-                // Wait before attempting to reconnect
-                sleep(Duration::from_secs(5)).await;
-            }
-        }
+    #[tokio::test]
+    async fn test_trades_are_served_over_rest_and_grpc() {
+        let now = SystemClock.now_millis();
+        let server = MockBinanceServer::start(vec![
+            vec![
+                MockFrame::trade("E2ETR", 1, "100", "0.5", now),
+                MockFrame::Raw(r#"{"stream":"e2etr@trade","data":{"e":"trade"}}"#.to_string()),
+                MockFrame::Disconnect,
+            ],
+            vec![
+                MockFrame::null_data("e2etr@trade"),
+                MockFrame::trade("E2ETR", 2, "101", "0.25", now),
+            ],
+        ]);
+        let services = test_services();
+        let service = services.trade_history.clone();
+        let client = tokio::spawn({
+            let ingestion = CombinedStream::new(&server.url(), &["E2ETR".to_string()])
+                .with_feed(Arc::new(TradeFeed));
+            let sink = services.clone();
+            async move { ingestion.run(sink.as_ref(), &test_supervisor(CancellationToken::new())).await }
+        });
+
+        eventually("both trades", || async {
+            service.history_len("E2ETR").await == Some(2)
+        }).await;
+        client.abort();
+
+        assert_eq!(server.connections(), 2);
+        assert_eq!(server.subscriptions()[1], vec!["e2etr@trade".to_string()]);
+
+        let api = create_trade_history_rest_api(service.clone());
+        let response = warp::test::request().path("/tradehistory/E2ETR/total_volume").reply(&api).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["total_volume"], "0.75");
+
+        let response = warp::test::request().path("/tradehistory/E2ETR/candles/1m").reply(&api).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!((&body["candles"][0]["open"], &body["candles"][0]["close"]), (&serde_json::json!("100"), &serde_json::json!("101")));
+        assert_eq!(body["event_time"], now);
+        assert!(body["received_at"].as_u64().is_some_and(|received_at| received_at >= now));
+
+        let grpc = MyTradeHistoryService::new(service);
+        let average = grpc.get_average_volume_per_trade(Request::new(WindowRequest { symbol: "E2ETR".to_string(), window_secs: 0 })).await.unwrap();
+        assert_eq!(average.into_inner().average_volume, "0.3750");
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_the_client() {
+        let server = MockBinanceServer::start(vec![
+            vec![MockFrame::trade("E2ESD", 1, "100", "1", SystemClock.now_millis())],
+        ]);
+        let services = test_services();
+        let service = services.trade_history.clone();
+        let shutdown = CancellationToken::new();
+        let client = tokio::spawn({
+            let ingestion = CombinedStream::new(&server.url(), &["E2ESD".to_string()])
+                .with_feed(Arc::new(TradeFeed));
+            let supervisor = test_supervisor(shutdown.clone());
+            let sink = services.clone();
+            async move { ingestion.run(sink.as_ref(), &supervisor).await }
+        });
+
+        eventually("the trade", || async {
+            service.history_len("E2ESD").await == Some(1)
+        }).await;
+        shutdown.cancel();
+
+        // The open connection is closed instead of waiting for the next message, and there is no reconnect
+        tokio::time::timeout(std::time::Duration::from_secs(5), client).await.unwrap().unwrap();
+        assert_eq!(server.connections(), 1);
     }
-}
\ No newline at end of file
+}
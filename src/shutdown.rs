@@ -0,0 +1,50 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio_util::sync::CancellationToken;
+
+// Whether the shutdown was caused by a failure rather than a signal
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+// Wait for Ctrl+C or SIGTERM (sent by docker stop and Kubernetes), then cancel `shutdown`, the token main hands to
+// the WebSocket clients, the servers and the streams
+pub async fn cancel_on_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+
+    log::info!("Shutdown requested, stopping...");
+    shutdown.cancel();
+}
+
+// Stop the application because of an unrecoverable failure, it exits with a non-zero code after the drain
+pub fn fail(shutdown: &CancellationToken) {
+    FAILED.store(true, Ordering::SeqCst);
+    shutdown.cancel();
+}
+
+// Exit code of the process once the shutdown completed
+pub fn exit_code() -> i32 {
+    if FAILED.load(Ordering::SeqCst) { 1 } else { 0 }
+}